@@ -6,6 +6,7 @@ import * as crypto from 'crypto';
 import { SyntheticPRProvider } from './syntheticPRProvider';
 import { WalkthroughWebviewProvider } from './walkthroughWebview';
 import { Bus } from './bus';
+import { WalkthroughDocument } from './walkthroughDocument';
 
 // TEST TEST TEST 
 
@@ -60,7 +61,8 @@ interface SyntheticPRPayload {
 }
 
 interface PresentWalkthroughPayload {
-    content: string;  // HTML content with resolved XML elements
+    document?: WalkthroughDocument;  // Walkthrough with resolved XML elements
+    content: string;  // The document rendered as HTML by the server
     base_uri: string;
 }
 
@@ -116,18 +118,30 @@ interface DiffLine {
     content: string;
 }
 
+interface UserResponse {
+    author: string;
+    content: string;
+    timestamp: string;
+}
+
 interface CommentThread {
-    id: string;
+    thread_id: string;
     file_path: string;
     line_number: number;
-    comment_type: 'insight' | 'question' | 'todo' | 'fixme';
+    /** Last line of a comment spanning several lines */
+    end_line_number?: number;
+    comment_type: 'insight' | 'question' | 'todo' | 'fixme' | 'comment';
     content: string;
+    author?: string;
+    responses: UserResponse[];
+    outdated?: boolean;
 }
 
 interface UserFeedback {
     feedback_type: 'comment' | 'complete_review';
     review_id: string;
     // For Comment variant
+    thread_id?: string;
     file_path?: string;
     line_number?: number;
     comment_text?: string;
@@ -273,8 +287,12 @@ export class DaemonClient implements vscode.Disposable {
                     this.walkthroughProvider.setBaseUri(walkthroughPayload.base_uri);
                 }
 
-                // Show walkthrough HTML content in webview
-                this.walkthroughProvider.showWalkthroughHtml(walkthroughPayload.content);
+                // Render the resolved document, or show the server's HTML from older servers
+                if (walkthroughPayload.document) {
+                    this.walkthroughProvider.showWalkthroughDocument(walkthroughPayload.document);
+                } else {
+                    this.walkthroughProvider.showWalkthroughHtml(walkthroughPayload.content);
+                }
 
                 // Send success response back through daemon
                 this.sendResponse(message.id, { success: true });
@@ -511,7 +529,7 @@ export class DaemonClient implements vscode.Disposable {
     /**
      * Handle comment feedback from diff view
      */
-    public handleCommentFeedback(comment: string, filePath: string, lineNumber: number): void {
+    public handleCommentFeedback(comment: string, filePath: string, lineNumber: number, threadId?: string): void {
         const reviewId = this.currentReviewId;
         if (!reviewId) {
             vscode.window.showErrorMessage('No active review found');
@@ -529,6 +547,7 @@ export class DaemonClient implements vscode.Disposable {
             feedback_type: 'comment',
             review_id: reviewId,
             comment_text: comment,
+            thread_id: threadId,
             file_path: filePath,
             line_number: lineNumber
         });
@@ -956,8 +975,8 @@ export function activate(context: vscode.ExtensionContext) {
     daemonClient.start();
 
     // Set up comment callback to send comments as feedback
-    syntheticPRProvider.setCommentCallback((comment: string, filePath: string, lineNumber: number) => {
-        daemonClient.handleCommentFeedback(comment, filePath, lineNumber);
+    syntheticPRProvider.setCommentCallback((comment: string, filePath: string, lineNumber: number, threadId?: string) => {
+        daemonClient.handleCommentFeedback(comment, filePath, lineNumber, threadId);
     });
 
     // 💡: Set up universal selection detection for interactive code review
@@ -34,12 +34,25 @@ interface DiffLine {
     content: string;
 }
 
+interface UserResponse {
+    author: string;
+    content: string;
+    timestamp: string;
+}
+
 interface CommentThread {
-    id: string;
+    thread_id: string;
     file_path: string;
     line_number: number;
-    comment_type: 'insight' | 'question' | 'todo' | 'fixme';
+    /** Last line of a comment spanning several lines */
+    end_line_number?: number;
+    comment_type: 'insight' | 'question' | 'todo' | 'fixme' | 'comment';
     content: string;
+    author?: string;
+    responses: UserResponse[];
+    outdated?: boolean;
+    /** How the reviewed changes affected an insight comment */
+    marker_status?: 'Introduced' | 'PreExisting' | 'Resolved' | null;
 }
 
 /**
@@ -68,7 +81,9 @@ export class SyntheticPRProvider implements vscode.Disposable {
     private treeProvider: SyntheticPRTreeProvider;
     private diffContentProvider: DialecticDiffContentProvider;
     private currentPR: SyntheticPRData | null = null;
-    private onCommentCallback?: (comment: string, filePath: string, lineNumber: number) => void;
+    private onCommentCallback?: (comment: string, filePath: string, lineNumber: number, threadId?: string) => void;
+    // Maps VSCode comment threads back to the review's thread IDs so replies land in the right thread
+    private threadIds = new WeakMap<vscode.CommentThread, string>();
 
     constructor(private context: vscode.ExtensionContext) {
         // Create diff content provider for virtual diff content
@@ -371,12 +386,16 @@ export class SyntheticPRProvider implements vscode.Disposable {
             if (!workspaceFolder) {
                 return;
             }
+            // Resolved markers are gone from the file; the tree view lists them
+            if (thread.marker_status === 'Resolved') {
+                return;
+            }
             const uri = vscode.Uri.joinPath(workspaceFolder.uri, thread.file_path);
             
-            // Create a simple range for the comment
+            // Cover every line of multi-line comments
             const range = new vscode.Range(
                 Math.max(0, thread.line_number - 1), 0,
-                Math.max(0, thread.line_number - 1), 0
+                Math.max(0, (thread.end_line_number ?? thread.line_number) - 1), 0
             );
             
             const commentThread = this.commentController.createCommentThread(uri, range, []);
@@ -385,17 +404,27 @@ export class SyntheticPRProvider implements vscode.Disposable {
             commentThread.collapsibleState = vscode.CommentThreadCollapsibleState.Expanded;
             commentThread.canReply = true;
 
-            // Create comment with AI insight
+            // Create comment with AI insight, followed by the conversation so far
             const comment: vscode.Comment = {
                 body: new vscode.MarkdownString(this.formatComment(thread)),
                 mode: vscode.CommentMode.Preview,
                 author: {
-                    name: 'AI Assistant'
+                    name: thread.author ?? 'AI Assistant'
                 }
             };
+            const responses: vscode.Comment[] = (thread.responses ?? []).map(response => ({
+                body: new vscode.MarkdownString(response.content),
+                mode: vscode.CommentMode.Preview,
+                author: {
+                    name: response.author
+                }
+            }));
             
-            commentThread.comments = [comment];
-            commentThread.label = `${this.getCommentIcon(thread.comment_type)} ${thread.comment_type.toUpperCase()}`;
+            commentThread.comments = [comment, ...responses];
+            this.threadIds.set(commentThread, thread.thread_id);
+            commentThread.label = `${this.getCommentIcon(thread.comment_type)} ${thread.comment_type.toUpperCase()}`
+                + (thread.marker_status === 'PreExisting' ? ' (pre-existing)' : '')
+                + (thread.outdated ? ' (outdated)' : '');
             
         } catch (error) {
             console.error(`Failed to create comment thread for ${thread.file_path}:${thread.line_number}`, error);
@@ -465,14 +494,14 @@ export class SyntheticPRProvider implements vscode.Disposable {
                 uri.path.replace('/diff/', '') : // Extract file path from diff URI
                 vscode.workspace.asRelativePath(uri);
             
-            this.onCommentCallback(reply.text, filePath, lineNumber);
+            this.onCommentCallback(reply.text, filePath, lineNumber, this.threadIds.get(reply.thread));
         }
     }
 
     /**
      * Set callback for when user submits a comment
      */
-    setCommentCallback(callback: (comment: string, filePath: string, lineNumber: number) => void): void {
+    setCommentCallback(callback: (comment: string, filePath: string, lineNumber: number, threadId?: string) => void): void {
         this.onCommentCallback = callback;
     }
 
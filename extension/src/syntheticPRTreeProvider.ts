@@ -6,30 +6,79 @@ interface SyntheticPRData {
     description: any;
     commit_range: string;
     files_changed: FileChange[];
+    /** Commits in the range, oldest first; uncommitted changes last with no sha */
+    commits?: ReviewCommit[];
+    rounds?: ReviewRound[];
     comment_threads: CommentThread[];
+    /** files_changed riskiest first, optionally grouped into sections */
+    file_ranking?: FileRanking;
     status: string;
 }
 
+interface FileRanking {
+    files: RankedFile[];
+    sections: FileSection[];
+}
+
+interface RankedFile {
+    path: string;
+    kind: 'Source' | 'Test' | 'Configuration' | 'Documentation' | 'Generated';
+    score: number;
+    reasons: string[];
+}
+
+interface FileSection {
+    title: string;
+    files: string[];
+}
+
+interface ReviewCommit {
+    sha: string | null;
+    author: string;
+    message: string;
+    timestamp: string;
+    files_changed: FileChange[];
+}
+
+interface ReviewRound {
+    number: number;
+    tree_oid: string;
+    created_at: string;
+    interdiff: FileChange[];
+}
+
 interface FileChange {
     path: string;
     additions: number;
     deletions: number;
     hunks: any[];
+    /** Changed tests exercising this source file, or sources this test exercises */
+    test_links?: TestLink[];
+}
+
+interface TestLink {
+    file: string;
+    symbols: string[];
 }
 
 interface CommentThread {
-    id: string;
+    thread_id: string;
     file_path: string;
     line_number: number;
-    comment_type: 'insight' | 'question' | 'todo' | 'fixme';
+    /** Last line of a comment spanning several lines */
+    end_line_number?: number;
+    comment_type: 'insight' | 'question' | 'todo' | 'fixme' | 'comment';
     content: string;
+    marker_status?: 'Introduced' | 'PreExisting' | 'Resolved' | null;
+    /** Commit in the review range that introduced this comment, if known */
+    commit_sha?: string | null;
 }
 
 class PRTreeItem extends vscode.TreeItem {
     constructor(
         public readonly label: string,
         public readonly collapsibleState: vscode.TreeItemCollapsibleState,
-        public readonly itemType: 'pr' | 'files' | 'file' | 'comments' | 'comment' | 'actions' | 'action' | 'placeholder',
+        public readonly itemType: 'pr' | 'files' | 'file-section' | 'file' | 'interdiff' | 'interdiff-file' | 'commits' | 'commit' | 'commit-file' | 'comments' | 'comment' | 'actions' | 'action' | 'placeholder',
         public readonly data?: any
     ) {
         super(label, collapsibleState);
@@ -121,13 +170,29 @@ export class SyntheticPRTreeProvider implements vscode.TreeDataProvider<PRTreeIt
         }
 
         if (element.itemType === 'pr') {
-            // PR children - Files, Comments, Actions
+            // PR children - Files, Changes since last round (if any), Commits, Comments, Actions
+            const latestRound = this.currentPR.rounds?.[this.currentPR.rounds.length - 1];
+            const interdiffItems = latestRound && latestRound.number > 1 ? [
+                new PRTreeItem(
+                    `Changes Since Round ${latestRound.number - 1} (${latestRound.interdiff.length})`,
+                    vscode.TreeItemCollapsibleState.Expanded,
+                    'interdiff',
+                    latestRound
+                )
+            ] : [];
+
             return Promise.resolve([
                 new PRTreeItem(
                     `Files Changed (${this.currentPR.files_changed.length})`,
                     vscode.TreeItemCollapsibleState.Expanded,
                     'files'
                 ),
+                ...interdiffItems,
+                new PRTreeItem(
+                    `Commits (${this.currentPR.commits?.length ?? 0})`,
+                    vscode.TreeItemCollapsibleState.Collapsed,
+                    'commits'
+                ),
                 new PRTreeItem(
                     `Comments (${this.currentPR.comment_threads.length})`,
                     this.commentsExpanded ? vscode.TreeItemCollapsibleState.Expanded : vscode.TreeItemCollapsibleState.Collapsed,
@@ -142,35 +207,98 @@ export class SyntheticPRTreeProvider implements vscode.TreeDataProvider<PRTreeIt
         }
 
         if (element.itemType === 'files') {
-            // Show individual files with comment indicators
+            // Show files riskiest first, in sections when the server grouped them
             if (!this.currentPR) return Promise.resolve([]);
-            
+
+            const sections = this.currentPR.file_ranking?.sections ?? [];
+            if (sections.length > 0) {
+                return Promise.resolve(
+                    sections.map(section =>
+                        new PRTreeItem(
+                            `${section.title} (${section.files.length})`,
+                            vscode.TreeItemCollapsibleState.Expanded,
+                            'file-section',
+                            section
+                        )
+                    )
+                );
+            }
+
+            const ranked = this.currentPR.file_ranking?.files.map(file => file.path)
+                ?? this.currentPR.files_changed.map(file => file.path);
+            return Promise.resolve(this.fileItems(ranked));
+        }
+
+        if (element.itemType === 'file-section') {
+            const section: FileSection = element.data;
+            return Promise.resolve(this.fileItems(section.files));
+        }
+
+        if (element.itemType === 'interdiff') {
+            // Files touched since the reviewer last looked
+            const round: ReviewRound = element.data;
             return Promise.resolve(
-                this.currentPR.files_changed.map(file => {
-                    const commentsInFile = this.currentPR!.comment_threads.filter(c => c.file_path === file.path);
-                    const commentIndicator = commentsInFile.length > 0 ? ` 💬${commentsInFile.length}` : '';
-                    
-                    return new PRTreeItem(
-                        `${file.path} (+${file.additions} -${file.deletions})${commentIndicator}`,
+                round.interdiff.map(file =>
+                    new PRTreeItem(
+                        `${file.path} (+${file.additions} -${file.deletions})`,
                         vscode.TreeItemCollapsibleState.None,
-                        'file',
+                        'interdiff-file',
                         file
+                    )
+                )
+            );
+        }
+
+        if (element.itemType === 'commits') {
+            // Show the range commit by commit, oldest first
+            return Promise.resolve(
+                (this.currentPR.commits ?? []).map(commit => {
+                    const subject = commit.message.split('\n')[0];
+                    const item = new PRTreeItem(
+                        commit.sha ? `${commit.sha.slice(0, 7)} ${subject}` : 'Uncommitted changes',
+                        vscode.TreeItemCollapsibleState.Collapsed,
+                        'commit',
+                        commit
                     );
+                    item.description = commit.author;
+                    item.tooltip = `${commit.sha ?? 'Working tree'}\n${commit.author}\n${commit.timestamp}\n\n${commit.message}`;
+                    return item;
                 })
             );
         }
 
+        if (element.itemType === 'commit') {
+            // Files this commit changed, relative to its first parent
+            const commit: ReviewCommit = element.data;
+            return Promise.resolve(
+                commit.files_changed.map(file =>
+                    new PRTreeItem(
+                        `${file.path} (+${file.additions} -${file.deletions})`,
+                        vscode.TreeItemCollapsibleState.None,
+                        'commit-file',
+                        file
+                    )
+                )
+            );
+        }
+
         if (element.itemType === 'comments') {
             // Show individual comments
             return Promise.resolve(
-                this.currentPR.comment_threads.map(comment => 
-                    new PRTreeItem(
-                        `${this.getCommentIcon(comment.comment_type)} ${comment.file_path}:${comment.line_number}`,
+                this.currentPR.comment_threads.map(comment => {
+                    const item = new PRTreeItem(
+                        `${this.getCommentIcon(comment.comment_type)} ${comment.file_path}:${comment.line_number}${comment.end_line_number ? `-${comment.end_line_number}` : ''}`
+                            + (comment.marker_status === 'Resolved' ? ' (resolved)' : comment.marker_status === 'PreExisting' ? ' (pre-existing)' : ''),
                         vscode.TreeItemCollapsibleState.None,
                         'comment',
                         comment
-                    )
-                )
+                    );
+                    // Introducing commit, as it appears under Commits
+                    if (comment.commit_sha) {
+                        item.description = comment.commit_sha.slice(0, 7);
+                    }
+                    return item;
+                })
             );
         }
 
@@ -201,6 +329,44 @@ export class SyntheticPRTreeProvider implements vscode.TreeDataProvider<PRTreeIt
         return Promise.resolve([]);
     }
 
+    /** Tree items for changed files, in the given order, with comment and test indicators, ranking reasons and test links */
+    private fileItems(paths: string[]): PRTreeItem[] {
+        if (!this.currentPR) return [];
+
+        return paths.flatMap(path => {
+            const file = this.currentPR!.files_changed.find(f => f.path === path);
+            if (!file) return [];
+
+            const commentsInFile = this.currentPR!.comment_threads.filter(c => c.file_path === file.path);
+            const commentIndicator = commentsInFile.length > 0 ? ` 💬${commentsInFile.length}` : '';
+
+            const testLinks = file.test_links ?? [];
+            const testIndicator = testLinks.length > 0 ? ` 🧪${testLinks.length}` : '';
+
+            const item = new PRTreeItem(
+                `${file.path} (+${file.additions} -${file.deletions})${commentIndicator}${testIndicator}`,
+                vscode.TreeItemCollapsibleState.None,
+                'file',
+                file
+            );
+            const tooltip: string[] = [];
+            const ranked = this.currentPR!.file_ranking?.files.find(f => f.path === path);
+            if (ranked) {
+                tooltip.push(`Risk score ${ranked.score}`, ...ranked.reasons.map(reason => `• ${reason}`));
+            }
+            if (testLinks.length > 0) {
+                const heading = ranked?.kind === 'Test' ? 'Sources this test exercises:' : 'Tests touching this file:';
+                tooltip.push(heading, ...testLinks.map(link =>
+                    `• ${link.file}${link.symbols.length > 0 ? ` (${link.symbols.join(', ')})` : ''}`
+                ));
+            }
+            if (tooltip.length > 0) {
+                item.tooltip = tooltip.join('\n');
+            }
+            return [item];
+        });
+    }
+
     private getCommentIcon(type: string): string {
         switch (type) {
             case 'insight': return '💡';
@@ -0,0 +1,136 @@
+import type { FileChange } from './walkthroughWebview';
+
+// Resolved walkthrough sent by the MCP server - mirrors server/src/walkthrough_document.rs
+export interface WalkthroughDocument {
+    sections: WalkthroughSection[];
+}
+
+export interface WalkthroughSection {
+    heading?: { level: number; text: string };
+    nodes: WalkthroughNode[];
+}
+
+export type WalkthroughNode =
+    | { type: 'markdown'; markdown: string }
+    | ({ type: 'comment' } & CommentNode)
+    | ({ type: 'gitdiff' } & GitDiffNode)
+    | { type: 'action'; button: string; message: string }
+    | { type: 'mermaid'; diagram: string };
+
+export interface CommentNode {
+    id: string;
+    icon?: string;
+    content: string;  // Markdown
+    dialect_expression: string;
+    locations: any[];  // Results of the Dialect expression
+    error?: string;
+}
+
+export interface GitDiffNode {
+    range: string;
+    files: FileChange[];
+    error?: string;
+}
+
+// A Map, so icon names like "constructor" do not pick up object properties
+const COMMENT_ICONS = new Map<string, string>([
+    ['info', 'ℹ️'],
+    ['lightbulb', '💡'],
+    ['gear', '⚙️'],
+    ['warning', '⚠️'],
+    ['question', '❓'],
+]);
+
+/**
+ * Escape text for use in HTML content and quoted attribute values
+ */
+export function escapeHtml(text: string): string {
+    return text
+        .replace(/&/g, '&amp;')
+        .replace(/</g, '&lt;')
+        .replace(/>/g, '&gt;')
+        .replace(/"/g, '&quot;')
+        .replace(/'/g, '&#39;');
+}
+
+/**
+ * Render a walkthrough document as HTML for the walkthrough webview
+ * @param renderMarkdown renders markdown nodes and comment content
+ */
+export function renderWalkthroughDocument(document: WalkthroughDocument, renderMarkdown: (markdown: string) => string): string {
+    let html = '';
+    for (const section of document.sections) {
+        if (section.heading) {
+            const level = Math.min(Math.max(section.heading.level, 1), 6);
+            html += `<h${level}>${escapeHtml(section.heading.text)}</h${level}>`;
+        }
+        for (const node of section.nodes) {
+            html += renderNode(node, renderMarkdown);
+        }
+    }
+    return html || '<div class="empty-state">Empty walkthrough</div>';
+}
+
+/**
+ * All files changed in the document's git diffs
+ */
+export function gitDiffFiles(document: WalkthroughDocument): FileChange[] {
+    return document.sections
+        .flatMap(section => section.nodes)
+        .flatMap(node => node.type === 'gitdiff' ? node.files : []);
+}
+
+function renderNode(node: WalkthroughNode, renderMarkdown: (markdown: string) => string): string {
+    switch (node.type) {
+        case 'markdown':
+            return renderMarkdown(node.markdown);
+        case 'comment': {
+            // Same shape the click handler already parses
+            const commentData = { id: node.id, locations: node.locations, comment: [node.content] };
+            const icon = COMMENT_ICONS.get(node.icon || '') ?? '💬';
+            return '<div class="content-item">'
+                + `<div class="comment-item" data-comment="${encodeURIComponent(JSON.stringify(commentData))}">`
+                + `<div class="comment-icon">${icon}</div>`
+                + '<div class="comment-content">'
+                + `<div class="comment-locations"><span class="comment-location">${escapeHtml(locationDisplay(node))}</span></div>`
+                + `<div class="comment-text">${renderMarkdown(node.content)}</div>`
+                + '</div></div></div>';
+        }
+        case 'gitdiff': {
+            let html = '<div class="content-item"><div class="gitdiff-container">';
+            if (node.error) {
+                html += `<div class="gitdiff-error">${escapeHtml(node.range)}: ${escapeHtml(node.error)}</div>`;
+            }
+            for (const file of node.files) {
+                html += '<div class="file-diff"><div class="file-header">'
+                    + `<span class="file-path clickable-file" data-file-path="${escapeHtml(file.path)}">${escapeHtml(file.path)}</span>`
+                    + `<span class="file-stats">+${file.additions} -${file.deletions}</span>`
+                    + '</div></div>';
+            }
+            return html + '</div></div>';
+        }
+        case 'action':
+            return '<div class="content-item">'
+                + `<button class="action-button" data-tell-agent="${escapeHtml(node.message)}">${escapeHtml(node.button)}</button>`
+                + '</div>';
+        case 'mermaid':
+            // Rendered client-side from the element's text
+            return `<mermaid>${escapeHtml(node.diagram)}</mermaid>`;
+    }
+}
+
+function locationDisplay(comment: CommentNode): string {
+    // Symbols found by findDefinitions are located where they are defined
+    const locations = comment.locations.map(loc => loc?.definedAt ?? loc);
+    if (locations.length === 0) {
+        return comment.error ? 'unresolved location' : 'no location';
+    }
+    if (locations.length === 1) {
+        return `${locations[0]?.path ?? 'unknown'}:${locations[0]?.start?.line ?? 1}`;
+    }
+    const firstFile = locations[0]?.path;
+    if (locations.every(loc => loc?.path === firstFile)) {
+        return `${firstFile} 🔍`;
+    }
+    return `(${locations.length} possible locations) 🔍`;
+}
@@ -4,6 +4,7 @@ import * as path from 'path';
 import * as MarkdownIt from 'markdown-it';
 import { openDialecticUrl } from './fileNavigation';
 import { Bus } from './bus';
+import { WalkthroughDocument, gitDiffFiles, renderWalkthroughDocument } from './walkthroughDocument';
 
 // Placement state for unified link and comment management
 interface PlacementState {
@@ -13,7 +14,7 @@ interface PlacementState {
 }
 
 // Reuse types from synthetic PR system
-interface FileChange {
+export interface FileChange {
     path: string;
     status: string;
     additions: number;
@@ -72,6 +73,7 @@ export class WalkthroughWebviewProvider implements vscode.WebviewViewProvider {
     private baseUri?: vscode.Uri;
     private diffContentProvider: WalkthroughDiffContentProvider;
     private currentWalkthrough?: WalkthroughData;
+    private currentDocument?: WalkthroughDocument;
     private offscreenHtmlContent?: string;
     private placementMemory = new Map<string, PlacementState>(); // Unified placement memory
     private commentController?: vscode.CommentController;
@@ -382,9 +384,18 @@ export class WalkthroughWebviewProvider implements vscode.WebviewViewProvider {
      * Get set of files that appear in gitdiff sections of current walkthrough
      */
     private getFilesInCurrentGitDiff(): Set<string> {
-        const filesInDiff = new Set<string>();
+        return new Set(this.currentFileChanges().map(fileChange => fileChange.path));
+    }
 
-        if (!this.currentWalkthrough) return filesInDiff;
+    /**
+     * File changes of all gitdiff elements in the current walkthrough
+     */
+    private currentFileChanges(): FileChange[] {
+        if (this.currentDocument) {
+            return gitDiffFiles(this.currentDocument);
+        }
+
+        if (!this.currentWalkthrough) return [];
 
         const allSections = [
             ...(this.currentWalkthrough.introduction || []),
@@ -393,16 +404,15 @@ export class WalkthroughWebviewProvider implements vscode.WebviewViewProvider {
             ...(this.currentWalkthrough.actions || [])
         ];
 
+        const fileChanges: FileChange[] = [];
         for (const item of allSections) {
             if (typeof item === 'object' && 'files' in item) {
                 // This is a GitDiffElement
-                item.files.forEach((fileChange: FileChange) => {
-                    filesInDiff.add(fileChange.path);
-                });
+                fileChanges.push(...item.files);
             }
         }
 
-        return filesInDiff;
+        return fileChanges;
     }
 
     /**
@@ -536,30 +546,14 @@ export class WalkthroughWebviewProvider implements vscode.WebviewViewProvider {
     private async showFileDiff(filePath: string): Promise<void> {
         console.log(`[WALKTHROUGH DIFF] Starting showFileDiff for: ${filePath}`);
 
-        if (!this.currentWalkthrough) {
+        if (!this.currentWalkthrough && !this.currentDocument) {
             console.log('[WALKTHROUGH DIFF] ERROR: No current walkthrough data');
             vscode.window.showErrorMessage('No walkthrough data available');
             return;
         }
 
-        // Find the file change in the walkthrough data
-        let fileChange: FileChange | undefined;
-
-        // Search through all sections for gitdiff elements
-        const allSections = [
-            ...(this.currentWalkthrough.introduction || []),
-            ...(this.currentWalkthrough.highlights || []),
-            ...(this.currentWalkthrough.changes || []),
-            ...(this.currentWalkthrough.actions || [])
-        ];
-
-        for (const item of allSections) {
-            if (typeof item === 'object' && 'files' in item) {
-                // This is a GitDiffElement named field - {"files": FileChange[]}
-                fileChange = item.files.find((fc: FileChange) => fc.path === filePath);
-                if (fileChange) break;
-            }
-        }
+        // Find the file change in the walkthrough's gitdiff elements
+        const fileChange = this.currentFileChanges().find(fc => fc.path === filePath);
 
         if (!fileChange) {
             console.log(`[WALKTHROUGH DIFF] ERROR: File not found in walkthrough: ${filePath}`);
@@ -745,11 +739,29 @@ export class WalkthroughWebviewProvider implements vscode.WebviewViewProvider {
         }
     }
 
+    /**
+     * Show a walkthrough resolved by the MCP server, rendering its markdown like other walkthroughs
+     */
+    public showWalkthroughDocument(document: WalkthroughDocument) {
+        this.bus.outputChannel.appendLine(`[WALKTHROUGH] showWalkthroughDocument called with ${document.sections.length} sections`);
+
+        // Keep the document for diff functionality
+        this.currentDocument = document;
+        this.currentWalkthrough = undefined;
+
+        this.clearPlacementMemory();
+        this.clearAllComments();
+
+        const html = renderWalkthroughDocument(document, markdown => this.sanitizeHtml(this.md.render(markdown)));
+        this.showWalkthroughHtml(html);
+    }
+
     public showWalkthrough(walkthrough: WalkthroughData) {
         console.log('WalkthroughWebviewProvider.showWalkthrough called with:', walkthrough);
 
         // Store walkthrough data for diff functionality
         this.currentWalkthrough = walkthrough;
+        this.currentDocument = undefined;
 
         // Clear placement memory for new walkthrough
         this.clearPlacementMemory();
@@ -0,0 +1,519 @@
+//! Escaping and sanitizing of HTML shown in the walkthrough webview.
+//!
+//! Walkthrough content comes from the agent. Everything interpolated into HTML is escaped,
+//! and HTML the agent writes itself is reduced to an allowlist of tags and attributes; other
+//! tags are escaped, so the webview shows them as text instead of running them.
+
+use pulldown_cmark::{Event, Parser, Tag, html};
+
+// ANCHOR: allowed_html
+/// Tags agent-written HTML may use
+pub const ALLOWED_TAGS: &[&str] = &[
+    "a",
+    "abbr",
+    "b",
+    "blockquote",
+    "br",
+    "code",
+    "dd",
+    "del",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "ins",
+    "kbd",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "q",
+    "s",
+    "span",
+    "strong",
+    "sub",
+    "summary",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "u",
+    "ul",
+];
+
+/// Attributes agent-written HTML may use on allowed tags
+pub const ALLOWED_ATTRIBUTES: &[&str] = &[
+    "align", "alt", "colspan", "height", "href", "open", "rowspan", "src", "start", "title",
+    "width",
+];
+
+/// Schemes URLs may use; URLs without a scheme are relative and always allowed
+pub const ALLOWED_SCHEMES: &[&str] = &["dialectic", "http", "https", "mailto"];
+// ANCHOR_END: allowed_html
+
+/// Attributes holding URLs, which must also use an allowed scheme
+const URL_ATTRIBUTES: &[&str] = &["href", "src"];
+
+/// Escape text for HTML content and for quoted attribute values.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Whether a link or image URL is relative or uses an allowed scheme.
+pub fn is_safe_url(url: &str) -> bool {
+    // Browsers ignore whitespace and control characters in schemes ("java\tscript:")
+    let normalized: String = url
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    match normalized.find([':', '/', '?', '#']) {
+        Some(end) if normalized[end..].starts_with(':') => {
+            ALLOWED_SCHEMES.contains(&&normalized[..end])
+        }
+        _ => true,
+    }
+}
+
+/// Reduce agent-written HTML to the allowlist.
+///
+/// Allowed tags keep their allowed attributes, re-quoted and escaped; URL attributes with
+/// other schemes are dropped. Other tags, and `<` that do not start a tag, are escaped.
+/// HTML comments are removed.
+pub fn sanitize_html(html: &str) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        output.push_str(&escape_text(&rest[..start]));
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--")
+            && let Some(end) = comment.find("-->")
+        {
+            rest = &comment[end + 3..];
+            continue;
+        }
+
+        match HtmlTag::parse(rest) {
+            Some((tag, len)) => {
+                match tag.sanitized() {
+                    Some(sanitized) => output.push_str(&sanitized),
+                    None => output.push_str(&escape_html(&rest[..len])),
+                }
+                rest = &rest[len..];
+            }
+            None => {
+                output.push_str("&lt;");
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(&escape_text(rest));
+    output
+}
+
+/// Sanitize the HTML written in markdown, leaving the rest of the markdown as it is.
+pub fn sanitize_markdown(markdown: &str) -> String {
+    let mut output = String::with_capacity(markdown.len());
+    let mut copied = 0;
+    for (event, range) in Parser::new(markdown).into_offset_iter() {
+        // An HTML block covers the whole block, so tags split across lines stay whole
+        let is_html = matches!(event, Event::Start(Tag::HtmlBlock) | Event::InlineHtml(_));
+        if is_html && range.start >= copied {
+            output.push_str(&markdown[copied..range.start]);
+            output.push_str(&sanitize_html(&markdown[range.clone()]));
+            copied = range.end;
+        }
+    }
+    output.push_str(&markdown[copied..]);
+    output
+}
+
+/// Render markdown to HTML, sanitizing the HTML written in it and dropping link and image
+/// URLs with other schemes.
+pub fn render_markdown(markdown: &str) -> String {
+    let events = Parser::new(markdown).map(|event| match event {
+        Event::Html(html) => Event::Html(sanitize_html(&html).into()),
+        Event::InlineHtml(html) => Event::InlineHtml(sanitize_html(&html).into()),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if !is_safe_url(&dest_url) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: "".into(),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if !is_safe_url(&dest_url) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: "".into(),
+            title,
+            id,
+        }),
+        event => event,
+    });
+    let mut output = String::new();
+    html::push_html(&mut output, events);
+    output
+}
+
+/// Escape the characters that could start or end tags in text between tags, keeping
+/// entities like `&nbsp;` working.
+fn escape_text(text: &str) -> String {
+    text.replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// A start or end tag of agent-written HTML.
+struct HtmlTag<'a> {
+    name: String,
+    closing: bool,
+    self_closing: bool,
+    attributes: Vec<(String, &'a str)>,
+}
+
+impl<'a> HtmlTag<'a> {
+    /// Parse the tag at the start of `html`, returning it and its length.
+    fn parse(html: &'a str) -> Option<(Self, usize)> {
+        let mut pos = 1;
+        let closing = html[pos..].starts_with('/');
+        if closing {
+            pos += 1;
+        }
+
+        let name_len = html[pos..]
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(html.len() - pos);
+        if name_len == 0 || !html[pos..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        let name = html[pos..pos + name_len].to_ascii_lowercase();
+        pos += name_len;
+
+        let mut attributes = vec![];
+        loop {
+            pos += whitespace_len(&html[pos..]);
+            let rest = &html[pos..];
+            if rest.starts_with('>') {
+                return Some((
+                    HtmlTag {
+                        name,
+                        closing,
+                        self_closing: false,
+                        attributes,
+                    },
+                    pos + 1,
+                ));
+            }
+            if rest.starts_with("/>") {
+                return Some((
+                    HtmlTag {
+                        name,
+                        closing,
+                        self_closing: true,
+                        attributes,
+                    },
+                    pos + 2,
+                ));
+            }
+
+            let attribute_len = rest
+                .find(|c: char| c.is_whitespace() || matches!(c, '=' | '>' | '/' | '"' | '\''))
+                .unwrap_or(rest.len());
+            if attribute_len == 0 {
+                return None;
+            }
+            let attribute = rest[..attribute_len].to_ascii_lowercase();
+            pos += attribute_len;
+
+            let after_name = whitespace_len(&html[pos..]);
+            if !html[pos + after_name..].starts_with('=') {
+                attributes.push((attribute, ""));
+                continue;
+            }
+            pos += after_name + 1;
+            pos += whitespace_len(&html[pos..]);
+
+            let rest = &html[pos..];
+            let value = match rest.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let end = rest[1..].find(quote)?;
+                    pos += end + 2;
+                    &rest[1..end + 1]
+                }
+                Some(_) => {
+                    let end = rest
+                        .find(|c: char| c.is_whitespace() || c == '>')
+                        .unwrap_or(rest.len());
+                    pos += end;
+                    &rest[..end]
+                }
+                None => return None,
+            };
+            attributes.push((attribute, value));
+        }
+    }
+
+    /// The tag with only allowed attributes, or `None` if the tag is not allowed.
+    fn sanitized(&self) -> Option<String> {
+        if !ALLOWED_TAGS.contains(&self.name.as_str()) {
+            return None;
+        }
+        if self.closing {
+            return Some(format!("</{}>", self.name));
+        }
+
+        let mut tag = format!("<{}", self.name);
+        for (attribute, value) in &self.attributes {
+            if !ALLOWED_ATTRIBUTES.contains(&attribute.as_str())
+                || (URL_ATTRIBUTES.contains(&attribute.as_str()) && !is_safe_url(value))
+            {
+                continue;
+            }
+            tag.push_str(&format!(" {}=\"{}\"", attribute, escape_html(value)));
+        }
+        tag.push_str(if self.self_closing { " />" } else { ">" });
+        Some(tag)
+    }
+}
+
+fn whitespace_len(text: &str) -> usize {
+    text.find(|c: char| !c.is_whitespace())
+        .unwrap_or(text.len())
+}
+
+/// Panic unless every tag in `html` is well-formed, allowed, and has only allowed,
+/// double-quoted and escaped attributes with safe URLs.
+///
+/// `extra_tags` and `extra_attributes` are those the renderer itself writes.
+#[cfg(test)]
+pub(crate) fn assert_safe_html(html: &str, extra_tags: &[&str], extra_attributes: &[&str]) {
+    use regex::Regex;
+    use std::sync::LazyLock;
+
+    static TAG: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r#"^<(/?)([a-z][a-z0-9]*)((?: [a-z][a-z-]*="[^"<>]*")*)( /)?>"#).unwrap()
+    });
+    static ATTRIBUTE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r#" ([a-z][a-z-]*)="([^"<>]*)""#).unwrap());
+
+    for (start, _) in html.match_indices('<') {
+        let Some(tag) = TAG.captures(&html[start..]) else {
+            panic!("malformed tag at {start} in:\n{html}");
+        };
+        let name = &tag[2];
+        assert!(
+            ALLOWED_TAGS.contains(&name) || extra_tags.contains(&name),
+            "tag <{name}> in:\n{html}"
+        );
+        for attribute in ATTRIBUTE.captures_iter(&tag[3]) {
+            let (attribute, value) = (&attribute[1], &attribute[2]);
+            assert!(
+                ALLOWED_ATTRIBUTES.contains(&attribute) || extra_attributes.contains(&attribute),
+                "attribute {attribute} in:\n{html}"
+            );
+            assert!(
+                !value.contains('\''),
+                "unescaped ' in {attribute} in:\n{html}"
+            );
+            if URL_ATTRIBUTES.contains(&attribute) {
+                assert!(
+                    is_safe_url(&decode_entities(value)),
+                    "unsafe {attribute} in:\n{html}"
+                );
+            }
+        }
+    }
+}
+
+/// Decode the entities a browser would decode in an attribute value.
+#[cfg(test)]
+fn decode_entities(value: &str) -> String {
+    use regex::Regex;
+    use std::sync::LazyLock;
+
+    static ENTITY: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"&(#[xX][0-9a-fA-F]+|#[0-9]+|[a-zA-Z]+);?").unwrap());
+    ENTITY
+        .replace_all(value, |captures: &regex::Captures| {
+            let name = &captures[1];
+            let decoded = match name.strip_prefix('#') {
+                Some(hex) if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16).ok(),
+                Some(decimal) => decimal.parse().ok(),
+                None => match name {
+                    "amp" => Some('&' as u32),
+                    "colon" => Some(':' as u32),
+                    "lt" => Some('<' as u32),
+                    "gt" => Some('>' as u32),
+                    "quot" => Some('"' as u32),
+                    "Tab" => Some('\t' as u32),
+                    _ => None,
+                },
+            };
+            decoded
+                .and_then(char::from_u32)
+                .map(String::from)
+                .unwrap_or_else(|| captures[0].to_string())
+        })
+        .into_owned()
+}
+
+/// Random walkthrough-like text, built from fragments that are hard to escape.
+#[cfg(test)]
+pub(crate) fn arbitrary_text(rng: &mut fastrand::Rng) -> String {
+    const FRAGMENTS: &[&str] = &[
+        "<",
+        ">",
+        "\"",
+        "'",
+        "&",
+        "=",
+        " ",
+        "/",
+        "\n",
+        "\n\n",
+        "x",
+        "# ",
+        "* ",
+        "`",
+        "**",
+        "javascript:",
+        "JaVa\tScRiPt:",
+        "java&#58;script:",
+        "&#x6A;avascript:",
+        "&quot;",
+        "&#39;",
+        "onerror=",
+        "onclick=\"alert(1)\"",
+        "<script>",
+        "</script>",
+        "<style>",
+        "<img src=x onerror=alert(1)>",
+        "<svg/onload=alert(1)>",
+        "<iframe src='",
+        "<a href=\"",
+        "<a href=javascript:alert(1)>",
+        "</a>",
+        "<b>",
+        "</b>",
+        "<div title='",
+        "<!--",
+        "-->",
+        "[link](",
+        "](javascript:alert(1))",
+        "<javascript:alert(1)>",
+        ")",
+        "<comment",
+        "<comment location=\"",
+        "<comment location=\"findDefinitions(`User`)\"",
+        " icon=\"",
+        "</comment>",
+        "<action button=\"",
+        "</action>",
+        "<gitdiff range=\"",
+        " />",
+        "<mermaid>",
+        "</mermaid>",
+        "\">",
+        "'>",
+        "\" onmouseover=\"alert(1)",
+    ];
+    (0..rng.usize(1..40))
+        .map(|_| FRAGMENTS[rng.usize(..FRAGMENTS.len())])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn test_sanitize_html() {
+        let sanitized = sanitize_html(
+            r#"<b onclick="alert(1)">bold</b> <script>alert("x")</script>
+<a href="javascript:alert(1)" title='a "title"'>link</a> <a href=https://example.com>ok</a>
+<img src=x onerror=alert(1) alt=pic /> <!-- note --> 1 < 2"#,
+        );
+        expect![[r#"
+            <b>bold</b> &lt;script&gt;alert("x")&lt;/script&gt;
+            <a title="a &quot;title&quot;">link</a> <a href="https://example.com">ok</a>
+            <img src="x" alt="pic" />  1 &lt; 2"#]]
+        .assert_eq(&sanitized);
+    }
+
+    #[test]
+    fn test_sanitize_markdown_keeps_markdown() {
+        let sanitized = sanitize_markdown(
+            "# Title\n\nSome `<script>` code and <span title=\"t\" style=\"x\">html</span>.\n\n<div\n  onclick=\"alert(1)\">\nblock\n</div>\n",
+        );
+        expect![[r#"
+            # Title
+
+            Some `<script>` code and <span title="t">html</span>.
+
+            <div>
+            block
+            </div>
+        "#]]
+        .assert_eq(&sanitized);
+    }
+
+    #[test]
+    fn test_is_safe_url() {
+        assert!(is_safe_url("src/main.rs#L10"));
+        assert!(is_safe_url("dialectic:src/main.rs?line=10"));
+        assert!(is_safe_url("https://example.com/a:b"));
+        assert!(!is_safe_url("javascript:alert(1)"));
+        assert!(!is_safe_url(" JaVa\tScRiPt:alert(1)"));
+        assert!(!is_safe_url("data:text/html,<script>"));
+    }
+
+    #[test]
+    fn test_arbitrary_html_is_sanitized() {
+        let mut rng = fastrand::Rng::with_seed(0x5eed);
+        for _ in 0..2000 {
+            let input = arbitrary_text(&mut rng);
+            assert_safe_html(&sanitize_html(&input), &[], &[]);
+
+            // Rendering markdown only adds the tags markdown produces
+            assert_safe_html(&render_markdown(&input), &[], &["class"]);
+            assert_safe_html(
+                &render_markdown(&sanitize_markdown(&input)),
+                &[],
+                &["class"],
+            );
+        }
+    }
+}
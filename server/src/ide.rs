@@ -301,6 +301,8 @@ impl<U: IpcClient> DialectFunction<U> for Search {
 /// - `{"gitdiff": {"commit_range": "HEAD^.."}}` - Changes in last commit
 /// - `{"gitdiff": {"commit_range": "HEAD~3..HEAD~1"}}` - Changes between specific commits  
 /// - `{"gitdiff": {"commit_range": "HEAD", "exclude_unstaged": true}}` - Only staged changes
+/// - `{"gitdiff": {"commit_range": "HEAD", "paths": ["server/src/"], "include_untracked": false}}` - Focused diff
+/// - `{"gitdiff": {"commit_range": "HEAD~1..HEAD", "context_lines": 10, "ignore_whitespace": "all"}}` - More context, no whitespace noise
 #[derive(Deserialize)]
 pub struct GitDiff {
     pub commit_range: String,
@@ -310,13 +312,24 @@ pub struct GitDiff {
 
     #[expect(dead_code)]
     pub exclude_staged: Option<bool>,
+
+    /// Context lines, whitespace handling, untracked files and pathspecs
+    #[serde(flatten)]
+    pub settings: crate::synthetic_pr::DiffSettings,
 }
 
 impl<U: IpcClient> DialectFunction<U> for GitDiff {
     type Output = GitDiffElement;
 
-    const PARAMETER_ORDER: &'static [&'static str] =
-        &["commit_range", "exclude_unstaged", "exclude_staged"];
+    const PARAMETER_ORDER: &'static [&'static str] = &[
+        "commit_range",
+        "exclude_unstaged",
+        "exclude_staged",
+        "context_lines",
+        "ignore_whitespace",
+        "include_untracked",
+        "paths",
+    ];
 
     async fn execute(
         self,
@@ -327,7 +340,8 @@ impl<U: IpcClient> DialectFunction<U> for GitDiff {
         // Use current directory as repo path (could be made configurable)
         let git_service = GitService::new(".")?;
         let (base_oid, head_oid) = git_service.parse_commit_range(&self.commit_range)?;
-        let file_changes = git_service.generate_diff(base_oid, head_oid)?;
+        let file_changes =
+            git_service.generate_diff_with_settings(base_oid, head_oid, &self.settings)?;
 
         // TODO: Apply exclude filters for staged/unstaged changes
         // For now, return all changes wrapped in GitDiffElement
@@ -516,10 +530,13 @@ impl<U: IpcClient> DialectFunction<U> for Action {
 
 /// Resolved walkthrough output from the `present_walkthrough` MCP tool.
 ///
-/// Contains HTML content with resolved XML elements and Dialect expressions.
+/// Contains the walkthrough resolved into a document, with its XML elements and Dialect
+/// expressions resolved, and the same document rendered as HTML.
 #[derive(Serialize, Debug)]
 pub struct ResolvedWalkthrough {
-    /// HTML content with resolved XML elements (comment, gitdiff, action, mermaid)
+    /// Resolved walkthrough, for the extension to render
+    pub document: crate::walkthrough_document::WalkthroughDocument,
+    /// The document rendered by its HTML backend, for extensions that predate `document`
     pub content: String,
     /// Base directory path for resolving relative file references
     pub base_uri: String,
@@ -560,6 +560,12 @@ async fn test_gitdiff_function() {
                             "old_start": Number(1),
                         },
                     ],
+                    "index": Object {
+                        "new_mode": Number(33188),
+                        "new_oid": String("0672e510676c59767b228f57ea04b864ebb3d33f"),
+                        "old_mode": Number(33188),
+                        "old_oid": String("244f744c2500ef2c65c00ae0ba7c13131d704820"),
+                    },
                     "path": String("src/main.rs"),
                     "status": String("Modified"),
                 },
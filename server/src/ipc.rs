@@ -3,7 +3,7 @@
 //! Handles Unix socket/named pipe communication with the VSCode extension.
 //! Ports the logic from server/src/ipc.ts to Rust with cross-platform support.
 
-use crate::synthetic_pr::UserFeedback;
+use crate::synthetic_pr::{FeedbackQueue, FeedbackTimeouts, UserFeedback};
 use crate::types::{
     FindAllReferencesPayload, GetSelectionResult, GoodbyePayload, IPCMessage, IPCMessageType,
     LogLevel, LogParams, PoloPayload, ResolveSymbolByNamePayload,
@@ -46,7 +46,7 @@ pub enum IPCError {
     #[error("Failed to write to IPC connection: {0}")]
     WriteError(#[from] std::io::Error),
 
-    #[error("Request timeout after 5 seconds")]
+    #[error("Request timed out")]
     Timeout,
 
     #[error("Response channel closed")]
@@ -84,6 +84,12 @@ struct IPCCommunicatorInner {
     /// Key: review_id, Value: channel to send UserFeedback back to caller
     pending_feedback: HashMap<String, oneshot::Sender<crate::synthetic_pr::UserFeedback>>,
 
+    /// User feedback on reviews shown in poll mode, waiting for `poll_review_feedback`
+    feedback_queue: FeedbackQueue,
+
+    /// How long to wait on the user, and the longest a poll may wait
+    timeouts: FeedbackTimeouts,
+
     /// Flag to track if we have an active connection and reader task
     /// When true, ensure_connection() is a no-op
     connected: bool,
@@ -106,6 +112,8 @@ impl IPCCommunicator {
                 write_half: None,
                 pending_requests: HashMap::new(),
                 pending_feedback: HashMap::new(),
+                feedback_queue: FeedbackQueue::new(),
+                timeouts: FeedbackTimeouts::from_env(|name| std::env::var(name).ok()),
                 connected: false,
                 vscode_pid,
                 terminal_shell_pid: shell_pid,
@@ -123,6 +131,8 @@ impl IPCCommunicator {
                 write_half: None,
                 pending_requests: HashMap::new(),
                 pending_feedback: HashMap::new(),
+                feedback_queue: FeedbackQueue::new(),
+                timeouts: FeedbackTimeouts::default(),
                 connected: false,
                 vscode_pid: 0,         // Dummy PID for test mode
                 terminal_shell_pid: 0, // Dummy PID for test mode
@@ -352,6 +362,41 @@ impl IPCCommunicator {
             });
         }
 
+        let message = self.create_synthetic_pr_message(review_response).await?;
+        debug!(
+            "Sending create_synthetic_pr message for review: {}",
+            review_response.review_id
+        );
+        self.send_message_with_reply(message).await
+    }
+
+    /// Send synthetic PR data to VSCode extension without waiting for the user
+    ///
+    /// The user's feedback is queued for the review once it arrives; collect it
+    /// from [`IPCCommunicator::feedback_queue`].
+    pub async fn send_create_synthetic_pr_for_polling(
+        &self,
+        review_response: &crate::synthetic_pr::ReviewData,
+    ) -> Result<()> {
+        if self.test_mode {
+            info!("Send create synthetic PR for polling called (test mode)");
+            self.feedback_queue().await.track(&review_response.review_id);
+            return Ok(());
+        }
+
+        let message = self.create_synthetic_pr_message(review_response).await?;
+        debug!(
+            "Sending create_synthetic_pr message for review {} in poll mode",
+            review_response.review_id
+        );
+        self.send_message_queueing_feedback(message, &review_response.review_id)
+            .await
+    }
+
+    async fn create_synthetic_pr_message(
+        &self,
+        review_response: &crate::synthetic_pr::ReviewData,
+    ) -> Result<IPCMessage> {
         let shell_pid = {
             let inner_guard = self.inner.lock().await;
             inner_guard.terminal_shell_pid
@@ -363,22 +408,28 @@ impl IPCCommunicator {
             description: review_response.description.clone(),
             commit_range: review_response.commit_range.clone(),
             files_changed: review_response.files_changed.clone(),
+            commits: review_response.commits.clone(),
+            rounds: review_response.rounds.clone(),
             comment_threads: review_response.comment_threads.clone(),
             status: review_response.status.clone(),
         };
 
-        let message = IPCMessage {
+        Ok(IPCMessage {
             shell_pid,
             message_type: IPCMessageType::CreateSyntheticPr,
             payload: serde_json::to_value(payload)?,
             id: Uuid::new_v4().to_string(),
-        };
+        })
+    }
 
-        debug!(
-            "Sending create_synthetic_pr message for review: {}",
-            review_response.review_id
-        );
-        self.send_message_with_reply(message).await
+    /// Queue of user feedback on reviews shown in poll mode
+    pub async fn feedback_queue(&self) -> FeedbackQueue {
+        self.inner.lock().await.feedback_queue.clone()
+    }
+
+    /// How long the server waits on the user
+    pub async fn feedback_timeouts(&self) -> FeedbackTimeouts {
+        self.inner.lock().await.timeouts
     }
 
     /// Wait for user feedback on a specific review
@@ -396,6 +447,7 @@ impl IPCCommunicator {
             return Ok(UserFeedback {
                 review_id: review_id.to_string(),
                 feedback: crate::synthetic_pr::FeedbackData::Comment {
+                    thread_id: None,
                     file_path: Some("test.rs".to_string()),
                     line_number: Some(42),
                     comment_text: "This is a test comment".to_string(),
@@ -451,6 +503,7 @@ impl IPCCommunicator {
             return Ok(UserFeedback {
                 review_id: "test_review".to_string(),
                 feedback: crate::synthetic_pr::FeedbackData::Comment {
+                    thread_id: None,
                     file_path: Some("test.rs".to_string()),
                     line_number: Some(42),
                     comment_text: "This looks good to me!".to_string(),
@@ -480,6 +533,36 @@ impl IPCCommunicator {
         Ok(response)
     }
 
+    /// Send review update to VSCode extension without waiting for the user
+    ///
+    /// The user's feedback is queued for the review once it arrives; collect it
+    /// from [`IPCCommunicator::feedback_queue`].
+    pub async fn send_review_update_for_polling<T: serde::Serialize>(
+        &self,
+        review_id: &str,
+        review: &T,
+    ) -> Result<()> {
+        if self.test_mode {
+            info!("Send review update for polling called (test mode)");
+            self.feedback_queue().await.track(review_id);
+            return Ok(());
+        }
+
+        let shell_pid = {
+            let inner = self.inner.lock().await;
+            inner.terminal_shell_pid
+        };
+
+        let message = IPCMessage {
+            shell_pid,
+            message_type: IPCMessageType::UpdateSyntheticPr,
+            payload: serde_json::to_value(review)?,
+            id: Uuid::new_v4().to_string(),
+        };
+
+        self.send_message_queueing_feedback(message, review_id).await
+    }
+
     /// Gracefully shutdown the IPC communicator, sending Goodbye discovery message
     pub async fn shutdown(&self) -> Result<()> {
         if self.test_mode {
@@ -505,13 +588,55 @@ impl IPCCommunicator {
     where
         R: DeserializeOwned,
     {
+        let timeout_duration = self.reply_timeout(&message.message_type).await;
+        let message_id = message.id.clone();
+        let rx = self.send_request(message).await?;
+        Self::receive_reply(&self.inner, &message_id, rx, timeout_duration).await
+    }
+
+    /// Sends a synthetic PR message and queues the user's feedback for the review
+    /// once the extension replies, instead of waiting for it
+    async fn send_message_queueing_feedback(
+        &self,
+        message: IPCMessage,
+        review_id: &str,
+    ) -> Result<()> {
+        let timeout_duration = self.reply_timeout(&message.message_type).await;
+        let feedback_queue = self.feedback_queue().await;
+        feedback_queue.track(review_id);
+
+        let message_id = message.id.clone();
+        let rx = self.send_request(message).await?;
+
+        let inner = Arc::clone(&self.inner);
+        tokio::spawn(async move {
+            match Self::receive_reply::<UserFeedback>(&inner, &message_id, rx, timeout_duration)
+                .await
+            {
+                Ok(feedback) => {
+                    if !feedback_queue.push(feedback) {
+                        warn!("Dropped feedback for a review that is not being polled");
+                    }
+                }
+                Err(e) => warn!("No user feedback for message ID {}: {}", message_id, e),
+            }
+        });
+        Ok(())
+    }
+
+    /// How long to wait for the reply to a message
+    async fn reply_timeout(&self, message_type: &IPCMessageType) -> Duration {
         // Use longer timeout for user feedback messages
-        let timeout_duration = match message.message_type {
+        match message_type {
             IPCMessageType::CreateSyntheticPr | IPCMessageType::UpdateSyntheticPr => {
-                std::time::Duration::from_secs(3600) // 1 hour for user interactions
+                self.feedback_timeouts().await.review
             }
-            _ => std::time::Duration::from_secs(5), // 5 seconds for normal operations
-        };
+            _ => Duration::from_secs(5), // 5 seconds for normal operations
+        }
+    }
+
+    /// Registers a response channel for the message and sends it
+    async fn send_request(&self, message: IPCMessage) -> Result<oneshot::Receiver<ResponsePayload>> {
         debug!(
             "Sending IPC message with ID: {} (PID: {})",
             message.id,
@@ -535,21 +660,33 @@ impl IPCCommunicator {
 
         self.write_message(&message_data).await?;
         trace!("write_message completed successfully");
+        Ok(rx)
+    }
 
-        trace!("Waiting for response with 5 second timeout...");
+    /// Waits for the response to a sent message and parses its data
+    async fn receive_reply<R>(
+        inner: &Arc<Mutex<IPCCommunicatorInner>>,
+        message_id: &str,
+        rx: oneshot::Receiver<ResponsePayload>,
+        timeout_duration: Duration,
+    ) -> Result<R>
+    where
+        R: DeserializeOwned,
+    {
+        trace!("Waiting for response with {:?} timeout...", timeout_duration);
 
         // Wait for response with appropriate timeout
         let response = tokio::time::timeout(timeout_duration, rx)
             .await
             .map_err(|_| {
                 // Clean up the leaked entry on timeout to fix memory leak
-                let inner_clone = Arc::clone(&self.inner);
-                let message_id = message.id.clone();
+                let inner_clone = Arc::clone(inner);
+                let pending_id = message_id.to_string();
                 tokio::spawn(async move {
                     let mut inner = inner_clone.lock().await;
-                    inner.pending_requests.remove(&message_id);
+                    inner.pending_requests.remove(&pending_id);
                 });
-                error!("Timeout waiting for response to message ID: {}", message.id);
+                error!("Timeout waiting for response to message ID: {}", message_id);
                 IPCError::Timeout
             })?
             .map_err(|_| IPCError::ChannelClosed)?;
@@ -893,6 +1030,7 @@ impl IPCCommunicator {
                     review_id: feedback_payload.review_id.clone(),
                     feedback: match feedback_payload.feedback_type.as_str() {
                         "comment" => crate::synthetic_pr::FeedbackData::Comment {
+                            thread_id: feedback_payload.thread_id,
                             file_path: feedback_payload.file_path,
                             line_number: feedback_payload.line_number,
                             comment_text: feedback_payload.comment_text.unwrap_or_default(),
@@ -926,7 +1064,7 @@ impl IPCCommunicator {
                     },
                 };
 
-                // Send to waiting MCP tool
+                // Send to waiting MCP tool, or queue it for a review shown in poll mode
                 let mut inner_guard = inner.lock().await;
                 if let Some(sender) = inner_guard
                     .pending_feedback
@@ -937,6 +1075,11 @@ impl IPCCommunicator {
                             "Failed to send user feedback to waiting MCP tool - receiver dropped"
                         );
                     }
+                } else if inner_guard.feedback_queue.push(user_feedback) {
+                    debug!(
+                        "Queued user feedback for review: {}",
+                        feedback_payload.review_id
+                    );
                 } else {
                     warn!(
                         "Received user feedback for unknown review ID: {}",
@@ -1079,6 +1222,7 @@ mod test {
     //! Tests the IPC communication layer and message structure
 
     use crate::ipc::IPCCommunicator;
+    use crate::synthetic_pr::{CompletionAction, FeedbackData, UserFeedback};
     use crate::types::{IPCMessage, IPCMessageType, PresentReviewParams, ReviewMode};
     use serde_json;
     use std::sync::Arc;
@@ -1100,6 +1244,34 @@ mod test {
         assert!(selection_result.message.unwrap().contains("test mode"));
     }
 
+    #[tokio::test]
+    async fn test_review_update_for_polling_test_mode() {
+        let _ = tracing_subscriber::fmt::try_init();
+
+        let reference_store = Arc::new(crate::reference_store::ReferenceStore::new());
+        let ipc = IPCCommunicator::new_test(reference_store);
+
+        // Returns without waiting for the user, and starts queueing the review's feedback
+        ipc.send_review_update_for_polling("review-1", &serde_json::json!({}))
+            .await
+            .unwrap();
+        let queue = ipc.feedback_queue().await;
+        assert!(queue.is_tracked("review-1"));
+
+        queue.push(UserFeedback {
+            review_id: "review-1".to_string(),
+            feedback: FeedbackData::CompleteReview {
+                completion_action: CompletionAction::Return,
+                additional_notes: None,
+            },
+        });
+        assert_eq!(ipc.feedback_queue().await.drain("review-1").len(), 1);
+        assert_eq!(
+            ipc.feedback_timeouts().await,
+            crate::synthetic_pr::FeedbackTimeouts::default()
+        );
+    }
+
     #[tokio::test]
     async fn test_ipc_message_structure() {
         let _ = tracing_subscriber::fmt::try_init();
@@ -4,10 +4,12 @@
 
 mod daemon;
 mod dialect;
+mod html_sanitizer;
 mod ide;
 mod ipc;
 mod pid_discovery;
 mod reference_store;
+mod walkthrough_document;
 mod walkthrough_parser;
 mod server;
 mod types;
@@ -5,6 +5,8 @@
 //! Provides tools for AI assistants to display code reviews in VSCode.
 //! Acts as a communication bridge between AI and the VSCode extension via IPC.
 
+use std::path::PathBuf;
+
 use anyhow::Result;
 use clap::Parser;
 use rmcp::{ServiceExt, transport::stdio};
@@ -12,6 +14,7 @@ use tracing::{Level, error, info};
 use tracing_subscriber::{self, EnvFilter};
 
 use dialectic_mcp_server::DialecticServer;
+use dialectic_mcp_server::synthetic_pr::{ExportFormat, ExportReviewParams};
 
 #[derive(Parser)]
 #[command(name = "dialectic-mcp-server")]
@@ -39,6 +42,25 @@ enum Command {
         #[arg(long)]
         prefix: Option<String>,
     },
+
+    /// Export a synthetic pull request as a patch series, Markdown report or SARIF log
+    Export {
+        /// Output format
+        #[arg(long, value_enum)]
+        format: ExportFormat,
+
+        /// Review to export (defaults to the most recently updated review)
+        #[arg(long)]
+        review_id: Option<String>,
+
+        /// Repository the review belongs to (defaults to the current directory)
+        #[arg(long)]
+        repo_path: Option<String>,
+
+        /// File to write to (defaults to stdout)
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
 }
 
 #[tokio::main]
@@ -101,6 +123,25 @@ async fn main() -> Result<()> {
             );
             dialectic_mcp_server::run_daemon_with_prefix(vscode_pid, prefix, None).await?;
         }
+        Some(Command::Export {
+            format,
+            review_id,
+            repo_path,
+            output,
+        }) => {
+            let exported = dialectic_mcp_server::synthetic_pr::export_review(ExportReviewParams {
+                review_id,
+                format,
+                repo_path,
+            })
+            .await
+            .map_err(|e| anyhow::anyhow!("Review export failed: {}", e))?;
+
+            match output {
+                Some(path) => std::fs::write(path, exported)?,
+                None => print!("{}", exported),
+            }
+        }
         None => {
             info!("Starting Dialectic MCP Server (Rust)");
 
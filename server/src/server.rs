@@ -20,11 +20,18 @@ use crate::dialect::DialectInterpreter;
 use crate::ipc::IPCCommunicator;
 use crate::reference_store::ReferenceStore;
 use crate::synthetic_pr::{
-    CompletionAction, RequestReviewParams, UpdateReviewParams, UserFeedback,
+    ApplySuggestionParams, ExportReviewParams, FeedbackData, FeedbackMode, FeedbackResult,
+    FeedbackTemplates, GITHUB_API_URL, GetReviewStatusParams, GitHubPublisher,
+    ImportReviewParams, ListReviewsParams, PendingComments, PollReviewFeedbackParams,
+    PublishReviewParams, RecordedFeedback, RequestReviewParams, ReviewData,
+    StripInsightCommentsParams, UpdateReviewParams, UserFeedback,
 };
 use crate::types::{LogLevel, PresentWalkthroughParams};
 use serde::{Deserialize, Serialize};
 
+/// How long a new review waits for the IDE's references before it is shown
+const REFERENCE_LOOKUP_DEADLINE: std::time::Duration = std::time::Duration::from_secs(5);
+
 /// Parameters for the expand_reference tool
 // ANCHOR: expand_reference_params
 #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
@@ -53,6 +60,10 @@ pub struct DialecticServer {
     interpreter: DialectInterpreter<IPCCommunicator>,
     tool_router: ToolRouter<DialecticServer>,
     reference_store: Arc<ReferenceStore>,
+    /// Comments on reviews in pending review mode, held until the user completes the review
+    pending_comments: PendingComments,
+    /// Name the MCP client reported when it connected, for picking its feedback templates
+    client_name: Arc<std::sync::Mutex<Option<String>>>,
 }
 
 #[tool_router]
@@ -99,6 +110,8 @@ impl DialecticServer {
             interpreter,
             tool_router: Self::tool_router(),
             reference_store,
+            pending_comments: PendingComments::new(),
+            client_name: Arc::new(std::sync::Mutex::new(None)),
         })
     }
 
@@ -107,73 +120,281 @@ impl DialecticServer {
         &self.ipc
     }
 
-    /// Format user feedback into clear instructions for the LLM
-    fn format_user_feedback_message(&self, feedback: &UserFeedback) -> String {
-        match &feedback.feedback {
-            crate::synthetic_pr::FeedbackData::Comment {
-                file_path,
-                line_number,
-                comment_text,
-                context_lines,
-            } => {
-                let file_path = file_path.as_deref().unwrap_or("unknown file");
-                let line_number = line_number.unwrap_or(0);
-
-                let context = if let Some(lines) = context_lines {
-                    format!("\n\nCode context:\n```\n{}\n```", lines.join("\n"))
-                } else {
-                    String::new()
-                };
+    /// Record user feedback in the review it names
+    async fn record_feedback(
+        &self,
+        repo_path: Option<&str>,
+        feedback: UserFeedback,
+    ) -> RecordedFeedback {
+        let recorded = crate::synthetic_pr::record_user_feedback(repo_path, &feedback)
+            .map_err(|e| e.to_string());
+        let thread_id = match recorded {
+            Ok(thread_id) => thread_id,
+            Err(e) => {
+                self.ipc
+                    .send_log(
+                        LogLevel::Error,
+                        format!("Failed to record user feedback in review: {}", e),
+                    )
+                    .await;
+                None
+            }
+        };
 
-                format!(
-                    "The user reviewed your code changes and left a comment on file `{}` at line {}:\n\n\
-                    User comment: '{}'{}\n\n\
-                    Please analyze the user's feedback and prepare a thoughtful response addressing their concern. \
-                    Do NOT modify any files on disk.\n\n\
-                    When ready, invoke the update_review tool with:\n\
-                    - review_id: '{}'\n\
-                    - action: AddComment\n\
-                    - comment: {{ response: 'Your response text here' }}\n\n\
-                    After responding, invoke update_review again with action: WaitForFeedback to continue the conversation.",
-                    file_path, line_number, comment_text, context, &feedback.review_id
-                )
+        RecordedFeedback { feedback, thread_id }
+    }
+
+    /// Record the user's feedback on a review and turn it into instructions for the LLM
+    ///
+    /// In pending review mode, comments are held and the review is shown again (with
+    /// the new comment) until the user completes it; all comments held for the review
+    /// are then delivered in one message.
+    async fn handle_user_feedback(
+        &self,
+        repo_path: Option<&str>,
+        review: &ReviewData,
+        feedback: UserFeedback,
+        feedback_mode: FeedbackMode,
+    ) -> Result<CallToolResult, McpError> {
+        let mut feedback = feedback;
+        if feedback_mode == FeedbackMode::PendingReview {
+            while matches!(feedback.feedback, FeedbackData::Comment { .. })
+                && feedback.review_id == review.review_id
+            {
+                let recorded = self.record_feedback(repo_path, feedback).await;
+                self.pending_comments.add(recorded);
+
+                // Show the comment in its thread and keep collecting
+                let current = crate::synthetic_pr::load_review_data(repo_path, &review.review_id)
+                    .unwrap_or_else(|_| review.clone());
+                feedback = self.ipc.send_review_update(&current).await.map_err(|e| {
+                    McpError::internal_error(
+                        "Failed to send review update",
+                        Some(serde_json::json!({
+                            "error": e.to_string()
+                        })),
+                    )
+                })?;
+            }
+        }
+
+        let review_id = feedback.review_id.clone();
+        let mut batch = self.pending_comments.take(&review_id);
+        batch.push(self.record_feedback(repo_path, feedback).await);
+        let result = FeedbackResult::from_batch(&review_id, &batch, repo_path, feedback_mode);
+        self.feedback_response(&result).await
+    }
+
+    /// Feedback templates for the connected client, from the user's configuration
+    ///
+    /// Never read from the reviewed repository, whose contents shouldn't steer the agent.
+    /// Falls back to the built-in templates (logging why) when the configuration is invalid.
+    async fn feedback_templates(&self) -> FeedbackTemplates {
+        let Some(path) = FeedbackTemplates::config_path(|name| std::env::var(name).ok()) else {
+            return FeedbackTemplates::default();
+        };
+
+        let client_name = self.client_name.lock().unwrap().clone();
+        match FeedbackTemplates::load(&path, client_name.as_deref()) {
+            Ok(templates) => templates,
+            Err(e) => {
+                self.ipc
+                    .send_log(
+                        LogLevel::Error,
+                        format!("Using the built-in feedback templates: {}", e),
+                    )
+                    .await;
+                FeedbackTemplates::default()
             }
-            crate::synthetic_pr::FeedbackData::CompleteReview {
-                completion_action,
-                additional_notes,
-            } => {
-                let notes = additional_notes.as_deref().unwrap_or("");
-
-                let notes_section = if !notes.is_empty() {
-                    format!("\nAdditional notes: '{}'\n", notes)
-                } else {
-                    String::new()
-                };
-
-                match completion_action {
-                    CompletionAction::RequestChanges => format!(
-                        "User completed their review and selected: 'Request agent to make changes'{}\n\
-                        Based on the review discussion, please implement the requested changes. \
-                        You may now edit files as needed.\n\n\
-                        When finished, invoke: update_review(review_id: '{}', action: Approve)",
-                        notes_section, &feedback.review_id
-                    ),
-                    CompletionAction::Checkpoint => format!(
-                        "User completed their review and selected: 'Request agent to checkpoint this work'{}\n\
-                        Please commit the current changes and document the work completed.\n\n\
-                        When finished, invoke: update_review(review_id: '{}', action: Approve)",
-                        notes_section, &feedback.review_id
-                    ),
-                    CompletionAction::Return => format!(
-                        "User completed their review and selected: 'Return to agent without explicit request'{}\n\
-                        The review is complete. You may proceed as you see fit.",
-                        notes_section
-                    ),
+        }
+    }
+
+    /// Hand feedback to the LLM: instructions rendered for the connected client,
+    /// followed by the same feedback as JSON
+    async fn feedback_response(&self, result: &FeedbackResult) -> Result<CallToolResult, McpError> {
+        let message = result.render(&self.feedback_templates().await);
+        let json_content = Content::json(result).map_err(|e| {
+            McpError::internal_error(
+                "Serialization failed",
+                Some(serde_json::json!({
+                    "error": format!("Failed to serialize feedback: {}", e)
+                })),
+            )
+        })?;
+        Ok(CallToolResult::success(vec![
+            Content::text(message),
+            json_content,
+        ]))
+    }
+
+    /// Ask the IDE which other files reference the symbols each changed file defines,
+    /// so the review's files are ranked by how much code depends on them
+    ///
+    /// The symbols are looked up concurrently, and lookups still running after
+    /// [`REFERENCE_LOOKUP_DEADLINE`] are dropped so the review isn't held up.
+    /// Best effort: returns None when the IDE finds no references (e.g., in test mode)
+    /// or they can't be recorded, leaving the review ranked without them.
+    async fn rank_by_references(
+        &self,
+        repo_path: Option<&str>,
+        review: &ReviewData,
+    ) -> Option<ReviewData> {
+        use crate::dialect::DialectFunction;
+        use futures::StreamExt;
+
+        let lookups: Vec<(String, Vec<String>)> = review
+            .files_changed
+            .iter()
+            .map(|file| {
+                (
+                    file.path.clone(),
+                    crate::synthetic_pr::defined_symbols(file),
+                )
+            })
+            .filter(|(_, symbols)| !symbols.is_empty())
+            .collect();
+        if lookups.is_empty() {
+            return None;
+        }
+
+        // Dialect functions aren't Send, same as in ide_operation
+        let interpreter = self.interpreter.clone();
+        let referenced_by = tokio::task::spawn_blocking(move || {
+            tokio::runtime::Handle::current().block_on(async move {
+                let mut referenced_by: std::collections::BTreeMap<String, Vec<String>> = lookups
+                    .iter()
+                    .map(|(file_path, _)| (file_path.clone(), Vec::new()))
+                    .collect();
+                let mut pending: futures::stream::FuturesUnordered<_> = lookups
+                    .into_iter()
+                    .flat_map(|(file_path, symbols)| {
+                        symbols.into_iter().map(move |symbol| (file_path.clone(), symbol))
+                    })
+                    .map(|(file_path, symbol)| {
+                        let mut interpreter = interpreter.clone();
+                        async move {
+                            let find = crate::ide::FindReferences {
+                                to: crate::ide::Symbols::Name(symbol),
+                            };
+                            // A symbol the IDE can't resolve just adds nothing
+                            let found = find.execute(&mut interpreter).await.unwrap_or_default();
+                            (file_path, found)
+                        }
+                    })
+                    .collect();
+
+                let deadline = tokio::time::Instant::now() + REFERENCE_LOOKUP_DEADLINE;
+                while let Ok(Some((file_path, found))) =
+                    tokio::time::timeout_at(deadline, pending.next()).await
+                {
+                    let references = found
+                        .into_iter()
+                        .filter(|r| {
+                            std::path::Path::new(&r.definition.defined_at.path).ends_with(&file_path)
+                        })
+                        .map(|r| r.referenced_at.path);
+                    referenced_by.entry(file_path.clone()).or_default().extend(references);
                 }
+                referenced_by
+            })
+        })
+        .await
+        .ok()?;
+        if referenced_by.values().all(Vec::is_empty) {
+            return None;
+        }
+
+        let recorded = crate::synthetic_pr::record_file_references(
+            repo_path,
+            &review.review_id,
+            referenced_by,
+        )
+        .map_err(|e| e.to_string());
+        match recorded {
+            Ok(ranked) => Some(ranked),
+            Err(e) => {
+                self.ipc
+                    .send_log(
+                        LogLevel::Error,
+                        format!("Failed to record file references: {}", e),
+                    )
+                    .await;
+                None
             }
         }
     }
 
+    /// Show a newly created review in VSCode and wait for the user's first feedback
+    ///
+    /// In poll mode, returns as soon as the review is shown; the feedback is queued.
+    /// The files are ranked by their references first, when the IDE can find them.
+    async fn present_new_review(
+        &self,
+        repo_path: Option<&str>,
+        review: &ReviewData,
+        feedback_mode: FeedbackMode,
+    ) -> Result<CallToolResult, McpError> {
+        let ranked = self.rank_by_references(repo_path, review).await;
+        let review = ranked.as_ref().unwrap_or(review);
+
+        if feedback_mode == FeedbackMode::Poll {
+            self.ipc
+                .send_create_synthetic_pr_for_polling(review)
+                .await
+                .map_err(|e| {
+                    McpError::internal_error(
+                        "Failed to send synthetic PR to VSCode",
+                        Some(serde_json::json!({
+                            "error": e.to_string()
+                        })),
+                    )
+                })?;
+
+            self.ipc
+                .send_log(
+                    LogLevel::Info,
+                    format!("Synthetic PR created in poll mode: {}", review.review_id),
+                )
+                .await;
+
+            let result = FeedbackResult::awaiting_feedback(&review.review_id, repo_path);
+            return self.feedback_response(&result).await;
+        }
+
+        // Send synthetic PR data to VSCode extension via IPC
+        self.ipc
+            .send_create_synthetic_pr(review)
+            .await
+            .map_err(|e| {
+                McpError::internal_error(
+                    "Failed to send synthetic PR to VSCode",
+                    Some(serde_json::json!({
+                        "error": e.to_string()
+                    })),
+                )
+            })?;
+
+        self.ipc
+            .send_log(
+                LogLevel::Info,
+                format!("Synthetic PR created successfully: {}", review.review_id),
+            )
+            .await;
+
+        // Send initial review to VSCode extension and wait for user response
+        let user_feedback = self.ipc.send_review_update(review).await.map_err(|e| {
+            McpError::internal_error(
+                "Failed to send initial review",
+                Some(serde_json::json!({
+                    "error": e.to_string()
+                })),
+            )
+        })?;
+
+        self.handle_user_feedback(repo_path, review, user_feedback, feedback_mode)
+            .await
+    }
+
     /// Ensure the message bus daemon is running for the given VSCode PID
     async fn ensure_daemon_running(vscode_pid: u32) -> Result<()> {
         crate::daemon::spawn_daemon_process(vscode_pid).await
@@ -201,6 +422,8 @@ impl DialecticServer {
             interpreter,
             tool_router: Self::tool_router(),
             reference_store,
+            pending_comments: PendingComments::new(),
+            client_name: Arc::new(std::sync::Mutex::new(None)),
         }
     }
 
@@ -236,16 +459,17 @@ impl DialecticServer {
 
         // Parse markdown with XML elements and resolve Dialect expressions
         let mut parser = crate::walkthrough_parser::WalkthroughParser::new(self.interpreter.clone());
-        let resolved_html = parser.parse_and_normalize(&params.content).await.map_err(|e| {
+        let document = parser.parse(&params.content).await.map_err(|e| {
             McpError::internal_error(
                 "Failed to parse walkthrough markdown",
                 Some(serde_json::json!({"error": e.to_string()})),
             )
         })?;
 
-        // Create resolved walkthrough with HTML content
+        // Create resolved walkthrough with the document and its HTML rendering
         let resolved = crate::ide::ResolvedWalkthrough {
-            content: resolved_html,
+            content: document.to_html(),
+            document,
             base_uri: params.base_uri.clone(),
         };
 
@@ -424,7 +648,9 @@ impl DialecticServer {
         description = "Create a synthetic pull request from a Git commit range with AI insight comments. \
                        Supports commit ranges like 'HEAD', 'HEAD~2', 'abc123..def456'. \
                        Extracts AI insight comments (TODO/FIXME/insight markers) and generates structured review data. \
-                       BLOCKS until user provides initial feedback."
+                       BLOCKS until user provides initial feedback (with feedback_mode 'pending_review', until they complete \
+                       the review, delivering all their comments at once), unless feedback_mode is 'poll': \
+                       then it returns right away and feedback is collected with poll_review_feedback."
     )]
     async fn request_review(
         &self,
@@ -437,6 +663,9 @@ impl DialecticServer {
             )
             .await;
 
+        let repo_path = params.repo_path.clone();
+        let feedback_mode = params.feedback_mode;
+
         // Execute the synthetic PR creation
         let result = crate::synthetic_pr::harvest_review_data(params)
             .await
@@ -449,48 +678,62 @@ impl DialecticServer {
                 )
             })?;
 
-        // Send synthetic PR data to VSCode extension via IPC
+        self.present_new_review(repo_path.as_deref(), &result, feedback_mode)
+            .await
+    }
+
+    /// Create a synthetic pull request from a GitHub pull request
+    ///
+    /// Imports the pull request's review comments as comment threads so the
+    /// agent can respond to human reviewers from the IDE.
+    // ANCHOR: import_review_tool
+    #[tool(
+        description = "Create a synthetic pull request from a GitHub pull request so you can answer its human reviewers. \
+                       Takes the JSON saved from 'gh api repos/{owner}/{repo}/pulls/{number}' and \
+                       'gh api repos/{owner}/{repo}/pulls/{number}/comments'; the pull request's commits must be fetched locally. \
+                       Review comments become comment threads to reply to with update_review. \
+                       BLOCKS until user provides initial feedback (with feedback_mode 'pending_review', until they complete \
+                       the review, delivering all their comments at once), unless feedback_mode is 'poll': \
+                       then it returns right away and feedback is collected with poll_review_feedback."
+    )]
+    async fn import_review(
+        &self,
+        Parameters(params): Parameters<ImportReviewParams>,
+    ) -> Result<CallToolResult, McpError> {
         self.ipc
-            .send_create_synthetic_pr(&result)
+            .send_log(
+                LogLevel::Debug,
+                format!("Received import_review tool call: {:?}", params),
+            )
+            .await;
+
+        let repo_path = params.repo_path.clone();
+        let feedback_mode = params.feedback_mode;
+
+        let result = crate::synthetic_pr::import_review(params)
             .await
             .map_err(|e| {
                 McpError::internal_error(
-                    "Failed to send synthetic PR to VSCode",
+                    "Pull request import failed",
                     Some(serde_json::json!({
                         "error": e.to_string()
                     })),
                 )
             })?;
 
-        self.ipc
-            .send_log(
-                LogLevel::Info,
-                format!("Synthetic PR created successfully: {}", result.review_id),
-            )
-            .await;
-
-        // Send initial review to VSCode extension and wait for user response
-        let user_feedback = self.ipc.send_review_update(&result).await.map_err(|e| {
-            McpError::internal_error(
-                "Failed to send initial review",
-                Some(serde_json::json!({
-                    "error": e.to_string()
-                })),
-            )
-        })?;
-
-        let message = self.format_user_feedback_message(&user_feedback);
-        Ok(CallToolResult::success(vec![Content::text(message)]))
+        self.present_new_review(repo_path.as_deref(), &result, feedback_mode)
+            .await
     }
 
     // ANCHOR: update_review_tool
     /// Update an existing synthetic pull request or wait for user feedback
     ///
-    /// Supports actions: wait_for_feedback, add_comment, approve, request_changes.
+    /// Supports actions: wait_for_feedback, add_comment, approve, request_changes, merge.
     /// Used for iterative review workflows between AI and developer.
     #[tool(
         description = "Update an existing synthetic pull request or wait for user feedback. \
                          This tool is used to interact with the user through their IDE. \
+                         Approve is refused, with the reasons, while the repository's review policy is not met. \
                          Do not invoke it except when asked to do so by other tools within dialectic."
     )]
     async fn update_review(
@@ -504,6 +747,9 @@ impl DialecticServer {
             )
             .await;
 
+        let repo_path = params.repo_path.clone();
+        let feedback_mode = params.feedback_mode;
+
         // 1. Update the review state based on action
         let updated_review = crate::synthetic_pr::update_review(params)
             .await
@@ -517,9 +763,33 @@ impl DialecticServer {
             })?;
 
         // 2. Send updated state to VSCode extension via IPC and wait for response
+        // (in poll mode, the response is queued for poll_review_feedback instead)
+        if feedback_mode == FeedbackMode::Poll {
+            self.ipc
+                .send_review_update_for_polling(
+                    &updated_review.review.review_id,
+                    &updated_review.review,
+                )
+                .await
+                .map_err(|e| {
+                    McpError::internal_error(
+                        "Failed to send review update",
+                        Some(serde_json::json!({
+                            "error": e.to_string()
+                        })),
+                    )
+                })?;
+
+            let result = FeedbackResult::awaiting_feedback(
+                &updated_review.review.review_id,
+                repo_path.as_deref(),
+            );
+            return self.feedback_response(&result).await;
+        }
+
         let user_feedback = self
             .ipc
-            .send_review_update(&updated_review)
+            .send_review_update(&updated_review.review)
             .await
             .map_err(|e| {
                 McpError::internal_error(
@@ -531,30 +801,275 @@ impl DialecticServer {
             })?;
 
         // 3. Return formatted user response to LLM
-        let message = self.format_user_feedback_message(&user_feedback);
-        Ok(CallToolResult::success(vec![Content::text(message)]))
+        self.handle_user_feedback(
+            repo_path.as_deref(),
+            &updated_review.review,
+            user_feedback,
+            feedback_mode,
+        )
+        .await
+    }
+
+    /// Collect queued user feedback on a review shown in poll mode
+    ///
+    /// Returns right away when feedback is queued (or `wait_seconds` is 0); otherwise
+    /// waits up to `wait_seconds` for some to arrive.
+    // ANCHOR: poll_review_feedback_tool
+    #[tool(description = "Collect the user's feedback on a synthetic pull request created or updated with feedback_mode 'poll'. \
+                       Returns all feedback queued since the last poll, as instructions for how to respond followed by the same feedback as JSON (with suggested next tool calls). \
+                       With wait_seconds 0 (the default) it returns right away; otherwise it waits up to wait_seconds \
+                       (capped by the server) for feedback to arrive. Returns 'No feedback yet' when there is none.")]
+    async fn poll_review_feedback(
+        &self,
+        Parameters(params): Parameters<PollReviewFeedbackParams>,
+    ) -> Result<CallToolResult, McpError> {
+        self.ipc
+            .send_log(
+                LogLevel::Debug,
+                format!("Received poll_review_feedback tool call: {:?}", params),
+            )
+            .await;
+
+        let queue = self.ipc.feedback_queue().await;
+        if !queue.is_tracked(&params.review_id) {
+            return Err(McpError::invalid_params(
+                format!(
+                    "Review '{}' is not awaiting feedback in poll mode; \
+                    show it with feedback_mode 'poll' first",
+                    params.review_id
+                ),
+                None,
+            ));
+        }
+
+        let max_wait = self.ipc.feedback_timeouts().await.max_poll_wait;
+        let wait = std::time::Duration::from_secs(params.wait_seconds).min(max_wait);
+        let feedback = queue.wait(&params.review_id, wait).await;
+
+        let repo_path = params.repo_path.as_deref();
+        if feedback.is_empty() {
+            let result = FeedbackResult::no_feedback_yet(&params.review_id, repo_path);
+            return self.feedback_response(&result).await;
+        }
+
+        let mut batch = self.pending_comments.take(&params.review_id);
+        for feedback in feedback {
+            batch.push(self.record_feedback(repo_path, feedback).await);
+        }
+        let result =
+            FeedbackResult::from_batch(&params.review_id, &batch, repo_path, FeedbackMode::Poll);
+        self.feedback_response(&result).await
     }
 
-    /// Get the status of the current synthetic pull request
+    /// Get the status of a synthetic pull request
     ///
-    /// Returns information about the active review including file counts,
-    /// comment threads, and current status.
+    /// Returns information about the requested review (or the most recently
+    /// updated one) including file counts, comment threads, current status and
+    /// the history of who commented, requested changes, approved or merged it.
     // ANCHOR: get_review_status_tool
-    #[tool(description = "Get the status of the current synthetic pull request. \
-                       Returns review information including file counts, comment threads, and status.")]
-    async fn get_review_status(&self) -> Result<CallToolResult, McpError> {
+    #[tool(description = "Get the status of a synthetic pull request. \
+                       Pass review_id to pick a review; defaults to the most recently updated one. \
+                       Returns review information including file counts, comment threads, status, \
+                       the history of review events with their actors and timestamps, \
+                       and whether the review meets the repository's review policy.")]
+    async fn get_review_status(
+        &self,
+        Parameters(params): Parameters<GetReviewStatusParams>,
+    ) -> Result<CallToolResult, McpError> {
         self.ipc
             .send_log(
                 LogLevel::Debug,
-                "Received get_review_status tool call".to_string(),
+                format!("Received get_review_status tool call: {:?}", params),
             )
             .await;
 
-        let result = crate::synthetic_pr::get_review_status(None)
+        let result = crate::synthetic_pr::get_review_status(
+            params.repo_path.as_deref(),
+            params.review_id.as_deref(),
+        )
+        .await
+        .map_err(|e| {
+            McpError::internal_error(
+                "Status retrieval failed",
+                Some(serde_json::json!({
+                    "error": e.to_string()
+                })),
+            )
+        })?;
+
+        let json_content = Content::json(result).map_err(|e| {
+            McpError::internal_error(
+                "Serialization failed",
+                Some(serde_json::json!({
+                    "error": format!("Failed to serialize status result: {}", e)
+                })),
+            )
+        })?;
+
+        Ok(CallToolResult::success(vec![json_content]))
+    }
+
+    /// List the synthetic pull requests of a repository
+    ///
+    /// Returns a summary of every stored review, most recently updated first.
+    // ANCHOR: list_reviews_tool
+    #[tool(description = "List the synthetic pull requests stored for this repository. \
+                       Returns review ids, titles, commit ranges, statuses and timestamps, most recently updated first.")]
+    async fn list_reviews(
+        &self,
+        Parameters(params): Parameters<ListReviewsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        self.ipc
+            .send_log(
+                LogLevel::Debug,
+                format!("Received list_reviews tool call: {:?}", params),
+            )
+            .await;
+
+        let reviews = crate::synthetic_pr::list_reviews(params.repo_path.as_deref())
             .await
             .map_err(|e| {
                 McpError::internal_error(
-                    "Status retrieval failed",
+                    "Listing reviews failed",
+                    Some(serde_json::json!({
+                        "error": e.to_string()
+                    })),
+                )
+            })?;
+
+        let json_content = Content::json(reviews).map_err(|e| {
+            McpError::internal_error(
+                "Serialization failed",
+                Some(serde_json::json!({
+                    "error": format!("Failed to serialize review list: {}", e)
+                })),
+            )
+        })?;
+
+        Ok(CallToolResult::success(vec![json_content]))
+    }
+
+    /// Export a synthetic pull request to a standard format
+    ///
+    /// Produces a patch series, a Markdown report or a SARIF log of the review.
+    // ANCHOR: export_review_tool
+    #[tool(description = "Export a synthetic pull request as an artifact. \
+                       Formats: 'mbox' (git format-patch style patch series), \
+                       'markdown' (report with comment threads next to the hunks they refer to) \
+                       and 'sarif' (TODO, FIXME and question threads as SARIF results). \
+                       Pass review_id to pick a review; defaults to the most recently updated one.")]
+    async fn export_review(
+        &self,
+        Parameters(params): Parameters<ExportReviewParams>,
+    ) -> Result<CallToolResult, McpError> {
+        self.ipc
+            .send_log(
+                LogLevel::Debug,
+                format!("Received export_review tool call: {:?}", params),
+            )
+            .await;
+
+        let exported = crate::synthetic_pr::export_review(params)
+            .await
+            .map_err(|e| {
+                McpError::internal_error(
+                    "Review export failed",
+                    Some(serde_json::json!({
+                        "error": e.to_string()
+                    })),
+                )
+            })?;
+
+        Ok(CallToolResult::success(vec![Content::text(exported)]))
+    }
+
+    /// Publish a synthetic pull request to GitHub
+    ///
+    /// Opens a pull request between existing branches and attaches the review's
+    /// comment threads as review comments. Authenticates with `$GITHUB_TOKEN`.
+    // ANCHOR: publish_review_tool
+    #[tool(description = "Publish a synthetic pull request to GitHub as a real pull request. \
+                       Opens a pull request from 'head' into 'base' (both must already be pushed) in 'repository' (owner/repo) \
+                       and attaches the comment threads as review comments. Requires the GITHUB_TOKEN environment variable. \
+                       Pass review_id to pick a review; defaults to the most recently updated one.")]
+    async fn publish_review(
+        &self,
+        Parameters(params): Parameters<PublishReviewParams>,
+    ) -> Result<CallToolResult, McpError> {
+        self.ipc
+            .send_log(
+                LogLevel::Debug,
+                format!("Received publish_review tool call: {:?}", params),
+            )
+            .await;
+
+        let invalid = |message: &str| McpError::invalid_params(message.to_string(), None);
+        let (owner, repo) = params
+            .repository
+            .split_once('/')
+            .ok_or_else(|| invalid("repository must be given as owner/repo"))?;
+        let token = std::env::var("GITHUB_TOKEN")
+            .map_err(|_| invalid("GITHUB_TOKEN is not set; it is needed to publish to GitHub"))?;
+        // The token goes to this host, so only the user's environment chooses it, never the
+        // tool call
+        let api_url =
+            std::env::var("GITHUB_API_URL").unwrap_or_else(|_| GITHUB_API_URL.to_string());
+        let publisher = GitHubPublisher::new(owner, repo, &token).with_api_url(&api_url);
+
+        let published = crate::synthetic_pr::publish_review(
+            &publisher,
+            params.repo_path.as_deref(),
+            params.review_id.as_deref(),
+            params.branches,
+        )
+        .await
+        .map_err(|e| {
+            McpError::internal_error(
+                "Publishing review failed",
+                Some(serde_json::json!({
+                    "error": e.to_string()
+                })),
+            )
+        })?;
+
+        let json_content = Content::json(published).map_err(|e| {
+            McpError::internal_error(
+                "Serialization failed",
+                Some(serde_json::json!({
+                    "error": format!("Failed to serialize published pull request: {}", e)
+                })),
+            )
+        })?;
+
+        Ok(CallToolResult::success(vec![json_content]))
+    }
+
+    /// Remove a review's insight comments from the working tree
+    ///
+    /// Reports the edits as a diff, and writes them only when asked to, so the
+    /// 💡/❓ markers can be cleaned up before the work is committed.
+    // ANCHOR: strip_insight_comments_tool
+    #[tool(description = "Remove the AI insight comments listed in a synthetic pull request from the working tree. \
+                       Strips 'explanation' (💡) and 'question' (❓) comments by default; pass comment_types to choose others. \
+                       With keep_answered_questions, questions answered during the review become regular comments holding the answers. \
+                       Returns the edits as a unified diff; they are only written to the files when apply is true. \
+                       Pass review_id to pick a review; defaults to the most recently updated one.")]
+    async fn strip_insight_comments(
+        &self,
+        Parameters(params): Parameters<StripInsightCommentsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        self.ipc
+            .send_log(
+                LogLevel::Debug,
+                format!("Received strip_insight_comments tool call: {:?}", params),
+            )
+            .await;
+
+        let result = crate::synthetic_pr::strip_insight_comments(params)
+            .await
+            .map_err(|e| {
+                McpError::internal_error(
+                    "Stripping insight comments failed",
                     Some(serde_json::json!({
                         "error": e.to_string()
                     })),
@@ -565,7 +1080,51 @@ impl DialecticServer {
             McpError::internal_error(
                 "Serialization failed",
                 Some(serde_json::json!({
-                    "error": format!("Failed to serialize status result: {}", e)
+                    "error": format!("Failed to serialize stripped comments: {}", e)
+                })),
+            )
+        })?;
+
+        Ok(CallToolResult::success(vec![json_content]))
+    }
+
+    /// Apply a change suggested in a review comment
+    ///
+    /// Writes the suggestion's replacement lines to the working tree once the lines
+    /// it replaces are confirmed unchanged.
+    // ANCHOR: apply_suggestion_tool
+    #[tool(description = "Apply a change suggested in a synthetic pull request comment (a ```suggestion block, \
+                       as on GitHub) to the working tree. The suggestion replaces the lines of the comment's thread. \
+                       Pass suggestion_id, or thread_id to apply the thread's most recent suggestion not yet applied. \
+                       Fails without writing anything if those lines changed since the suggestion was made. \
+                       Returns the edit as a unified diff.")]
+    async fn apply_suggestion(
+        &self,
+        Parameters(params): Parameters<ApplySuggestionParams>,
+    ) -> Result<CallToolResult, McpError> {
+        self.ipc
+            .send_log(
+                LogLevel::Debug,
+                format!("Received apply_suggestion tool call: {:?}", params),
+            )
+            .await;
+
+        let result = crate::synthetic_pr::apply_suggestion(params)
+            .await
+            .map_err(|e| {
+                McpError::internal_error(
+                    "Applying the suggestion failed",
+                    Some(serde_json::json!({
+                        "error": e.to_string()
+                    })),
+                )
+            })?;
+
+        let json_content = Content::json(result).map_err(|e| {
+            McpError::internal_error(
+                "Serialization failed",
+                Some(serde_json::json!({
+                    "error": format!("Failed to serialize applied suggestion: {}", e)
                 })),
             )
         })?;
@@ -662,8 +1221,15 @@ impl ServerHandler for DialecticServer {
                 'ide_operation' to execute IDE operations like finding symbol definitions and references using Dialect function calls, \
                 'present_walkthrough' to display structured code walkthroughs with interactive elements, \
                 'request_review' to create synthetic pull requests from Git commit ranges with AI insight comments, \
+                'import_review' to create one from a GitHub pull request and its review comments, \
                 'update_review' to manage review workflows and wait for user feedback, \
-                and 'get_review_status' to check the current synthetic PR status."
+                'poll_review_feedback' to collect feedback on reviews shown with feedback_mode 'poll' without blocking, \
+                'get_review_status' to check the status of a synthetic PR, \
+                'list_reviews' to list the synthetic PRs of the repository, \
+                'export_review' to export a synthetic PR as a patch series, Markdown report or SARIF log, \
+                'publish_review' to publish a synthetic PR to GitHub, \
+                'apply_suggestion' to apply a change suggested in a review comment, \
+                and 'strip_insight_comments' to remove a synthetic PR's insight comments from the code before committing."
                     .to_string(),
             ),
         }
@@ -671,10 +1237,14 @@ impl ServerHandler for DialecticServer {
 
     async fn initialize(
         &self,
-        _request: InitializeRequestParam,
+        request: InitializeRequestParam,
         _context: RequestContext<RoleServer>,
     ) -> Result<InitializeResult, McpError> {
-        info!("MCP client connected and initialized");
+        info!(
+            "MCP client connected and initialized: {}",
+            request.client_info.name
+        );
+        *self.client_name.lock().unwrap() = Some(request.client_info.name);
         Ok(self.get_info())
     }
 }
@@ -0,0 +1,165 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Number of lines above and below the anchored line kept in the fingerprint.
+pub const ANCHOR_CONTEXT_LINES: usize = 2;
+
+/// Where a comment thread is anchored in the reviewed code.
+///
+/// Records the blob the thread was anchored in, so later edits can be mapped through a
+/// diff, plus a fingerprint of the anchored line and its surroundings for when the
+/// diff mapping lands on a changed line. Lines are compared with surrounding
+/// whitespace trimmed so re-indentation does not lose the anchor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CommentAnchor {
+    /// Git blob of the file contents the thread was anchored in
+    pub blob_oid: String,
+    /// Content of the anchored line
+    pub line: String,
+    /// Lines just above the anchored line, nearest last
+    pub before: Vec<String>,
+    /// Lines just below the anchored line, nearest first
+    pub after: Vec<String>,
+}
+
+impl CommentAnchor {
+    /// Capture the anchor for a (1-based) line of a file.
+    ///
+    /// # Returns
+    /// * `Some(CommentAnchor)` - Fingerprint of the line and its context
+    /// * `None` - The line does not exist in `content`
+    pub fn capture(content: &str, line_number: u32, blob_oid: String) -> Option<Self> {
+        let lines: Vec<&str> = content.lines().map(str::trim).collect();
+        let index = (line_number as usize).checked_sub(1)?;
+        let line = lines.get(index)?;
+
+        let before_start = index.saturating_sub(ANCHOR_CONTEXT_LINES);
+        let after_end = (index + 1 + ANCHOR_CONTEXT_LINES).min(lines.len());
+
+        Some(CommentAnchor {
+            blob_oid,
+            line: line.to_string(),
+            before: lines[before_start..index]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            after: lines[index + 1..after_end]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        })
+    }
+
+    /// Find the anchored line in new file contents.
+    ///
+    /// `hint` is where the line is expected to be (e.g., mapped through a diff, or its
+    /// previous position). Among the lines equal to the anchored line, the one whose
+    /// context best matches the fingerprint is chosen, nearest to the hint on ties.
+    /// A candidate with no matching context is only accepted if it is the only
+    /// occurrence of the line, so generic lines like `}` don't jump around.
+    ///
+    /// # Returns
+    /// * `Some(line_number)` - 1-based line the thread now belongs to
+    /// * `None` - The anchor is gone; the thread is outdated
+    pub fn relocate(&self, content: &str, hint: u32) -> Option<u32> {
+        let lines: Vec<&str> = content.lines().map(str::trim).collect();
+
+        let hint_index = (hint as usize).saturating_sub(1);
+        let candidates: Vec<usize> = lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| **line == self.line)
+            .map(|(index, _)| index)
+            .collect();
+
+        let (best_index, best_score) = candidates
+            .iter()
+            .map(|&index| (index, self.context_score(&lines, index)))
+            .max_by(|(a_index, a_score), (b_index, b_score)| {
+                a_score.cmp(b_score).then_with(|| {
+                    b_index
+                        .abs_diff(hint_index)
+                        .cmp(&a_index.abs_diff(hint_index))
+                })
+            })?;
+
+        if best_score == 0 && candidates.len() > 1 {
+            return None;
+        }
+
+        Some(best_index as u32 + 1)
+    }
+
+    /// Count the context lines around `index` that match the fingerprint.
+    fn context_score(&self, lines: &[&str], index: usize) -> usize {
+        let before = self
+            .before
+            .iter()
+            .rev()
+            .enumerate()
+            .filter(|(offset, expected)| {
+                index
+                    .checked_sub(offset + 1)
+                    .and_then(|i| lines.get(i))
+                    .is_some_and(|line| line == expected)
+            })
+            .count();
+        let after = self
+            .after
+            .iter()
+            .enumerate()
+            .filter(|(offset, expected)| {
+                lines
+                    .get(index + offset + 1)
+                    .is_some_and(|line| line == expected)
+            })
+            .count();
+        before + after
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGINAL: &str = "fn a() {\n    // ❓ Why not cache this?\n    load()\n}\n";
+
+    #[test]
+    fn test_capture_anchor() {
+        let anchor = CommentAnchor::capture(ORIGINAL, 2, "blob".to_string()).unwrap();
+        assert_eq!(anchor.line, "// ❓ Why not cache this?");
+        assert_eq!(anchor.before, vec!["fn a() {"]);
+        assert_eq!(anchor.after, vec!["load()", "}"]);
+        assert!(CommentAnchor::capture(ORIGINAL, 5, "blob".to_string()).is_none());
+    }
+
+    #[test]
+    fn test_relocate_moved_line() {
+        let anchor = CommentAnchor::capture(ORIGINAL, 2, "blob".to_string()).unwrap();
+
+        // Unchanged position
+        assert_eq!(anchor.relocate(ORIGINAL, 2), Some(2));
+
+        // Code inserted above, and the block re-indented
+        let moved = "use cache;\n\nmod m {\n  fn a() {\n        // ❓ Why not cache this?\n        load()\n  }\n}\n";
+        assert_eq!(anchor.relocate(moved, 2), Some(5));
+
+        // Line removed
+        let removed = "fn a() {\n    load()\n}\n";
+        assert_eq!(anchor.relocate(removed, 2), None);
+    }
+
+    #[test]
+    fn test_relocate_prefers_matching_context() {
+        let content = "fn a() {\n    one()\n}\nfn b() {\n    two()\n}\n";
+        let anchor = CommentAnchor::capture(content, 6, "blob".to_string()).unwrap();
+
+        // The functions swapped places; the closing brace of `b` follows it
+        let swapped = "fn b() {\n    two()\n}\nfn a() {\n    one()\n}\n";
+        assert_eq!(anchor.relocate(swapped, 6), Some(3));
+
+        // Ambiguous without context: the anchor is lost rather than guessed
+        let unrelated = "fn c() {\n    three()\n}\nfn d() {\n    four()\n}\n";
+        assert_eq!(anchor.relocate(unrelated, 6), None);
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use git2::Oid;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::synthetic_pr::{CommentType, GitService};
+
+/// Comment marker configuration file, relative to the root of the repository.
+pub const COMMENT_CONFIG_FILE: &str = ".socratic-shell/comment-markers.json";
+
+/// Errors that can occur while loading the comment marker configuration.
+#[derive(Error, Debug)]
+pub enum CommentConfigError {
+    #[error("Failed to read comment marker config: {0}")]
+    Git(#[from] git2::Error),
+
+    #[error("Invalid comment marker config: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Comment marker rule for type {comment_type:?} has an empty marker")]
+    EmptyMarker { comment_type: String },
+}
+
+// ANCHOR: comment_config
+/// Which comment markers the comment parser recognizes, and how comments are written
+/// in each language.
+///
+/// Loaded from [`COMMENT_CONFIG_FILE`]; every field is optional, so a project only
+/// lists what it adds to the built-in grammar.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct CommentConfig {
+    /// Marker rules, tried after the built-in ones (💡, ❓, `TODO:`, `FIXME:`).
+    /// A rule using the same marker as a built-in one replaces it.
+    #[serde(default)]
+    pub markers: Vec<MarkerRule>,
+
+    /// Drop the built-in marker rules and use only `markers`
+    #[serde(default)]
+    pub replace_default_markers: bool,
+
+    /// Comment syntax keyed by file extension (without the dot), replacing the
+    /// built-in syntax for that extension
+    #[serde(default)]
+    pub languages: BTreeMap<String, CommentSyntax>,
+}
+
+/// A marker that turns a comment into a comment thread of the given type.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MarkerRule {
+    /// Text that starts the comment, e.g. `"SAFETY:"` or `"💡"`
+    pub marker: String,
+
+    /// `explanation`, `question`, `todo`, `fixme` or `comment` for the built-in types;
+    /// any other name (e.g. `"note"`) defines a custom comment type
+    #[serde(rename = "type")]
+    pub comment_type: String,
+}
+
+/// How comments are written in a language.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct CommentSyntax {
+    /// Tokens starting a comment that runs to the end of the line, e.g. `"//"` or `"--"`
+    #[serde(default)]
+    pub line: Vec<String>,
+
+    /// Delimiters of block comments, e.g. `/* ... */` or Python docstrings
+    #[serde(default)]
+    pub block: Vec<BlockDelimiters>,
+}
+
+/// Opening and closing delimiters of a block comment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct BlockDelimiters {
+    pub start: String,
+    pub end: String,
+}
+// ANCHOR_END: comment_config
+
+impl CommentConfig {
+    /// Load the configuration committed at `commit_oid`.
+    ///
+    /// Reviews parse their threads with the configuration of their base commit, never
+    /// the working tree, so the changes under review can't hide a new FIXME or question
+    /// by remapping or dropping markers.
+    ///
+    /// # Returns
+    /// * `Ok(CommentConfig)` - Configuration from [`COMMENT_CONFIG_FILE`], or the default if there is none
+    /// * `Err(CommentConfigError)` - Commit not found, or the configuration is invalid
+    pub fn load(git_service: &GitService, commit_oid: Oid) -> Result<Self, CommentConfigError> {
+        let Some(content) = git_service.file_at_commit(COMMENT_CONFIG_FILE, commit_oid)? else {
+            return Ok(Self::default());
+        };
+
+        let config: Self = serde_json::from_str(&content)?;
+        if let Some(rule) = config
+            .markers
+            .iter()
+            .find(|rule| rule.marker.trim().is_empty())
+        {
+            return Err(CommentConfigError::EmptyMarker {
+                comment_type: rule.comment_type.clone(),
+            });
+        }
+        Ok(config)
+    }
+
+    /// Marker rules in effect: the built-in ones (unless replaced) followed by the configured ones.
+    pub fn marker_rules(&self) -> Vec<(String, CommentType)> {
+        let mut rules: Vec<(String, CommentType)> = Vec::new();
+        if !self.replace_default_markers {
+            rules.extend([
+                ("💡".to_string(), CommentType::Explanation),
+                ("❓".to_string(), CommentType::Question),
+                ("TODO:".to_string(), CommentType::Todo),
+                ("FIXME:".to_string(), CommentType::Fixme),
+            ]);
+        }
+
+        for rule in &self.markers {
+            let comment_type = CommentType::from_name(&rule.comment_type);
+            match rules.iter_mut().find(|(marker, _)| *marker == rule.marker) {
+                Some(existing) => existing.1 = comment_type,
+                None => rules.push((rule.marker.clone(), comment_type)),
+            }
+        }
+        rules
+    }
+
+    /// Comment syntax for a file, chosen by its extension.
+    ///
+    /// Files with an unknown (or no) extension get `//`, `#`, `/* */` and `<!-- -->`.
+    pub fn syntax_for(&self, file_path: &str) -> CommentSyntax {
+        let extension = Path::new(file_path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        if let Some(syntax) = self.languages.get(&extension) {
+            return syntax.clone();
+        }
+        builtin_syntax(&extension).unwrap_or_else(|| syntax(&["//", "#"], &[C_BLOCK, HTML_BLOCK]))
+    }
+}
+
+const C_BLOCK: (&str, &str) = ("/*", "*/");
+const HTML_BLOCK: (&str, &str) = ("<!--", "-->");
+
+fn syntax(line: &[&str], block: &[(&str, &str)]) -> CommentSyntax {
+    CommentSyntax {
+        line: line.iter().map(|token| token.to_string()).collect(),
+        block: block
+            .iter()
+            .map(|(start, end)| BlockDelimiters {
+                start: start.to_string(),
+                end: end.to_string(),
+            })
+            .collect(),
+    }
+}
+
+/// Built-in comment syntax of common languages, by lowercased file extension.
+fn builtin_syntax(extension: &str) -> Option<CommentSyntax> {
+    let syntax = match extension {
+        "rs" | "c" | "h" | "cc" | "cpp" | "hpp" | "cs" | "java" | "kt" | "kts" | "scala"
+        | "swift" | "go" | "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "dart" | "php"
+        | "groovy" | "proto" | "zig" => syntax(&["//"], &[C_BLOCK]),
+        "css" => syntax(&[], &[C_BLOCK]),
+        "scss" | "less" => syntax(&["//"], &[C_BLOCK]),
+        "py" | "pyi" => syntax(&["#"], &[("\"\"\"", "\"\"\""), ("'''", "'''")]),
+        "sh" | "bash" | "zsh" | "fish" | "rb" | "pl" | "r" | "yaml" | "yml" | "toml" | "nix"
+        | "cmake" | "mk" | "dockerfile" | "ex" | "exs" | "jl" => syntax(&["#"], &[]),
+        "sql" => syntax(&["--"], &[C_BLOCK]),
+        "lua" => syntax(&["--"], &[("--[[", "]]")]),
+        "hs" | "elm" => syntax(&["--"], &[("{-", "-}")]),
+        "ml" | "mli" => syntax(&[], &[("(*", "*)")]),
+        "lisp" | "el" | "clj" | "cljs" | "scm" | "rkt" | "asm" | "s" | "ini" => syntax(&[";"], &[]),
+        "tex" | "sty" | "erl" | "hrl" | "m" => syntax(&["%"], &[]),
+        "html" | "htm" | "xml" | "svg" | "md" | "markdown" | "vue" | "svelte" => {
+            syntax(&[], &[HTML_BLOCK])
+        }
+        _ => return None,
+    };
+    Some(syntax)
+}
+
+impl CommentType {
+    /// Comment type for a name used in the comment marker configuration.
+    ///
+    /// Built-in type names are matched case-insensitively; any other name becomes a
+    /// [`CommentType::Custom`] type.
+    pub fn from_name(name: &str) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "explanation" | "insight" => CommentType::Explanation,
+            "question" => CommentType::Question,
+            "todo" => CommentType::Todo,
+            "fixme" => CommentType::Fixme,
+            "comment" => CommentType::Comment,
+            _ => CommentType::Custom(name.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marker_rules_extend_defaults() {
+        let config: CommentConfig = serde_json::from_str(
+            r#"{
+                "markers": [
+                    { "marker": "SAFETY:", "type": "safety" },
+                    { "marker": "TODO:", "type": "fixme" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let rules = config.marker_rules();
+        let markers: Vec<&str> = rules.iter().map(|(marker, _)| marker.as_str()).collect();
+        assert_eq!(markers, ["💡", "❓", "TODO:", "FIXME:", "SAFETY:"]);
+        assert!(matches!(rules[2].1, CommentType::Fixme));
+        assert!(matches!(&rules[4].1, CommentType::Custom(name) if name == "safety"));
+    }
+
+    #[test]
+    fn test_syntax_by_extension() {
+        let config: CommentConfig =
+            serde_json::from_str(r##"{ "languages": { "sql": { "line": ["#"] } } }"##).unwrap();
+
+        assert_eq!(config.syntax_for("queries/report.sql"), syntax(&["#"], &[]));
+        assert_eq!(config.syntax_for("src/lib.RS"), syntax(&["//"], &[C_BLOCK]));
+        assert_eq!(config.syntax_for("init.el"), syntax(&[";"], &[]));
+        assert_eq!(
+            config.syntax_for("Makefile"),
+            syntax(&["//", "#"], &[C_BLOCK, HTML_BLOCK])
+        );
+    }
+}
@@ -1,5 +1,9 @@
-use regex::Regex;
-use crate::synthetic_pr::{CommentThread, CommentType, ParsedComment, FileChange};
+use git2::Oid;
+
+use crate::synthetic_pr::{
+    CommentConfig, CommentConfigError, CommentSyntax, CommentThread, CommentType, DiffHunk,
+    DiffLine, DiffLineType, FileChange, GitService, MarkerStatus, ParsedComment,
+};
 
 /// Parses AI insight comments from source code files for synthetic pull request generation.
 ///
@@ -9,27 +13,50 @@ use crate::synthetic_pr::{CommentThread, CommentType, ParsedComment, FileChange}
 /// - TODO: Future work items identified during implementation
 /// - FIXME: Known issues that need addressing
 ///
-/// Supports multiple comment syntaxes (// # <!-- -->) for cross-language compatibility.
+/// Further markers and the comment syntax of each language come from the repository's
+/// [`CommentConfig`], so e.g. `-- NOTE:` in SQL or `/* SAFETY: */` in C can be picked up too.
 pub struct CommentParser {
-    lightbulb_regex: Regex,
-    question_regex: Regex,
-    todo_regex: Regex,
-    fixme_regex: Regex,
+    /// Markers and the comment type they produce, longest marker first
+    markers: Vec<(String, CommentType)>,
+    config: CommentConfig,
 }
 
 impl CommentParser {
-    /// Creates a new CommentParser with pre-compiled regex patterns.
-    ///
-    /// Initializes regex patterns for detecting AI insight comments across
-    /// multiple programming languages and comment syntaxes.
+    /// Creates a new CommentParser with the built-in markers and comment syntax.
     pub fn new() -> Self {
-        Self {
-            // Match various comment styles: //, #, <!-- -->, etc.
-            lightbulb_regex: Regex::new(r"(?://|#|<!--)\s*💡\s*(.+?)(?:-->)?$").unwrap(),
-            question_regex: Regex::new(r"(?://|#|<!--)\s*❓\s*(.+?)(?:-->)?$").unwrap(),
-            todo_regex: Regex::new(r"(?://|#|<!--)\s*TODO:\s*(.+?)(?:-->)?$").unwrap(),
-            fixme_regex: Regex::new(r"(?://|#|<!--)\s*FIXME:\s*(.+?)(?:-->)?$").unwrap(),
-        }
+        Self::with_config(CommentConfig::default())
+    }
+
+    /// Creates a CommentParser recognizing the markers and comment syntax of `config`.
+    ///
+    /// # Arguments
+    /// * `config` - Marker rules and per-language comment syntax
+    pub fn with_config(config: CommentConfig) -> Self {
+        let mut markers = config.marker_rules();
+        // Prefer the most specific marker when one is a prefix of another (e.g. `TODO` and `TODO:`)
+        markers.sort_by_key(|(marker, _)| std::cmp::Reverse(marker.len()));
+        Self { markers, config }
+    }
+
+    /// Creates a CommentParser using the comment marker config file committed at `commit_oid`, if any.
+    ///
+    /// # Arguments
+    /// * `git_service` - Repository to read the config from
+    /// * `commit_oid` - Commit whose config applies, e.g. a review's base commit
+    ///
+    /// # Returns
+    /// * `Ok(CommentParser)` - Parser for the committed configuration
+    /// * `Err(CommentConfigError)` - Commit not found, or the config is invalid
+    pub fn for_commit(
+        git_service: &GitService,
+        commit_oid: Oid,
+    ) -> Result<Self, CommentConfigError> {
+        Ok(Self::with_config(CommentConfig::load(git_service, commit_oid)?))
+    }
+
+    /// Comment syntax used for a file, chosen by its extension.
+    pub(crate) fn syntax_for(&self, file_path: &str) -> CommentSyntax {
+        self.config.syntax_for(file_path)
     }
 
     /// Parse all AI insight comments from a single source file.
@@ -45,20 +72,19 @@ impl CommentParser {
     /// * `Err(std::io::Error)` - File read error
     pub fn parse_file(&self, file_path: &str) -> Result<Vec<CommentThread>, std::io::Error> {
         let content = std::fs::read_to_string(file_path)?;
-        let mut threads = Vec::new();
-
-        for (line_num, line) in content.lines().enumerate() {
-            if let Some(comment) = self.extract_comment(line) {
-                threads.push(CommentThread {
-                    thread_id: uuid::Uuid::new_v4().to_string(),
-                    file_path: file_path.to_string(),
-                    line_number: line_num as u32 + 1, // 1-indexed
-                    comment_type: comment.comment_type,
-                    content: comment.content,
-                    responses: vec![],
-                });
-            }
-        }
+        let syntax = self.config.syntax_for(file_path);
+        let lines: Vec<&str> = content.lines().collect();
+
+        let threads = self
+            .extract_comments(&lines, &syntax)
+            .into_iter()
+            .map(|extracted| {
+                // 1-indexed
+                let first = extracted.first as u32 + 1;
+                let last = extracted.last as u32 + 1;
+                comment_thread(file_path, first, last, extracted.comment)
+            })
+            .collect();
 
         Ok(threads)
     }
@@ -93,81 +119,385 @@ impl CommentParser {
         Ok(all_threads)
     }
 
-    /// Extract a single AI insight comment from a line of source code.
+    /// Extract the AI insight comments from consecutive lines of source code.
     ///
-    /// Matches against pre-compiled regex patterns for different comment types
-    /// and extracts the comment content while preserving type information.
+    /// A comment may span several lines: full-line comments right below a marker
+    /// comment (using the same comment token, up to an empty comment line or the next
+    /// marker) continue it, and a block comment continues up to its closing delimiter.
+    /// Markers are also found at the start of lines inside block comments and
+    /// docstrings, after any `*` decoration.
     ///
     /// # Arguments
-    /// * `line` - Single line of source code to analyze
+    /// * `lines` - Consecutive lines of one file
+    /// * `syntax` - Comment syntax of that file
     ///
     /// # Returns
-    /// * `Some(ParsedComment)` - AI insight comment found with type and content
-    /// * `None` - No AI insight comment detected on this line
-    fn extract_comment(&self, line: &str) -> Option<ParsedComment> {
-        if let Some(caps) = self.lightbulb_regex.captures(line) {
-            Some(ParsedComment {
-                comment_type: CommentType::Explanation,
-                content: caps[1].trim().to_string(),
-            })
-        } else if let Some(caps) = self.question_regex.captures(line) {
-            Some(ParsedComment {
-                comment_type: CommentType::Question,
-                content: caps[1].trim().to_string(),
-            })
-        } else if let Some(caps) = self.todo_regex.captures(line) {
-            Some(ParsedComment {
-                comment_type: CommentType::Todo,
-                content: caps[1].trim().to_string(),
-            })
-        } else if let Some(caps) = self.fixme_regex.captures(line) {
-            Some(ParsedComment {
-                comment_type: CommentType::Fixme,
-                content: caps[1].trim().to_string(),
-            })
-        } else {
-            None
+    /// * `Vec<ExtractedComment>` - Comments found, with their position in `lines`
+    pub(crate) fn extract_comments(&self, lines: &[&str], syntax: &CommentSyntax) -> Vec<ExtractedComment> {
+        let mut comments = Vec::new();
+        // Closing delimiter of the (marker-less) block comment the current line is in
+        let mut open_block: Option<&str> = None;
+        let mut i = 0;
+
+        while i < lines.len() {
+            let line = lines[i];
+            let (comment_type, mut content, last, start_column, end_column, enclosed) = if let Some(end) = open_block {
+                let (text, closed) = split_block_end(line, end);
+                if closed {
+                    open_block = None;
+                }
+                let Some((comment_type, first)) = self.match_marker(strip_decoration(text)) else {
+                    i += 1;
+                    continue;
+                };
+                let mut content = vec![first.to_string()];
+                let last = if closed {
+                    i
+                } else {
+                    open_block = None;
+                    continue_block(lines, i + 1, end, &mut content)
+                };
+                // Part of a larger block comment, whose closing delimiter isn't ours
+                let indent = line.len() - line.trim_start().len();
+                (comment_type.clone(), content, last, indent, lines[last].find(end), true)
+            } else if let Some(start) = self.comment_start(line, syntax) {
+                let mut content = vec![start.content.to_string()];
+                let (last, end_column) = match start.continuation {
+                    Continuation::None => (i, None),
+                    Continuation::Closed(end_column) => (i, Some(end_column)),
+                    Continuation::Line(token) => (self.continue_lines(lines, i + 1, token, &mut content), None),
+                    Continuation::Block(end) => {
+                        let last = continue_block(lines, i + 1, end, &mut content);
+                        (last, lines[last].find(end).map(|column| column + end.len()))
+                    }
+                };
+                (start.comment_type.clone(), content, last, start.column, end_column, false)
+            } else {
+                open_block = opened_block(line, syntax);
+                i += 1;
+                continue;
+            };
+
+            content.retain(|text| !text.is_empty());
+            if !content.is_empty() {
+                comments.push(ExtractedComment {
+                    first: i,
+                    last,
+                    start_column,
+                    end_column,
+                    enclosed,
+                    comment: ParsedComment {
+                        comment_type,
+                        content: content.join("\n"),
+                    },
+                });
+            }
+            i = last + 1;
         }
+
+        comments
+    }
+
+    /// Find an AI insight comment starting on a line of source code.
+    ///
+    /// Looks for a comment opening (line token or block start) followed by one of the
+    /// configured markers. Doc comment variants such as `///`, `//!`, `/**` or `;;;`
+    /// are accepted as well.
+    ///
+    /// # Returns
+    /// * `Some(CommentStart)` - Marker found, with the rest of the comment on this line
+    /// * `None` - No AI insight comment starts on this line
+    fn comment_start<'a>(&'a self, line: &'a str, syntax: &'a CommentSyntax) -> Option<CommentStart<'a>> {
+        // Every place a comment could start, leftmost first; at the same position the
+        // longer token wins (Lua's `--[[` over `--`)
+        let mut openings: Vec<(usize, &str, Option<&str>)> = Vec::new();
+        for token in &syntax.line {
+            openings.extend(line.match_indices(token.as_str()).map(|(i, _)| (i, token.as_str(), None)));
+        }
+        for block in &syntax.block {
+            openings.extend(
+                line.match_indices(block.start.as_str())
+                    .map(|(i, _)| (i, block.start.as_str(), Some(block.end.as_str()))),
+            );
+        }
+        openings.sort_by_key(|(i, token, _)| (*i, std::cmp::Reverse(token.len())));
+
+        for (position, token, block_end) in openings {
+            let after_opening = position + token.len();
+            let body = strip_opening(&line[after_opening..], token);
+            let Some((comment_type, content)) = self.match_marker(body) else {
+                continue;
+            };
+
+            let (content, continuation) = match block_end {
+                Some(end) => match content.split_once(end) {
+                    Some((content, _)) => {
+                        let close = line[after_opening..].find(end).unwrap_or_default();
+                        (content.trim(), Continuation::Closed(after_opening + close + end.len()))
+                    }
+                    None => (content, Continuation::Block(end)),
+                },
+                // Only comments on a line of their own continue on the next lines
+                None if line[..position].trim().is_empty() => (content, Continuation::Line(token)),
+                None => (content, Continuation::None),
+            };
+            return Some(CommentStart {
+                column: position,
+                comment_type,
+                content,
+                continuation,
+            });
+        }
+        None
+    }
+
+    /// Match a configured marker at the start of comment text.
+    ///
+    /// # Returns
+    /// * `Some((type, content))` - The marker's comment type and the trimmed text after it (possibly empty)
+    /// * `None` - The text doesn't start with a marker
+    fn match_marker<'a>(&'a self, text: &'a str) -> Option<(&'a CommentType, &'a str)> {
+        self.markers.iter().find_map(|(marker, comment_type)| {
+            text.strip_prefix(marker.as_str())
+                .map(|content| (comment_type, content.trim()))
+        })
+    }
+
+    /// Append the full-line comments continuing a marker comment.
+    ///
+    /// # Returns
+    /// * `usize` - Index of the last line of the comment
+    fn continue_lines(&self, lines: &[&str], from: usize, token: &str, content: &mut Vec<String>) -> usize {
+        let mut last = from - 1;
+        for line in &lines[from..] {
+            let Some(rest) = line.trim_start().strip_prefix(token) else {
+                break;
+            };
+            let text = strip_opening(rest, token).trim();
+            if text.is_empty() || self.match_marker(text).is_some() {
+                break;
+            }
+            content.push(text.to_string());
+            last += 1;
+        }
+        last
     }
 
     /// Parse AI insight comments from FileChange structures with diff hunks
     ///
-    /// Only extracts comments from lines that were actually changed (added or context),
-    /// making synthetic PRs focused on the specific modifications.
+    /// Only extracts comments from lines the diff shows, making synthetic PRs focused
+    /// on the specific modifications. Each thread's `marker_status` tells whether the
+    /// comment was introduced by the changes, was already there (on context lines, or
+    /// moved unchanged within the file), or was removed (resolved).
     ///
     /// # Arguments
     /// * `file_changes` - Array of FileChange with diff hunks
     ///
     /// # Returns
-    /// * `Ok(Vec<CommentThread>)` - Comment threads from changed lines only
+    /// * `Ok(Vec<CommentThread>)` - Comment threads from the diffed lines
     /// * `Err(Box<dyn std::error::Error>)` - Error parsing hunks
     pub fn parse_file_changes(&self, file_changes: &[FileChange]) -> Result<Vec<CommentThread>, Box<dyn std::error::Error>> {
         let mut all_threads = Vec::new();
         
         for file_change in file_changes {
+            let syntax = self.config.syntax_for(&file_change.path);
+            let mut threads = Vec::new();
+            let mut removed = Vec::new();
+
             for hunk in &file_change.hunks {
-                for line in &hunk.lines {
-                    // Only parse added or context lines (not removed lines)
-                    if matches!(line.line_type, crate::synthetic_pr::DiffLineType::Added | crate::synthetic_pr::DiffLineType::Context) {
-                        if let Some(comment) = self.extract_comment(&line.content) {
-                            all_threads.push(CommentThread {
-                                thread_id: format!("{}:{}", file_change.path, line.new_line_number.unwrap_or(0)),
-                                file_path: file_change.path.clone(),
-                                line_number: line.new_line_number.unwrap_or(0) as u32,
-                                comment_type: comment.comment_type,
-                                content: comment.content,
-                                responses: vec![],
-                            });
-                        }
+                // Added and context lines are consecutive lines of the new version of
+                // the file; removed and context lines are those of the old version
+                let new_side = Side::of(hunk, DiffLineType::Added, |line| line.new_line_number);
+                for extracted in self.extract_comments(&new_side.lines, &syntax) {
+                    let status = if new_side.changed(&extracted) {
+                        MarkerStatus::Introduced
+                    } else {
+                        MarkerStatus::PreExisting
+                    };
+                    threads.push(new_side.thread(&file_change.path, extracted, status));
+                }
+
+                let old_side = Side::of(hunk, DiffLineType::Removed, |line| line.old_line_number);
+                for extracted in self.extract_comments(&old_side.lines, &syntax) {
+                    // Only comments whose marker line was removed are gone
+                    if old_side.line_types[extracted.first] == DiffLineType::Removed {
+                        removed.push(old_side.thread(&file_change.path, extracted, MarkerStatus::Resolved));
                     }
                 }
             }
+
+            // A marker removed in one place and added unchanged in another was only moved
+            for resolved in removed {
+                let moved = threads.iter_mut().find(|thread| {
+                    thread.marker_status == Some(MarkerStatus::Introduced)
+                        && thread.comment_type == resolved.comment_type
+                        && thread.content == resolved.content
+                });
+                match moved {
+                    Some(thread) => thread.marker_status = Some(MarkerStatus::PreExisting),
+                    None => threads.push(resolved),
+                }
+            }
+            all_threads.append(&mut threads);
         }
         
         Ok(all_threads)
     }
 }
 
+/// The lines of one version of a file shown by a diff hunk.
+struct Side<'a> {
+    lines: Vec<&'a str>,
+    line_numbers: Vec<u32>,
+    line_types: Vec<DiffLineType>,
+}
+
+impl<'a> Side<'a> {
+    /// Context lines plus the lines of `changed` type, with their numbers in that version.
+    fn of(hunk: &'a DiffHunk, changed: DiffLineType, line_number: impl Fn(&DiffLine) -> Option<usize>) -> Self {
+        let mut side = Side {
+            lines: Vec::new(),
+            line_numbers: Vec::new(),
+            line_types: Vec::new(),
+        };
+        for line in &hunk.lines {
+            if line.line_type == changed || line.line_type == DiffLineType::Context {
+                side.lines.push(&line.content);
+                side.line_numbers.push(line_number(line).unwrap_or(0) as u32);
+                side.line_types.push(line.line_type.clone());
+            }
+        }
+        side
+    }
+
+    /// Whether any line of a comment was added or removed.
+    fn changed(&self, extracted: &ExtractedComment) -> bool {
+        self.line_types[extracted.first..=extracted.last]
+            .iter()
+            .any(|line_type| *line_type != DiffLineType::Context)
+    }
+
+    /// Comment thread for a comment found on this side.
+    fn thread(&self, file_path: &str, extracted: ExtractedComment, status: MarkerStatus) -> CommentThread {
+        let mut thread = comment_thread(
+            file_path,
+            self.line_numbers[extracted.first],
+            self.line_numbers[extracted.last],
+            extracted.comment,
+        );
+        thread.marker_status = Some(status);
+        thread
+    }
+}
+
+/// An AI insight comment found by [`CommentParser::extract_comments`].
+pub(crate) struct ExtractedComment {
+    /// Index of the comment's first line
+    pub first: usize,
+    /// Index of the comment's last line
+    pub last: usize,
+    /// Byte offset in the first line where the comment starts
+    pub start_column: usize,
+    /// Byte offset in the last line where the comment ends; None when it runs to the end of the line
+    pub end_column: Option<usize>,
+    /// True when the comment is part of a larger block comment (or docstring) without a marker;
+    /// `end_column` is then where that block's closing delimiter starts
+    pub enclosed: bool,
+    pub comment: ParsedComment,
+}
+
+/// AI insight comment starting on a line, as found by [`CommentParser::comment_start`].
+struct CommentStart<'a> {
+    /// Byte offset of the comment opening
+    column: usize,
+    comment_type: &'a CommentType,
+    /// Rest of the comment on this line
+    content: &'a str,
+    continuation: Continuation<'a>,
+}
+
+/// How an AI insight comment may continue past the line its marker is on.
+enum Continuation<'a> {
+    /// The comment runs to the end of this line
+    None,
+    /// Block comment closed on this line; byte offset just past its closing delimiter
+    Closed(usize),
+    /// Full-line comment; following full-line comments with this token continue it
+    Line(&'a str),
+    /// Block comment closed by this delimiter on a later line
+    Block(&'a str),
+}
+
+/// New comment thread for an AI insight comment on lines `first..=last` of a file.
+fn comment_thread(file_path: &str, first: u32, last: u32, comment: ParsedComment) -> CommentThread {
+    CommentThread {
+        thread_id: uuid::Uuid::new_v4().to_string(),
+        file_path: file_path.to_string(),
+        line_number: first,
+        end_line_number: (last > first).then_some(last),
+        comment_type: comment.comment_type,
+        content: comment.content,
+        responses: vec![],
+        commit_sha: None,
+        author: None,
+        anchor: None,
+        outdated: false,
+        marker_status: None,
+    }
+}
+
+/// Skip repeated opening characters and doc comment variants (`///`, `//!`, `/**`, `;;;`).
+fn strip_opening<'a>(text: &'a str, token: &str) -> &'a str {
+    text.trim_start_matches(|c: char| token.contains(c) || c == '!' || c == '*')
+        .trim_start()
+}
+
+/// Text of a line inside a block comment without its ` * ` decoration.
+fn strip_decoration(text: &str) -> &str {
+    text.trim().trim_start_matches('*').trim()
+}
+
+/// Split a line of a block comment at its closing delimiter.
+///
+/// # Returns
+/// * `(text, closed)` - The comment text on the line, and whether the comment ends on it
+fn split_block_end<'a>(line: &'a str, end: &str) -> (&'a str, bool) {
+    match line.split_once(end) {
+        Some((text, _)) => (text, true),
+        None => (line, false),
+    }
+}
+
+/// Append the lines of a block comment up to its closing delimiter.
+///
+/// # Returns
+/// * `usize` - Index of the line closing the comment, or of the last line if it isn't closed
+fn continue_block(lines: &[&str], from: usize, end: &str, content: &mut Vec<String>) -> usize {
+    for (i, line) in lines.iter().enumerate().skip(from) {
+        let (text, closed) = split_block_end(line, end);
+        content.push(strip_decoration(text).to_string());
+        if closed {
+            return i;
+        }
+    }
+    lines.len() - 1
+}
+
+/// Closing delimiter of a block comment opened (and not closed) on a line, if any.
+fn opened_block<'a>(line: &str, syntax: &'a CommentSyntax) -> Option<&'a str> {
+    let line_comment = syntax
+        .line
+        .iter()
+        .filter_map(|token| line.find(token.as_str()))
+        .min()
+        .unwrap_or(line.len());
+    syntax.block.iter().find_map(|block| {
+        let start = line.find(block.start.as_str())?;
+        let rest = &line[start + block.start.len()..];
+        (start <= line_comment && !rest.contains(block.end.as_str())).then_some(block.end.as_str())
+    })
+}
+
 impl Default for CommentParser {
     fn default() -> Self {
         Self::new()
@@ -178,11 +508,36 @@ impl Default for CommentParser {
 mod tests {
     use super::*;
 
+    fn rust() -> CommentSyntax {
+        CommentConfig::default().syntax_for("src/lib.rs")
+    }
+
+    fn python() -> CommentSyntax {
+        CommentConfig::default().syntax_for("app.py")
+    }
+
+    impl CommentParser {
+        /// The comment found on a single line, if any
+        fn extract_comment(&self, line: &str, syntax: &CommentSyntax) -> Option<ParsedComment> {
+            self.extract_comments(&[line], syntax).pop().map(|extracted| extracted.comment)
+        }
+    }
+
+    /// `(first, last, content)` of each comment found in `source`
+    fn extract_all(source: &str, syntax: &CommentSyntax) -> Vec<(usize, usize, String)> {
+        let lines: Vec<&str> = source.lines().collect();
+        CommentParser::new()
+            .extract_comments(&lines, syntax)
+            .into_iter()
+            .map(|extracted| (extracted.first, extracted.last, extracted.comment.content))
+            .collect()
+    }
+
     #[test]
     fn test_extract_lightbulb_comment() {
         let parser = CommentParser::new();
         
-        let comment = parser.extract_comment("// 💡 Using JWT instead of sessions for stateless design");
+        let comment = parser.extract_comment("// 💡 Using JWT instead of sessions for stateless design", &rust());
         assert!(comment.is_some());
         let comment = comment.unwrap();
         assert!(matches!(comment.comment_type, CommentType::Explanation));
@@ -193,7 +548,7 @@ mod tests {
     fn test_extract_question_comment() {
         let parser = CommentParser::new();
         
-        let comment = parser.extract_comment("# ❓ Should we add rate limiting here?");
+        let comment = parser.extract_comment("# ❓ Should we add rate limiting here?", &python());
         assert!(comment.is_some());
         let comment = comment.unwrap();
         assert!(matches!(comment.comment_type, CommentType::Question));
@@ -204,7 +559,7 @@ mod tests {
     fn test_extract_todo_comment() {
         let parser = CommentParser::new();
         
-        let comment = parser.extract_comment("// TODO: Add error handling for invalid tokens");
+        let comment = parser.extract_comment("// TODO: Add error handling for invalid tokens", &rust());
         assert!(comment.is_some());
         let comment = comment.unwrap();
         assert!(matches!(comment.comment_type, CommentType::Todo));
@@ -215,7 +570,121 @@ mod tests {
     fn test_no_comment() {
         let parser = CommentParser::new();
         
-        let comment = parser.extract_comment("let x = 42; // Regular comment");
+        let comment = parser.extract_comment("let x = 42; // Regular comment", &rust());
         assert!(comment.is_none());
     }
+
+    #[test]
+    fn test_extract_comment_in_other_syntaxes() {
+        let parser = CommentParser::new();
+        let syntax = |path| CommentConfig::default().syntax_for(path);
+
+        let cases = [
+            ("/* 💡 Cached to avoid a lookup per request */", "src/cache.c"),
+            ("SELECT 1; -- TODO: Use the index", "report.sql"),
+            (";; FIXME: Leaks on error", "init.el"),
+            ("% ❓ Is this bound tight?", "paper.tex"),
+            ("/// 💡 Builder keeps the config immutable", "src/lib.rs"),
+            ("\"\"\"💡 Retries are idempotent\"\"\"", "client.py"),
+            ("<!-- TODO: Document the flags -->", "README.md"),
+        ];
+        for (line, path) in cases {
+            let comment = parser.extract_comment(line, &syntax(path));
+            assert!(comment.is_some(), "no comment found in {line:?}");
+            assert!(!comment.unwrap().content.ends_with("*/"));
+        }
+
+        // `#` is not a comment in Rust
+        assert!(parser.extract_comment("#[doc = \"TODO: nope\"]", &syntax("src/lib.rs")).is_none());
+    }
+
+    #[test]
+    fn test_custom_markers() {
+        let config: CommentConfig = serde_json::from_str(
+            r#"{
+                "markers": [
+                    { "marker": "SAFETY:", "type": "safety" },
+                    { "marker": "NOTE:", "type": "explanation" }
+                ],
+                "languages": { "fnl": { "line": [";"] } }
+            }"#,
+        )
+        .unwrap();
+        let parser = CommentParser::with_config(config);
+        let syntax = |path| parser.config.syntax_for(path);
+
+        let comment = parser
+            .extract_comment("// SAFETY: The pointer is non-null here", &syntax("src/ffi.rs"))
+            .unwrap();
+        assert!(matches!(&comment.comment_type, CommentType::Custom(name) if name == "safety"));
+        assert_eq!(comment.content, "The pointer is non-null here");
+
+        let comment = parser
+            .extract_comment("; NOTE: Macros expand at compile time", &syntax("main.fnl"))
+            .unwrap();
+        assert!(matches!(comment.comment_type, CommentType::Explanation));
+
+        // Built-in markers still apply
+        assert!(parser.extract_comment("// TODO: Later", &syntax("src/ffi.rs")).is_some());
+    }
+
+    #[test]
+    fn test_continuation_lines() {
+        let source = "\
+fn retry() {
+    // 💡 Retries use exponential backoff so a flapping
+    // dependency isn't hammered while it recovers.
+    //
+    // Unrelated note.
+    let x = 1; // ❓ Is one enough?
+    // Not part of the question.
+    // TODO: Make the limit configurable
+    // FIXME: Jitter is missing
+}
+";
+        assert_eq!(
+            extract_all(source, &rust()),
+            [
+                (1, 2, "Retries use exponential backoff so a flapping\ndependency isn't hammered while it recovers.".to_string()),
+                (5, 5, "Is one enough?".to_string()),
+                (7, 7, "Make the limit configurable".to_string()),
+                (8, 8, "Jitter is missing".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_block_comments() {
+        let source = "\
+/* 💡 The cache is keyed by tenant
+ * so that evictions stay local. */
+int lookup(void);
+/**
+ * Frees the buffer.
+ *
+ * ❓ Should this also reset the length?
+ */
+void release(void);
+/* TODO: Inline */ int x;
+";
+        assert_eq!(
+            extract_all(source, &CommentConfig::default().syntax_for("cache.c")),
+            [
+                (0, 1, "The cache is keyed by tenant\nso that evictions stay local.".to_string()),
+                (6, 7, "Should this also reset the length?".to_string()),
+                (9, 9, "Inline".to_string()),
+            ]
+        );
+
+        let source = "\
+def fetch():
+    \"\"\"💡 Idempotent, so callers may retry
+    without checking for partial writes.
+    \"\"\"
+";
+        assert_eq!(
+            extract_all(source, &python()),
+            [(1, 3, "Idempotent, so callers may retry\nwithout checking for partial writes.".to_string())]
+        );
+    }
 }
@@ -0,0 +1,791 @@
+use std::fmt::Write;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::synthetic_pr::{
+    ChangeStatus, CommentThread, CommentType, DiffHunk, DiffLineType, FileChange, FileIndex,
+    MarkerStatus, ReviewCommit, ReviewState,
+};
+
+/// SHA used in the `From` line of patches for uncommitted changes, as `git format-patch` does for
+/// patches without a commit.
+const NULL_SHA: &str = "0000000000000000000000000000000000000000";
+
+/// Width the diffstat of a patch is fitted to, as `git format-patch` does for mail.
+const DIFFSTAT_WIDTH: usize = 72;
+
+/// Length blob ids are abbreviated to in `index` lines.
+const ABBREV_LEN: usize = 7;
+
+/// Format a review can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// `git format-patch`-style mboxrd with one patch per commit in the range
+    Mbox,
+    /// Markdown report with comment threads inlined after the hunks they refer to
+    Markdown,
+    /// SARIF 2.1.0 log of the TODO, FIXME and question threads
+    Sarif,
+}
+
+/// Render a review in the given export format.
+///
+/// Works from the stored review alone, so a review can be exported after the
+/// repository has moved on.
+///
+/// # Arguments
+/// * `review` - Review to export
+/// * `format` - Output format
+///
+/// # Returns
+/// * `String` - The exported document
+pub fn render_review(review: &ReviewState, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Mbox => render_mbox(review),
+        ExportFormat::Markdown => render_markdown(review),
+        ExportFormat::Sarif => render_sarif(review),
+    }
+}
+
+/// Render one patch per commit; reviews without commit information become a single patch.
+///
+/// The output is mboxrd: `From ` lines inside a message are quoted with `>`, so it is
+/// applied with `git am --patch-format=mboxrd`.
+fn render_mbox(review: &ReviewState) -> String {
+    let fallback;
+    let commits = if review.commits.is_empty() {
+        fallback = [ReviewCommit {
+            sha: None,
+            author: String::new(),
+            message: review.title.clone(),
+            timestamp: review.created_at,
+            files_changed: review.files_changed.clone(),
+        }];
+        &fallback[..]
+    } else {
+        &review.commits[..]
+    };
+
+    let mut out = String::new();
+    for (index, commit) in commits.iter().enumerate() {
+        let (subject, body) = match commit.message.split_once('\n') {
+            Some((subject, body)) => (subject, body.trim()),
+            None => (commit.message.as_str(), ""),
+        };
+        let author = if commit.author.is_empty() {
+            "Dialectic <dialectic@localhost>"
+        } else {
+            &commit.author
+        };
+        let numbering = if commits.len() > 1 {
+            format!("PATCH {}/{}", index + 1, commits.len())
+        } else {
+            "PATCH".to_string()
+        };
+
+        let _ = writeln!(
+            out,
+            "From {} Mon Sep 17 00:00:00 2001",
+            commit.sha.as_deref().unwrap_or(NULL_SHA)
+        );
+
+        let mut message = String::new();
+        let _ = writeln!(message, "From: {}", author);
+        let _ = writeln!(message, "Date: {}", commit.timestamp.to_rfc2822());
+        let _ = writeln!(message, "Subject: [{}] {}", numbering, subject);
+        message.push('\n');
+        if !body.is_empty() {
+            let _ = writeln!(message, "{}\n", body);
+        }
+        message.push_str("---\n");
+        render_diffstat(&mut message, &commit.files_changed);
+        message.push('\n');
+        for file in &commit.files_changed {
+            render_file_diff(&mut message, file);
+        }
+        message.push_str("-- \ndialectic\n\n");
+
+        for line in message.split_inclusive('\n') {
+            if line.trim_start_matches('>').starts_with("From ") {
+                out.push('>');
+            }
+            out.push_str(line);
+        }
+    }
+    out
+}
+
+/// Render a `git diff --stat`-style summary, with the bars scaled to fit [`DIFFSTAT_WIDTH`].
+fn render_diffstat(out: &mut String, files: &[FileChange]) {
+    let changes = |file: &FileChange| (file.additions + file.deletions) as usize;
+    let max_change = files.iter().map(changes).max().unwrap_or(0);
+    let number_width = max_change.to_string().len();
+
+    // Split the width between names and bars the way git does: when a line would be too
+    // long, the bars get at most 3/8 of it and long names are shortened
+    let mut name_width = files
+        .iter()
+        .map(|f| f.path.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut graph_width = max_change;
+    if name_width + number_width + 6 + graph_width > DIFFSTAT_WIDTH {
+        graph_width = graph_width.min(
+            (DIFFSTAT_WIDTH * 3 / 8)
+                .saturating_sub(number_width + 6)
+                .max(6),
+        );
+        let available = DIFFSTAT_WIDTH - number_width - 6 - graph_width;
+        if name_width > available {
+            name_width = available;
+        } else {
+            graph_width = DIFFSTAT_WIDTH - number_width - 6 - name_width;
+        }
+    }
+    let scale = |count: usize| {
+        if count == 0 {
+            0
+        } else {
+            1 + count * (graph_width - 1) / max_change
+        }
+    };
+
+    for file in files {
+        let (mut added, mut removed) = (file.additions as usize, file.deletions as usize);
+        if graph_width <= max_change {
+            // A file with both additions and deletions always shows one of each
+            let mut total = scale(added + removed);
+            if total < 2 && added > 0 && removed > 0 {
+                total = 2;
+            }
+            if added < removed {
+                added = scale(added);
+                removed = total - added;
+            } else {
+                removed = scale(removed);
+                added = total - removed;
+            }
+        }
+        let _ = writeln!(
+            out,
+            " {:name_width$} | {:>number_width$}{}{}{}",
+            shorten_path(&file.path, name_width),
+            changes(file),
+            if changes(file) > 0 { " " } else { "" },
+            "+".repeat(added),
+            "-".repeat(removed),
+        );
+    }
+
+    let additions: u32 = files.iter().map(|f| f.additions).sum();
+    let deletions: u32 = files.iter().map(|f| f.deletions).sum();
+    let _ = write!(out, " {} file{} changed", files.len(), plural(files.len()));
+    if additions > 0 || deletions == 0 {
+        let _ = write!(
+            out,
+            ", {} insertion{}(+)",
+            additions,
+            plural(additions as usize)
+        );
+    }
+    if deletions > 0 || additions == 0 {
+        let _ = write!(
+            out,
+            ", {} deletion{}(-)",
+            deletions,
+            plural(deletions as usize)
+        );
+    }
+    out.push('\n');
+
+    for file in files {
+        let Some(index) = &file.index else { continue };
+        let _ = match file.status {
+            ChangeStatus::Added => writeln!(out, " create mode {:o} {}", index.new_mode, file.path),
+            ChangeStatus::Deleted => {
+                writeln!(out, " delete mode {:o} {}", index.old_mode, file.path)
+            }
+            ChangeStatus::Modified if index.old_mode != index.new_mode => writeln!(
+                out,
+                " mode change {:o} => {:o} {}",
+                index.old_mode, index.new_mode, file.path
+            ),
+            ChangeStatus::Modified => Ok(()),
+        };
+    }
+}
+
+/// Shorten a path to `width` characters as git's diffstat does, keeping its end after `...`.
+fn shorten_path(path: &str, width: usize) -> String {
+    let len = path.chars().count();
+    if len <= width {
+        return path.to_string();
+    }
+    let tail: String = path.chars().skip(len - width + 3).collect();
+    // Start at a directory boundary when there is one
+    match tail.find('/') {
+        Some(slash) => format!("...{}", &tail[slash..]),
+        None => format!("...{}", tail),
+    }
+}
+
+fn render_file_diff(out: &mut String, file: &FileChange) {
+    let _ = writeln!(out, "diff --git a/{0} b/{0}", file.path);
+    if let Some(index) = &file.index {
+        render_index(out, file, index);
+    }
+    // Files without hunks (e.g. mode changes or empty files) have no `---`/`+++` lines
+    if file.hunks.is_empty() {
+        return;
+    }
+    let (old, new) = match file.status {
+        ChangeStatus::Added => ("/dev/null".to_string(), format!("b/{}", file.path)),
+        ChangeStatus::Deleted => (format!("a/{}", file.path), "/dev/null".to_string()),
+        ChangeStatus::Modified => (format!("a/{}", file.path), format!("b/{}", file.path)),
+    };
+    let _ = writeln!(out, "--- {}\n+++ {}", old, new);
+    for hunk in &file.hunks {
+        render_hunk(out, hunk);
+    }
+}
+
+/// Render the extended header lines giving a file's modes and blob ids.
+fn render_index(out: &mut String, file: &FileChange, index: &FileIndex) {
+    let null = "0".repeat(ABBREV_LEN);
+    let abbrev = |oid: &str| oid.get(..ABBREV_LEN).unwrap_or(oid).to_string();
+    let (old_oid, new_oid) = match file.status {
+        ChangeStatus::Added => {
+            let _ = writeln!(out, "new file mode {:o}", index.new_mode);
+            (Some(null), index.new_oid.as_deref().map(abbrev))
+        }
+        ChangeStatus::Deleted => {
+            let _ = writeln!(out, "deleted file mode {:o}", index.old_mode);
+            (index.old_oid.as_deref().map(abbrev), Some(null))
+        }
+        ChangeStatus::Modified => {
+            if index.old_mode != index.new_mode {
+                let _ = writeln!(
+                    out,
+                    "old mode {:o}\nnew mode {:o}",
+                    index.old_mode, index.new_mode
+                );
+            }
+            (
+                index.old_oid.as_deref().map(abbrev),
+                index.new_oid.as_deref().map(abbrev),
+            )
+        }
+    };
+
+    // Uncommitted files may not have a blob id; the index line is optional
+    let (Some(old_oid), Some(new_oid)) = (old_oid, new_oid) else {
+        return;
+    };
+    if old_oid == new_oid {
+        return;
+    }
+    let _ = write!(out, "index {}..{}", old_oid, new_oid);
+    if file.status == ChangeStatus::Modified && index.old_mode == index.new_mode {
+        let _ = write!(out, " {:o}", index.new_mode);
+    }
+    out.push('\n');
+}
+
+fn render_hunk(out: &mut String, hunk: &DiffHunk) {
+    let _ = writeln!(out, "{}", hunk.header);
+    for line in &hunk.lines {
+        let origin = match line.line_type {
+            DiffLineType::Added => '+',
+            DiffLineType::Removed => '-',
+            DiffLineType::Context => ' ',
+        };
+        let _ = writeln!(out, "{}{}", origin, line.content);
+        if line.missing_newline {
+            out.push_str("\\ No newline at end of file\n");
+        }
+    }
+}
+
+/// Render a report with each thread placed after the hunk showing its line.
+fn render_markdown(review: &ReviewState) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# {}\n", review.title);
+
+    render_description(&mut out, &review.description);
+
+    let additions: u32 = review.files_changed.iter().map(|f| f.additions).sum();
+    let deletions: u32 = review.files_changed.iter().map(|f| f.deletions).sum();
+    let _ = writeln!(
+        out,
+        "**Status:** {:?} · **Range:** `{}` · {} file{} changed, +{} −{}\n",
+        review.status,
+        review.commit_range,
+        review.files_changed.len(),
+        plural(review.files_changed.len()),
+        additions,
+        deletions,
+    );
+
+    let mut by_line: Vec<usize> = (0..review.comment_threads.len()).collect();
+    by_line.sort_by_key(|&index| review.comment_threads[index].line_number);
+
+    let mut placed = vec![false; review.comment_threads.len()];
+    for file in &review.files_changed {
+        let _ = writeln!(
+            out,
+            "## `{}` ({:?}, +{} −{})\n",
+            file.path, file.status, file.additions, file.deletions
+        );
+        for hunk in &file.hunks {
+            let mut diff = String::new();
+            render_hunk(&mut diff, hunk);
+            let _ = writeln!(out, "{}\n", fenced("diff", diff.trim_end()));
+
+            for &index in &by_line {
+                let thread = &review.comment_threads[index];
+                // Resolved markers refer to the old version of the file
+                let in_hunk = if thread.is_resolved() {
+                    hunk.contains_old_line(thread.line_number)
+                } else {
+                    hunk.contains_new_line(thread.line_number)
+                };
+                if !placed[index] && thread.file_path == file.path && in_hunk {
+                    placed[index] = true;
+                    render_thread(&mut out, thread);
+                }
+            }
+        }
+    }
+
+    let others: Vec<&CommentThread> = review
+        .comment_threads
+        .iter()
+        .zip(&placed)
+        .filter(|(_, placed)| !**placed)
+        .map(|(thread, _)| thread)
+        .collect();
+    if !others.is_empty() {
+        out.push_str("## Other comments\n\n");
+        for thread in others {
+            render_thread(&mut out, thread);
+        }
+    }
+
+    out
+}
+
+/// Render a review description: text as-is, anything else as a JSON block.
+pub(crate) fn render_description(out: &mut String, description: &serde_json::Value) {
+    match description {
+        serde_json::Value::Null => {}
+        serde_json::Value::String(description) if description.is_empty() => {}
+        serde_json::Value::String(description) => {
+            let _ = writeln!(out, "{}\n", description);
+        }
+        description => {
+            let json = serde_json::to_string_pretty(description).unwrap_or_default();
+            let _ = writeln!(out, "{}\n", fenced("json", &json));
+        }
+    }
+}
+
+/// Render a thread and its replies as a Markdown block quote.
+pub(crate) fn render_thread(out: &mut String, thread: &CommentThread) {
+    let (icon, label) = comment_type_label(&thread.comment_type);
+    let mut heading = format!(
+        "{} **{}** on `{}:{}`",
+        icon,
+        label,
+        thread.file_path,
+        line_label(thread)
+    );
+    if let Some(author) = &thread.author {
+        let _ = write!(heading, " by {}", author);
+    }
+    match thread.marker_status {
+        Some(MarkerStatus::PreExisting) => heading.push_str(" (pre-existing)"),
+        Some(MarkerStatus::Resolved) => heading.push_str(" (resolved)"),
+        Some(MarkerStatus::Introduced) | None => {}
+    }
+    if thread.outdated {
+        heading.push_str(" (outdated)");
+    }
+
+    let _ = writeln!(out, "> {}\n>", heading);
+    quote(out, &thread.content);
+    for response in &thread.responses {
+        let _ = writeln!(out, ">\n> **{}:**", response.author);
+        quote(out, &response.content);
+    }
+    out.push('\n');
+}
+
+/// Render the TODO, FIXME and question threads as SARIF results.
+fn render_sarif(review: &ReviewState) -> String {
+    let rules =
+        [CommentType::Todo, CommentType::Fixme, CommentType::Question].map(|comment_type| {
+            let (id, description) = sarif_rule(&comment_type).unwrap_or_default();
+            json!({
+                "id": id,
+                "shortDescription": { "text": description },
+            })
+        });
+
+    let results: Vec<serde_json::Value> = review
+        .comment_threads
+        .iter()
+        .filter_map(|thread| {
+            // Resolved markers are no longer in the code
+            if thread.is_resolved() {
+                return None;
+            }
+            let (rule_id, _) = sarif_rule(&thread.comment_type)?;
+            let level = match thread.comment_type {
+                CommentType::Fixme => "warning",
+                _ => "note",
+            };
+            Some(json!({
+                "ruleId": rule_id,
+                "level": level,
+                "message": { "text": thread.content },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": thread.file_path },
+                        "region": {
+                            "startLine": thread.line_number,
+                            "endLine": thread.line_range().end(),
+                        },
+                    },
+                }],
+                "partialFingerprints": { "dialecticThreadId": thread.thread_id },
+                "properties": {
+                    "outdated": thread.outdated,
+                    "responses": thread.responses.len(),
+                },
+            }))
+        })
+        .collect();
+
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "dialectic",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "properties": {
+                "reviewId": review.review_id,
+                "title": review.title,
+                "commitRange": review.commit_range,
+            },
+            "results": results,
+        }],
+    });
+    serde_json::to_string_pretty(&log).expect("JSON values always serialize")
+}
+
+/// SARIF rule id and description for the comment types that are exported as results.
+fn sarif_rule(comment_type: &CommentType) -> Option<(&'static str, &'static str)> {
+    match comment_type {
+        CommentType::Todo => Some(("todo", "TODO left for future work")),
+        CommentType::Fixme => Some(("fixme", "FIXME marking a known issue")),
+        CommentType::Question => Some(("question", "Question for the reviewer")),
+        CommentType::Explanation | CommentType::Comment | CommentType::Custom(_) => None,
+    }
+}
+
+/// Line of a thread, or its line range (e.g. `12-14`) when it spans several lines.
+fn line_label(thread: &CommentThread) -> String {
+    match thread.end_line_number {
+        Some(end) => format!("{}-{}", thread.line_number, end),
+        None => thread.line_number.to_string(),
+    }
+}
+
+/// Icon and display name of a comment type.
+pub(crate) fn comment_type_label(comment_type: &CommentType) -> (&'static str, &str) {
+    match comment_type {
+        CommentType::Explanation => ("💡", "Insight"),
+        CommentType::Question => ("❓", "Question"),
+        CommentType::Todo => ("📝", "TODO"),
+        CommentType::Fixme => ("🔧", "FIXME"),
+        CommentType::Comment => ("💬", "Comment"),
+        CommentType::Custom(name) => ("🏷️", name),
+    }
+}
+
+/// Wrap text in a code fence longer than any backtick run it contains.
+fn fenced(language: &str, text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{fence}{language}\n{text}\n{fence}")
+}
+
+fn quote(out: &mut String, text: &str) {
+    for line in text.lines() {
+        if line.is_empty() {
+            out.push_str(">\n");
+        } else {
+            let _ = writeln!(out, "> {}", line);
+        }
+    }
+}
+
+fn plural(count: usize) -> &'static str {
+    if count == 1 { "" } else { "s" }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synthetic_pr::{DiffLine, UserResponse};
+    use chrono::{TimeZone, Utc};
+
+    fn test_review() -> ReviewState {
+        let timestamp = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+        let file = FileChange {
+            path: "src/auth.rs".to_string(),
+            status: ChangeStatus::Modified,
+            additions: 2,
+            deletions: 1,
+            hunks: vec![DiffHunk {
+                header: "@@ -1,2 +1,3 @@".to_string(),
+                old_start: 1,
+                old_lines: 2,
+                new_start: 1,
+                new_lines: 3,
+                lines: vec![
+                    DiffLine {
+                        line_type: DiffLineType::Context,
+                        content: "fn check() {".to_string(),
+                        old_line_number: Some(1),
+                        new_line_number: Some(1),
+                        missing_newline: false,
+                    },
+                    DiffLine {
+                        line_type: DiffLineType::Removed,
+                        content: "    true".to_string(),
+                        old_line_number: Some(2),
+                        new_line_number: None,
+                        missing_newline: false,
+                    },
+                    DiffLine {
+                        line_type: DiffLineType::Added,
+                        content: "    // TODO: validate the token".to_string(),
+                        old_line_number: None,
+                        new_line_number: Some(2),
+                        missing_newline: false,
+                    },
+                    DiffLine {
+                        line_type: DiffLineType::Added,
+                        content: "    false".to_string(),
+                        old_line_number: None,
+                        new_line_number: Some(3),
+                        missing_newline: true,
+                    },
+                ],
+            }],
+            index: Some(FileIndex {
+                old_oid: Some("244f744c2500ef2c65c00ae0ba7c13131d704820".to_string()),
+                new_oid: Some("0672e510676c59767b228f57ea04b864ebb3d33f".to_string()),
+                old_mode: 0o100644,
+                new_mode: 0o100644,
+            }),
+            test_links: vec![],
+        };
+        let thread = |thread_id: &str, line_number, comment_type, content: &str| CommentThread {
+            thread_id: thread_id.to_string(),
+            ..CommentThread::for_test("src/auth.rs", line_number, comment_type, content)
+        };
+
+        let mut todo = thread("t1", 2, CommentType::Todo, "validate the token");
+        todo.responses.push(UserResponse {
+            author: "user".to_string(),
+            content: "Please do it now".to_string(),
+            timestamp,
+        });
+        let mut outdated = thread("t2", 40, CommentType::Fixme, "leaks on error");
+        outdated.outdated = true;
+
+        ReviewState {
+            title: "Harden auth".to_string(),
+            description: json!("Stop accepting every token."),
+            commit_range: "HEAD~1..HEAD".to_string(),
+            files_changed: vec![file.clone()],
+            commits: vec![ReviewCommit {
+                sha: Some("1234567890abcdef1234567890abcdef12345678".to_string()),
+                author: "Ada <ada@example.com>".to_string(),
+                message:
+                    "Harden auth\n\nReject tokens by default.\nFrom now on, callers must validate."
+                        .to_string(),
+                timestamp,
+                files_changed: vec![file],
+            }],
+            comment_threads: vec![
+                todo,
+                outdated,
+                thread("t3", 1, CommentType::Explanation, "entry point"),
+            ],
+            created_at: timestamp,
+            updated_at: timestamp,
+            ..ReviewState::for_test()
+        }
+    }
+
+    #[test]
+    fn test_render_mbox() {
+        expect_test::expect![[r#"
+            From 1234567890abcdef1234567890abcdef12345678 Mon Sep 17 00:00:00 2001
+            From: Ada <ada@example.com>
+            Date: Tue, 2 Jan 2024 03:04:05 +0000
+            Subject: [PATCH] Harden auth
+
+            Reject tokens by default.
+            >From now on, callers must validate.
+
+            ---
+             src/auth.rs | 3 ++-
+             1 file changed, 2 insertions(+), 1 deletion(-)
+
+            diff --git a/src/auth.rs b/src/auth.rs
+            index 244f744..0672e51 100644
+            --- a/src/auth.rs
+            +++ b/src/auth.rs
+            @@ -1,2 +1,3 @@
+             fn check() {
+            -    true
+            +    // TODO: validate the token
+            +    false
+            \ No newline at end of file
+            -- 
+            dialectic
+
+        "#]]
+        .assert_eq(&render_review(&test_review(), ExportFormat::Mbox));
+    }
+
+    #[test]
+    fn test_render_diffstat() {
+        let file = |path: &str, status, additions, deletions, modes: (u32, u32)| FileChange {
+            path: path.to_string(),
+            status,
+            additions,
+            deletions,
+            hunks: vec![],
+            index: Some(FileIndex {
+                old_oid: None,
+                new_oid: None,
+                old_mode: modes.0,
+                new_mode: modes.1,
+            }),
+            test_links: vec![],
+        };
+
+        // Bars are scaled to fit, and long names are shortened at a directory boundary
+        let mut out = String::from("---\n");
+        render_diffstat(
+            &mut out,
+            &[
+                file(
+                    "Cargo.lock",
+                    ChangeStatus::Modified,
+                    400,
+                    100,
+                    (0o100644, 0o100644),
+                ),
+                file("src/lib.rs", ChangeStatus::Added, 3, 0, (0, 0o100644)),
+                file(
+                    "src/some/deeply/nested/module/with/a/very/long/path/name.rs",
+                    ChangeStatus::Deleted,
+                    0,
+                    12,
+                    (0o100644, 0),
+                ),
+                file("run.sh", ChangeStatus::Modified, 0, 0, (0o100644, 0o100755)),
+            ],
+        );
+        expect_test::expect![[r#"
+            ---
+             Cargo.lock                                    | 500 ++++++++++++++----
+             src/lib.rs                                    |   3 +
+             .../module/with/a/very/long/path/name.rs      |  12 -
+             run.sh                                        |   0
+             4 files changed, 403 insertions(+), 112 deletions(-)
+             create mode 100644 src/lib.rs
+             delete mode 100644 src/some/deeply/nested/module/with/a/very/long/path/name.rs
+             mode change 100644 => 100755 run.sh
+        "#]]
+        .assert_eq(&out);
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        expect_test::expect![[r#"
+            # Harden auth
+
+            Stop accepting every token.
+
+            **Status:** Pending · **Range:** `HEAD~1..HEAD` · 1 file changed, +2 −1
+
+            ## `src/auth.rs` (Modified, +2 −1)
+
+            ```diff
+            @@ -1,2 +1,3 @@
+             fn check() {
+            -    true
+            +    // TODO: validate the token
+            +    false
+            \ No newline at end of file
+            ```
+
+            > 💡 **Insight** on `src/auth.rs:1`
+            >
+            > entry point
+
+            > 📝 **TODO** on `src/auth.rs:2`
+            >
+            > validate the token
+            >
+            > **user:**
+            > Please do it now
+
+            ## Other comments
+
+            > 🔧 **FIXME** on `src/auth.rs:40` (outdated)
+            >
+            > leaks on error
+
+        "#]]
+        .assert_eq(&render_review(&test_review(), ExportFormat::Markdown));
+    }
+
+    #[test]
+    fn test_render_sarif() {
+        let sarif: serde_json::Value =
+            serde_json::from_str(&render_review(&test_review(), ExportFormat::Sarif)).unwrap();
+        assert_eq!(sarif["version"], "2.1.0");
+
+        let run = &sarif["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 3);
+
+        // The insight is not a finding; TODO and FIXME are
+        let results = run["results"].as_array().unwrap();
+        let rules: Vec<_> = results
+            .iter()
+            .map(|r| r["ruleId"].as_str().unwrap())
+            .collect();
+        assert_eq!(rules, vec!["todo", "fixme"]);
+        assert_eq!(results[1]["level"], "warning");
+        let location = &results[0]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "src/auth.rs");
+        assert_eq!(location["region"]["startLine"], 2);
+        assert_eq!(results[1]["properties"]["outdated"], true);
+    }
+}
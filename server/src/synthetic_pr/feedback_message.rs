@@ -0,0 +1,702 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::synthetic_pr::feedback_templates::render_template;
+use crate::synthetic_pr::{
+    ApplySuggestionParams, CompletionAction, FeedbackData, FeedbackMode, FeedbackTemplates,
+    GitService, PollReviewFeedbackParams, RecordedFeedback, ReviewComment,
+    StripInsightCommentsParams, StripOptions, UpdateReviewAction, UpdateReviewParams,
+    parse_suggestion_blocks,
+};
+
+/// Lines shown above and below a commented line when the IDE sent no code context.
+const CONTEXT_LINES: usize = 2;
+
+/// `wait_seconds` in suggested `poll_review_feedback` calls.
+const SUGGESTED_POLL_WAIT_SECS: u64 = 60;
+
+// ANCHOR: feedback_result
+/// The user's feedback on a review and what the agent should do next.
+///
+/// Returned as JSON content alongside the instructions rendered from it, so agents
+/// can act on the feedback without parsing prose.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FeedbackResult {
+    pub review_id: String,
+    pub status: FeedbackStatus,
+    /// The user's feedback, oldest first
+    pub feedback: Vec<FeedbackItem>,
+    /// Tool calls the agent is expected to make next, in order
+    pub next_tool_calls: Vec<SuggestedToolCall>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedbackStatus {
+    /// The user left feedback
+    Feedback,
+    /// The review was shown in poll mode; feedback will be queued
+    AwaitingFeedback,
+    /// A poll found no feedback
+    NoFeedbackYet,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FeedbackItem {
+    /// The user commented on a line
+    Comment {
+        file_path: Option<String>,
+        line_number: Option<u32>,
+        comment: String,
+        /// Thread the comment was recorded in, to reply to
+        thread_id: Option<String>,
+        /// Lines around the commented line
+        code_context: Option<String>,
+        /// True when the comment suggests a change with a ```suggestion block,
+        /// which `apply_suggestion` can apply
+        #[serde(default)]
+        has_suggestion: bool,
+    },
+    /// The user completed the review
+    CompleteReview {
+        completion_action: CompletionAction,
+        additional_notes: Option<String>,
+    },
+}
+
+/// A tool call, with arguments ready to pass to the tool.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SuggestedToolCall {
+    pub tool: String,
+    pub arguments: serde_json::Value,
+}
+// ANCHOR_END: feedback_result
+
+impl SuggestedToolCall {
+    fn new(tool: &str, arguments: &impl Serialize) -> Self {
+        SuggestedToolCall {
+            tool: tool.to_string(),
+            arguments: serde_json::to_value(arguments).unwrap_or_default(),
+        }
+    }
+}
+
+impl FeedbackResult {
+    /// Feedback on a review, as delivered to the agent.
+    ///
+    /// A single comment or review completion gets its own message. Several comments
+    /// (e.g., a pending review submitted at once, or feedback queued between polls) are
+    /// delivered together; a review completion in the batch comes first, with the
+    /// comments to reply to after it.
+    ///
+    /// # Arguments
+    /// * `review_id` - Review the feedback is on
+    /// * `batch` - Feedback on the review, oldest first, with the threads it was recorded in
+    /// * `repo_path` - Repository the review belongs to, for reading code context (defaults to current directory)
+    /// * `feedback_mode` - Mode the review is shown in, used in the suggested tool calls
+    pub fn from_batch(
+        review_id: &str,
+        batch: &[RecordedFeedback],
+        repo_path: Option<&str>,
+        feedback_mode: FeedbackMode,
+    ) -> Self {
+        let workdir = GitService::new(repo_path.unwrap_or("."))
+            .ok()
+            .and_then(|git_service| git_service.workdir().map(Path::to_path_buf));
+
+        let feedback: Vec<FeedbackItem> = batch
+            .iter()
+            .map(|recorded| feedback_item(recorded, workdir.as_deref()))
+            .collect();
+
+        let mut result = FeedbackResult {
+            review_id: review_id.to_string(),
+            status: FeedbackStatus::Feedback,
+            feedback,
+            next_tool_calls: vec![],
+        };
+        result.next_tool_calls = result.suggest_tool_calls(repo_path, feedback_mode);
+        result
+    }
+
+    /// A review shown in poll mode, whose feedback is collected with `poll_review_feedback`.
+    pub fn awaiting_feedback(review_id: &str, repo_path: Option<&str>) -> Self {
+        Self::polling(review_id, repo_path, FeedbackStatus::AwaitingFeedback)
+    }
+
+    /// A poll that found no feedback on the review.
+    pub fn no_feedback_yet(review_id: &str, repo_path: Option<&str>) -> Self {
+        Self::polling(review_id, repo_path, FeedbackStatus::NoFeedbackYet)
+    }
+
+    fn polling(review_id: &str, repo_path: Option<&str>, status: FeedbackStatus) -> Self {
+        FeedbackResult {
+            review_id: review_id.to_string(),
+            status,
+            feedback: vec![],
+            next_tool_calls: vec![poll_call(review_id, repo_path)],
+        }
+    }
+
+    /// The last review completion in the feedback, should the user have completed the review more than once.
+    fn completion(&self) -> Option<(&CompletionAction, Option<&str>)> {
+        self.feedback.iter().rev().find_map(|item| match item {
+            FeedbackItem::CompleteReview {
+                completion_action,
+                additional_notes,
+            } => Some((completion_action, additional_notes.as_deref())),
+            FeedbackItem::Comment { .. } => None,
+        })
+    }
+
+    fn comments(&self) -> Vec<BatchComment<'_>> {
+        self.feedback.iter().filter_map(batch_comment).collect()
+    }
+
+    fn suggest_tool_calls(
+        &self,
+        repo_path: Option<&str>,
+        feedback_mode: FeedbackMode,
+    ) -> Vec<SuggestedToolCall> {
+        let update_review = |action| {
+            SuggestedToolCall::new(
+                "update_review",
+                &UpdateReviewParams {
+                    review_id: self.review_id.clone(),
+                    action,
+                    repo_path: repo_path.map(str::to_string),
+                    feedback_mode,
+                },
+            )
+        };
+
+        let mut calls = Vec::new();
+        let completion = self.completion();
+        match completion {
+            Some((CompletionAction::RequestChanges, _)) => {
+                // Changes the user spelled out can be applied as they are
+                for item in &self.feedback {
+                    if let FeedbackItem::Comment {
+                        thread_id: Some(thread_id),
+                        has_suggestion: true,
+                        ..
+                    } = item
+                    {
+                        calls.push(SuggestedToolCall::new(
+                            "apply_suggestion",
+                            &ApplySuggestionParams {
+                                review_id: self.review_id.clone(),
+                                suggestion_id: None,
+                                thread_id: Some(thread_id.clone()),
+                                repo_path: repo_path.map(str::to_string),
+                            },
+                        ));
+                    }
+                }
+                calls.push(update_review(UpdateReviewAction::Approve));
+            }
+            Some((CompletionAction::Checkpoint, _)) => {
+                calls.push(SuggestedToolCall::new(
+                    "strip_insight_comments",
+                    &StripInsightCommentsParams {
+                        review_id: Some(self.review_id.clone()),
+                        options: StripOptions::default(),
+                        // Preview first; the checkpoint template asks to apply once the diff looks right
+                        apply: false,
+                        repo_path: repo_path.map(str::to_string),
+                    },
+                ));
+                calls.push(update_review(UpdateReviewAction::Merge));
+            }
+            Some((CompletionAction::Return, _)) | None => {}
+        }
+
+        let comments = self.comments();
+        for comment in &comments {
+            let reply = match comment.thread_id {
+                Some(thread_id) => ReviewComment {
+                    thread_id: Some(thread_id.to_string()),
+                    file_path: None,
+                    line_number: None,
+                    body: "Your response text here".to_string(),
+                },
+                None => ReviewComment {
+                    thread_id: None,
+                    file_path: Some(comment.file_path.to_string()),
+                    line_number: Some(comment.line_number),
+                    body: "Your response text here".to_string(),
+                },
+            };
+            calls.push(update_review(UpdateReviewAction::AddComment {
+                comment: reply,
+            }));
+        }
+
+        // Once the comments are answered, keep the conversation going
+        if completion.is_none() && !comments.is_empty() {
+            calls.push(match feedback_mode {
+                FeedbackMode::Poll => poll_call(&self.review_id, repo_path),
+                FeedbackMode::Block | FeedbackMode::PendingReview => {
+                    update_review(UpdateReviewAction::WaitForFeedback)
+                }
+            });
+        }
+        calls
+    }
+
+    /// Render instructions for the LLM from the templates.
+    ///
+    /// # Returns
+    /// * `String` - Instructions for the LLM; empty if there is no feedback
+    pub fn render(&self, templates: &FeedbackTemplates) -> String {
+        let review_id = self.review_id.as_str();
+        match self.status {
+            FeedbackStatus::AwaitingFeedback => {
+                return render_template(&templates.awaiting_feedback, &[("review_id", review_id)]);
+            }
+            FeedbackStatus::NoFeedbackYet => {
+                return render_template(&templates.no_feedback_yet, &[("review_id", review_id)]);
+            }
+            FeedbackStatus::Feedback => {}
+        }
+
+        let comments = self.comments();
+        let comment_count = comments.len().to_string();
+        match (self.completion(), comments.as_slice()) {
+            (None, []) => String::new(),
+            (None, [comment]) => format_single_comment(templates, review_id, comment),
+            (None, _) => render_template(
+                &templates.comments,
+                &[
+                    ("review_id", review_id),
+                    ("comment_count", &comment_count),
+                    ("comments", &format_comments_by_file(&comments)),
+                ],
+            ),
+            (Some((completion_action, notes)), []) => {
+                format_completion(templates, review_id, completion_action, notes)
+            }
+            (Some((completion_action, notes)), _) => format!(
+                "{}\n\n{}",
+                format_completion(templates, review_id, completion_action, notes),
+                render_template(
+                    &templates.comments_after_completion,
+                    &[
+                        ("review_id", review_id),
+                        ("comment_count", &comment_count),
+                        ("comments", &format_comments_by_file(&comments)),
+                    ],
+                )
+            ),
+        }
+    }
+}
+
+fn poll_call(review_id: &str, repo_path: Option<&str>) -> SuggestedToolCall {
+    SuggestedToolCall::new(
+        "poll_review_feedback",
+        &PollReviewFeedbackParams {
+            review_id: review_id.to_string(),
+            wait_seconds: SUGGESTED_POLL_WAIT_SECS,
+            repo_path: repo_path.map(str::to_string),
+        },
+    )
+}
+
+fn feedback_item(recorded: &RecordedFeedback, workdir: Option<&Path>) -> FeedbackItem {
+    match &recorded.feedback.feedback {
+        FeedbackData::Comment {
+            file_path,
+            line_number,
+            comment_text,
+            context_lines,
+            ..
+        } => {
+            let code_context = match context_lines {
+                Some(lines) => Some(lines.join("\n")),
+                None => workdir
+                    .zip(file_path.as_deref())
+                    .zip(*line_number)
+                    .and_then(|((workdir, file_path), line_number)| {
+                        read_context(&workdir.join(file_path), line_number)
+                    }),
+            };
+            FeedbackItem::Comment {
+                file_path: file_path.clone(),
+                line_number: *line_number,
+                comment: comment_text.clone(),
+                thread_id: recorded.thread_id.clone(),
+                code_context,
+                has_suggestion: !parse_suggestion_blocks(comment_text).is_empty(),
+            }
+        }
+        FeedbackData::CompleteReview {
+            completion_action,
+            additional_notes,
+        } => FeedbackItem::CompleteReview {
+            completion_action: completion_action.clone(),
+            additional_notes: additional_notes.clone(),
+        },
+    }
+}
+
+/// A user comment from the feedback, ready to be rendered.
+struct BatchComment<'a> {
+    file_path: &'a str,
+    line_number: u32,
+    comment_text: &'a str,
+    context: Option<&'a str>,
+    thread_id: Option<&'a str>,
+    /// How the agent addresses its reply (`thread_id: '...'`, or a new thread at the same spot)
+    reply_target: String,
+}
+
+fn batch_comment(item: &FeedbackItem) -> Option<BatchComment<'_>> {
+    let FeedbackItem::Comment {
+        file_path,
+        line_number,
+        comment,
+        thread_id,
+        code_context,
+        ..
+    } = item
+    else {
+        return None;
+    };
+
+    let file_path = file_path.as_deref().unwrap_or("unknown file");
+    let line_number = line_number.unwrap_or(0);
+
+    // Reply in the user's thread; without one, start a thread at the same spot
+    let reply_target = match thread_id {
+        Some(thread_id) => format!("thread_id: '{}'", thread_id),
+        None => format!("file_path: '{}', line_number: {}", file_path, line_number),
+    };
+
+    Some(BatchComment {
+        file_path,
+        line_number,
+        comment_text: comment,
+        context: code_context.as_deref(),
+        thread_id: thread_id.as_deref(),
+        reply_target,
+    })
+}
+
+/// Lines around `line_number` (1-based) of a working tree file, numbered like an editor gutter.
+fn read_context(path: &Path, line_number: u32) -> Option<String> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let lines: Vec<&str> = content.lines().collect();
+    let index = (line_number as usize).checked_sub(1)?;
+    if index >= lines.len() {
+        return None;
+    }
+
+    let first = index.saturating_sub(CONTEXT_LINES);
+    let last = (index + CONTEXT_LINES).min(lines.len() - 1);
+    let width = (last + 1).to_string().len();
+    let context: Vec<String> = (first..=last)
+        .map(|i| format!("{:>width$} | {}", i + 1, lines[i]))
+        .collect();
+    Some(context.join("\n"))
+}
+
+fn format_single_comment(
+    templates: &FeedbackTemplates,
+    review_id: &str,
+    comment: &BatchComment,
+) -> String {
+    let context = comment
+        .context
+        .map(|context| format!("\n\nCode context:\n```\n{}\n```", context))
+        .unwrap_or_default();
+
+    render_template(
+        &templates.comment,
+        &[
+            ("review_id", review_id),
+            ("file_path", comment.file_path),
+            ("line_number", &comment.line_number.to_string()),
+            ("comment", comment.comment_text),
+            ("context", &context),
+            ("reply_target", &comment.reply_target),
+        ],
+    )
+}
+
+/// Comments grouped under a heading per file, files in path order and comments by line.
+fn format_comments_by_file(comments: &[BatchComment]) -> String {
+    let mut by_file: BTreeMap<&str, Vec<&BatchComment>> = BTreeMap::new();
+    for comment in comments {
+        by_file.entry(comment.file_path).or_default().push(comment);
+    }
+
+    let mut sections = Vec::new();
+    for (file_path, mut comments) in by_file {
+        comments.sort_by_key(|comment| comment.line_number);
+
+        let mut section = format!("### `{}`", file_path);
+        for comment in comments {
+            section.push_str(&format!(
+                "\n\nLine {} (reply with {}):\nUser comment: '{}'",
+                comment.line_number, comment.reply_target, comment.comment_text
+            ));
+            if let Some(context) = comment.context {
+                section.push_str(&format!("\n```\n{}\n```", context));
+            }
+        }
+        sections.push(section);
+    }
+    sections.join("\n\n")
+}
+
+fn format_completion(
+    templates: &FeedbackTemplates,
+    review_id: &str,
+    completion_action: &CompletionAction,
+    additional_notes: Option<&str>,
+) -> String {
+    let notes = additional_notes.unwrap_or("");
+
+    let notes_section = if !notes.is_empty() {
+        format!("\nAdditional notes: '{}'\n", notes)
+    } else {
+        String::new()
+    };
+
+    let template = match completion_action {
+        CompletionAction::RequestChanges => &templates.request_changes,
+        CompletionAction::Checkpoint => &templates.checkpoint,
+        CompletionAction::Return => &templates.return_to_agent,
+    };
+    render_template(
+        template,
+        &[("review_id", review_id), ("notes", &notes_section)],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synthetic_pr::UserFeedback;
+
+    fn comment(
+        file_path: &str,
+        line_number: u32,
+        text: &str,
+        thread_id: Option<&str>,
+    ) -> RecordedFeedback {
+        RecordedFeedback {
+            feedback: UserFeedback {
+                review_id: "review-1".to_string(),
+                feedback: FeedbackData::Comment {
+                    thread_id: None,
+                    file_path: Some(file_path.to_string()),
+                    line_number: Some(line_number),
+                    comment_text: text.to_string(),
+                    context_lines: None,
+                },
+            },
+            thread_id: thread_id.map(str::to_string),
+        }
+    }
+
+    fn render(batch: &[RecordedFeedback], repo_path: Option<&str>) -> String {
+        FeedbackResult::from_batch("review-1", batch, repo_path, FeedbackMode::Block)
+            .render(&FeedbackTemplates::default())
+    }
+
+    #[test]
+    fn test_single_comment_with_code_context() {
+        let workdir = tempfile::tempdir().unwrap();
+        git2::Repository::init(workdir.path()).unwrap();
+        std::fs::create_dir(workdir.path().join("src")).unwrap();
+        std::fs::write(
+            workdir.path().join("src/lib.rs"),
+            "fn a() {}\nfn b() {}\nfn c() {}\nfn d() {}\n",
+        )
+        .unwrap();
+
+        let message = render(
+            &[comment("src/lib.rs", 1, "Rename this", Some("t1"))],
+            workdir.path().to_str(),
+        );
+        expect_test::expect![[r#"
+            The user reviewed your code changes and left a comment on file `src/lib.rs` at line 1:
+
+            User comment: 'Rename this'
+
+            Code context:
+            ```
+            1 | fn a() {}
+            2 | fn b() {}
+            3 | fn c() {}
+            ```
+
+            Please analyze the user's feedback and prepare a thoughtful response addressing their concern. Do NOT modify any files on disk.
+
+            When ready, invoke the update_review tool with:
+            - review_id: 'review-1'
+            - action: AddComment
+            - comment: { thread_id: 't1', body: 'Your response text here' }
+
+            After responding, invoke update_review again with action: WaitForFeedback to continue the conversation."#]]
+        .assert_eq(&message);
+    }
+
+    #[test]
+    fn test_batch_grouped_by_file() {
+        let batch = [
+            comment("src/queue.rs", 40, "Off by one?", Some("t2")),
+            comment("src/cache.rs", 7, "Why 30 seconds?", None),
+            comment("src/queue.rs", 12, "Needs a test", Some("t1")),
+            RecordedFeedback {
+                feedback: UserFeedback {
+                    review_id: "review-1".to_string(),
+                    feedback: FeedbackData::CompleteReview {
+                        completion_action: CompletionAction::RequestChanges,
+                        additional_notes: None,
+                    },
+                },
+                thread_id: None,
+            },
+        ];
+
+        let message = render(&batch, Some("/nonexistent"));
+        expect_test::expect![[r#"
+            User completed their review and selected: 'Request agent to make changes'
+            Based on the review discussion, please implement the requested changes. You may now edit files as needed.
+
+            When finished, invoke: update_review(review_id: 'review-1', action: Approve)
+
+            The user left 3 comments during the review:
+
+            ### `src/cache.rs`
+
+            Line 7 (reply with file_path: 'src/cache.rs', line_number: 7):
+            User comment: 'Why 30 seconds?'
+
+            ### `src/queue.rs`
+
+            Line 12 (reply with thread_id: 't1'):
+            User comment: 'Needs a test'
+
+            Line 40 (reply with thread_id: 't2'):
+            User comment: 'Off by one?'
+
+            Reply to each one by invoking update_review(review_id: 'review-1', action: AddComment, comment: { <reply target shown with the comment>, body: 'Your response text here' })."#]]
+        .assert_eq(&message);
+
+        // Without a completion, the comments are the message
+        let message = render(&batch[..3], Some("/nonexistent"));
+        assert!(message.starts_with("The user reviewed your code changes and left 3 comments:"));
+        assert!(message.ends_with("with action: WaitForFeedback to continue the conversation."));
+    }
+
+    #[test]
+    fn test_structured_feedback_suggests_next_calls() {
+        let batch = [
+            comment("src/queue.rs", 12, "Needs a test", Some("t1")),
+            RecordedFeedback {
+                feedback: UserFeedback {
+                    review_id: "review-1".to_string(),
+                    feedback: FeedbackData::CompleteReview {
+                        completion_action: CompletionAction::Checkpoint,
+                        additional_notes: Some("Squash it".to_string()),
+                    },
+                },
+                thread_id: None,
+            },
+        ];
+
+        let result = FeedbackResult::from_batch(
+            "review-1",
+            &batch,
+            Some("/nonexistent"),
+            FeedbackMode::PendingReview,
+        );
+        expect_test::expect![[r#"
+            {
+              "review_id": "review-1",
+              "status": "feedback",
+              "feedback": [
+                {
+                  "kind": "comment",
+                  "file_path": "src/queue.rs",
+                  "line_number": 12,
+                  "comment": "Needs a test",
+                  "thread_id": "t1",
+                  "code_context": null,
+                  "has_suggestion": false
+                },
+                {
+                  "kind": "complete_review",
+                  "completion_action": "checkpoint",
+                  "additional_notes": "Squash it"
+                }
+              ],
+              "next_tool_calls": [
+                {
+                  "tool": "strip_insight_comments",
+                  "arguments": {
+                    "apply": false,
+                    "comment_types": [
+                      "Explanation",
+                      "Question"
+                    ],
+                    "keep_answered_questions": false,
+                    "repo_path": "/nonexistent",
+                    "review_id": "review-1"
+                  }
+                },
+                {
+                  "tool": "update_review",
+                  "arguments": {
+                    "action": "Merge",
+                    "feedback_mode": "pending_review",
+                    "repo_path": "/nonexistent",
+                    "review_id": "review-1"
+                  }
+                },
+                {
+                  "tool": "update_review",
+                  "arguments": {
+                    "action": {
+                      "AddComment": {
+                        "comment": {
+                          "body": "Your response text here",
+                          "file_path": null,
+                          "line_number": null,
+                          "thread_id": "t1"
+                        }
+                      }
+                    },
+                    "feedback_mode": "pending_review",
+                    "repo_path": "/nonexistent",
+                    "review_id": "review-1"
+                  }
+                }
+              ]
+            }"#]]
+        .assert_eq(&serde_json::to_string_pretty(&result).unwrap());
+    }
+
+    #[test]
+    fn test_client_templates_steer_rendering() {
+        let templates = FeedbackTemplates {
+            no_feedback_yet: "Nothing on {review_id}; ask again in a minute.".to_string(),
+            ..FeedbackTemplates::default()
+        };
+
+        let result = FeedbackResult::no_feedback_yet("review-1", None);
+        assert_eq!(
+            result.render(&templates),
+            "Nothing on review-1; ask again in a minute."
+        );
+        assert_eq!(result.next_tool_calls[0].tool, "poll_review_feedback");
+    }
+}
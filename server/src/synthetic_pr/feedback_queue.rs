@@ -0,0 +1,331 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+use crate::synthetic_pr::{FeedbackData, UserFeedback};
+
+/// Environment variable overriding how long the server waits for the user to act on a review.
+pub const REVIEW_TIMEOUT_ENV: &str = "DIALECTIC_REVIEW_TIMEOUT_SECS";
+
+/// Environment variable overriding the longest `poll_review_feedback` may wait.
+pub const MAX_POLL_WAIT_ENV: &str = "DIALECTIC_MAX_POLL_WAIT_SECS";
+
+// ANCHOR: feedback_mode
+/// How a review tool hands the user's feedback back to the agent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedbackMode {
+    /// Block the tool call until the user responds in the IDE
+    #[default]
+    Block,
+    /// Block until the user completes the review; the comments they leave meanwhile
+    /// are collected and delivered together
+    PendingReview,
+    /// Return right away; feedback is queued and collected with `poll_review_feedback`
+    Poll,
+}
+// ANCHOR_END: feedback_mode
+
+/// How long the server waits on the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeedbackTimeouts {
+    /// How long a review shown in the IDE waits for the user to respond
+    pub review: Duration,
+    /// Longest wait a single `poll_review_feedback` call may ask for
+    pub max_poll_wait: Duration,
+}
+
+impl Default for FeedbackTimeouts {
+    fn default() -> Self {
+        FeedbackTimeouts {
+            review: Duration::from_secs(3600),
+            max_poll_wait: Duration::from_secs(300),
+        }
+    }
+}
+
+impl FeedbackTimeouts {
+    /// Default timeouts, overridden by [`REVIEW_TIMEOUT_ENV`] and [`MAX_POLL_WAIT_ENV`] (in seconds).
+    ///
+    /// Unset or unparsable variables keep their default.
+    ///
+    /// # Arguments
+    /// * `var` - Looks up an environment variable, e.g. `|name| std::env::var(name).ok()`
+    pub fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        let seconds = |name: &str| {
+            var(name)
+                .and_then(|value| value.trim().parse().ok())
+                .map(Duration::from_secs)
+        };
+
+        let defaults = Self::default();
+        FeedbackTimeouts {
+            review: seconds(REVIEW_TIMEOUT_ENV).unwrap_or(defaults.review),
+            max_poll_wait: seconds(MAX_POLL_WAIT_ENV).unwrap_or(defaults.max_poll_wait),
+        }
+    }
+}
+
+/// User feedback waiting to be collected, per review.
+///
+/// Reviews shown in [`FeedbackMode::Poll`] are tracked here; feedback arriving for them
+/// is queued until `poll_review_feedback` drains it, and the review stops being tracked
+/// once the user's review completion has been drained. Feedback for reviews that aren't
+/// tracked (e.g., another MCP server's, since the daemon broadcasts every message)
+/// is refused.
+#[derive(Debug, Clone, Default)]
+pub struct FeedbackQueue {
+    pending: Arc<Mutex<HashMap<String, VecDeque<UserFeedback>>>>,
+    arrived: Arc<Notify>,
+}
+
+impl FeedbackQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start queueing feedback for a review; feedback already queued is kept.
+    pub fn track(&self, review_id: &str) {
+        self.pending
+            .lock()
+            .unwrap()
+            .entry(review_id.to_string())
+            .or_default();
+    }
+
+    /// True when feedback for the review is being queued.
+    pub fn is_tracked(&self, review_id: &str) -> bool {
+        self.pending.lock().unwrap().contains_key(review_id)
+    }
+
+    /// Queue feedback for its review.
+    ///
+    /// # Returns
+    /// * `true` - Feedback queued
+    /// * `false` - The review isn't tracked, so the feedback was dropped
+    pub fn push(&self, feedback: UserFeedback) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        let Some(queue) = pending.get_mut(&feedback.review_id) else {
+            return false;
+        };
+        queue.push_back(feedback);
+        drop(pending);
+
+        self.arrived.notify_waiters();
+        true
+    }
+
+    /// Take all feedback queued for a review, oldest first, without waiting.
+    ///
+    /// Once the user's review completion is taken, the review is no longer tracked.
+    pub fn drain(&self, review_id: &str) -> Vec<UserFeedback> {
+        let mut pending = self.pending.lock().unwrap();
+        let feedback: Vec<_> = pending
+            .get_mut(review_id)
+            .map(|queue| queue.drain(..).collect())
+            .unwrap_or_default();
+
+        let completed = feedback
+            .iter()
+            .any(|f| matches!(f.feedback, FeedbackData::CompleteReview { .. }));
+        if completed {
+            pending.remove(review_id);
+        }
+        feedback
+    }
+
+    /// Take all feedback queued for a review, waiting up to `wait` for some to arrive.
+    ///
+    /// # Arguments
+    /// * `review_id` - Review to collect feedback for
+    /// * `wait` - How long to wait when nothing is queued yet; zero returns right away
+    ///
+    /// # Returns
+    /// * `Vec<UserFeedback>` - Queued feedback, oldest first; empty if none arrived in time
+    pub async fn wait(&self, review_id: &str, wait: Duration) -> Vec<UserFeedback> {
+        let deadline = tokio::time::Instant::now() + wait;
+        loop {
+            // Register for notifications before checking, so a push in between isn't missed
+            let arrived = self.arrived.notified();
+            tokio::pin!(arrived);
+            arrived.as_mut().enable();
+
+            let feedback = self.drain(review_id);
+            if !feedback.is_empty() {
+                return feedback;
+            }
+            if tokio::time::timeout_at(deadline, arrived).await.is_err() {
+                return vec![];
+            }
+        }
+    }
+}
+
+/// User feedback, with the comment thread it was recorded in.
+#[derive(Debug, Clone)]
+pub struct RecordedFeedback {
+    pub feedback: UserFeedback,
+    /// Thread the comment was recorded in; None for review completions, or when recording failed
+    pub thread_id: Option<String>,
+}
+
+/// Comments left on reviews in [`FeedbackMode::PendingReview`], held until the user
+/// completes the review.
+#[derive(Debug, Clone, Default)]
+pub struct PendingComments {
+    comments: Arc<Mutex<HashMap<String, Vec<RecordedFeedback>>>>,
+}
+
+impl PendingComments {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold a recorded comment until its review is completed.
+    pub fn add(&self, comment: RecordedFeedback) {
+        self.comments
+            .lock()
+            .unwrap()
+            .entry(comment.feedback.review_id.clone())
+            .or_default()
+            .push(comment);
+    }
+
+    /// Number of comments held for a review.
+    pub fn len(&self, review_id: &str) -> usize {
+        self.comments
+            .lock()
+            .unwrap()
+            .get(review_id)
+            .map_or(0, Vec::len)
+    }
+
+    /// Take the comments held for a review, oldest first.
+    pub fn take(&self, review_id: &str) -> Vec<RecordedFeedback> {
+        self.comments
+            .lock()
+            .unwrap()
+            .remove(review_id)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synthetic_pr::CompletionAction;
+    use std::collections::BTreeMap;
+
+    fn feedback(review_id: &str, comment_text: &str) -> UserFeedback {
+        UserFeedback {
+            review_id: review_id.to_string(),
+            feedback: FeedbackData::Comment {
+                thread_id: None,
+                file_path: Some("src/lib.rs".to_string()),
+                line_number: Some(3),
+                comment_text: comment_text.to_string(),
+                context_lines: None,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queue_per_review() {
+        let queue = FeedbackQueue::new();
+        assert!(!queue.push(feedback("other-server", "Not ours")));
+
+        queue.track("review-1");
+        queue.track("review-2");
+        assert!(queue.push(feedback("review-1", "First")));
+        assert!(queue.push(feedback("review-2", "Elsewhere")));
+        assert!(queue.push(feedback("review-1", "Second")));
+
+        let drained = queue.wait("review-1", Duration::ZERO).await;
+        let texts: Vec<_> = drained
+            .iter()
+            .map(|f| match &f.feedback {
+                FeedbackData::Comment { comment_text, .. } => comment_text.as_str(),
+                FeedbackData::CompleteReview { .. } => "",
+            })
+            .collect();
+        assert_eq!(texts, ["First", "Second"]);
+        assert!(queue.drain("review-1").is_empty());
+        assert_eq!(queue.drain("review-2").len(), 1);
+        assert!(queue.is_tracked("review-1"));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_feedback() {
+        let queue = FeedbackQueue::new();
+        queue.track("review-1");
+
+        // Nothing arrives: a clean empty result once the wait is over
+        assert!(
+            queue
+                .wait("review-1", Duration::from_millis(20))
+                .await
+                .is_empty()
+        );
+
+        let sender = queue.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            sender.push(UserFeedback {
+                review_id: "review-1".to_string(),
+                feedback: FeedbackData::CompleteReview {
+                    completion_action: CompletionAction::Checkpoint,
+                    additional_notes: None,
+                },
+            });
+        });
+        let arrived = queue.wait("review-1", Duration::from_secs(10)).await;
+        assert_eq!(arrived.len(), 1);
+
+        // The review is complete: later feedback for it is refused
+        assert!(!queue.is_tracked("review-1"));
+        assert!(!queue.push(feedback("review-1", "Too late")));
+    }
+
+    #[test]
+    fn test_pending_comments_per_review() {
+        let pending = PendingComments::new();
+        for (review_id, text) in [
+            ("review-1", "First"),
+            ("review-2", "Other"),
+            ("review-1", "Second"),
+        ] {
+            pending.add(RecordedFeedback {
+                feedback: feedback(review_id, text),
+                thread_id: None,
+            });
+        }
+
+        assert_eq!(pending.len("review-1"), 2);
+        assert_eq!(pending.take("review-1").len(), 2);
+        assert_eq!(pending.len("review-1"), 0);
+        assert_eq!(pending.take("review-2").len(), 1);
+    }
+
+    #[test]
+    fn test_timeouts_from_env() {
+        let env = BTreeMap::from([
+            (REVIEW_TIMEOUT_ENV, "90"),
+            (MAX_POLL_WAIT_ENV, "not a number"),
+        ]);
+        let timeouts = FeedbackTimeouts::from_env(|name| env.get(name).map(|v| v.to_string()));
+
+        assert_eq!(timeouts.review, Duration::from_secs(90));
+        assert_eq!(
+            timeouts.max_poll_wait,
+            FeedbackTimeouts::default().max_poll_wait
+        );
+        assert_eq!(
+            FeedbackTimeouts::from_env(|_| None),
+            FeedbackTimeouts::default()
+        );
+    }
+}
@@ -0,0 +1,322 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Feedback instruction templates file, relative to the user's configuration directory.
+///
+/// The templates steer the agent, so they come from the user's configuration and never
+/// from the repository under review.
+pub const FEEDBACK_TEMPLATES_FILE: &str = "socratic-shell/feedback-templates.json";
+
+/// Environment variable naming a feedback templates file to use instead.
+pub const FEEDBACK_TEMPLATES_ENV: &str = "DIALECTIC_FEEDBACK_TEMPLATES";
+
+/// Errors that can occur while loading the feedback instruction templates.
+#[derive(Error, Debug)]
+pub enum FeedbackTemplatesError {
+    #[error("Failed to read feedback templates: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid feedback templates: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Instructions given to the agent along with the user's feedback.
+///
+/// Each template is plain text with `{placeholder}`s filled in when rendering; text in
+/// braces that isn't a placeholder is kept as is. Placeholders available everywhere:
+/// `{review_id}`. Per template:
+/// * `comment`: `{file_path}`, `{line_number}`, `{comment}`, `{context}` (a code block, or empty), `{reply_target}`
+/// * `comments`, `comments_after_completion`: `{comment_count}`, `{comments}` (grouped by file)
+/// * `request_changes`, `checkpoint`, `return_to_agent`: `{notes}` (the user's notes, or empty)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedbackTemplates {
+    /// A single comment
+    pub comment: String,
+    /// Several comments delivered together
+    pub comments: String,
+    /// Comments delivered with a review completion, after its instructions
+    pub comments_after_completion: String,
+    /// The user asked for changes
+    pub request_changes: String,
+    /// The user asked to checkpoint the work
+    pub checkpoint: String,
+    /// The user returned to the agent without a request
+    pub return_to_agent: String,
+    /// A review was shown in poll mode
+    pub awaiting_feedback: String,
+    /// A poll found no feedback
+    pub no_feedback_yet: String,
+}
+
+impl Default for FeedbackTemplates {
+    fn default() -> Self {
+        FeedbackTemplates {
+            comment: "The user reviewed your code changes and left a comment on file `{file_path}` at line {line_number}:\n\n\
+                User comment: '{comment}'{context}\n\n\
+                Please analyze the user's feedback and prepare a thoughtful response addressing their concern. \
+                Do NOT modify any files on disk.\n\n\
+                When ready, invoke the update_review tool with:\n\
+                - review_id: '{review_id}'\n\
+                - action: AddComment\n\
+                - comment: { {reply_target}, body: 'Your response text here' }\n\n\
+                After responding, invoke update_review again with action: WaitForFeedback to continue the conversation."
+                .to_string(),
+            comments: "The user reviewed your code changes and left {comment_count} comments:\n\n{comments}\n\n\
+                Please analyze the user's feedback and prepare a thoughtful response to each comment. \
+                Do NOT modify any files on disk.\n\n\
+                Reply to each comment by invoking the update_review tool with:\n\
+                - review_id: '{review_id}'\n\
+                - action: AddComment\n\
+                - comment: { <reply target shown with the comment>, body: 'Your response text here' }\n\n\
+                After responding to all of them, invoke update_review again with action: WaitForFeedback to continue the conversation."
+                .to_string(),
+            comments_after_completion: "The user left {comment_count} comments during the review:\n\n{comments}\n\n\
+                Reply to each one by invoking update_review(review_id: '{review_id}', action: AddComment, \
+                comment: { <reply target shown with the comment>, body: 'Your response text here' })."
+                .to_string(),
+            request_changes: "User completed their review and selected: 'Request agent to make changes'{notes}\n\
+                Based on the review discussion, please implement the requested changes. \
+                You may now edit files as needed.\n\n\
+                When finished, invoke: update_review(review_id: '{review_id}', action: Approve)"
+                .to_string(),
+            checkpoint: "User completed their review and selected: 'Request agent to checkpoint this work'{notes}\n\
+                Please commit the current changes and document the work completed. \
+                Before committing, preview removing the review's insight comments with \
+                strip_insight_comments(review_id: '{review_id}', apply: false), \
+                then re-run it with apply: true once the diff looks right.\n\n\
+                When finished, invoke: update_review(review_id: '{review_id}', action: Merge)"
+                .to_string(),
+            return_to_agent: "User completed their review and selected: 'Return to agent without explicit request'{notes}\n\
+                The review is complete. You may proceed as you see fit."
+                .to_string(),
+            awaiting_feedback: "The review is shown to the user in their IDE. Their feedback will be queued.\n\n\
+                review_id: '{review_id}'\n\n\
+                You may continue with other work. To collect the feedback, invoke \
+                poll_review_feedback(review_id: '{review_id}', wait_seconds: N); it returns right away \
+                with 0 seconds, or waits up to N seconds for feedback to arrive."
+                .to_string(),
+            no_feedback_yet: "No feedback yet on review '{review_id}'. \
+                Invoke poll_review_feedback(review_id: '{review_id}', wait_seconds: N) again later."
+                .to_string(),
+        }
+    }
+}
+
+// ANCHOR: feedback_templates_config
+/// Contents of [`FEEDBACK_TEMPLATES_FILE`].
+///
+/// Templates left out keep their built-in text. `clients` is keyed by the name the
+/// MCP client reports when it connects (matched case-insensitively) and overrides
+/// `default` for that client.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct FeedbackTemplatesConfig {
+    /// Templates for every client
+    #[serde(default)]
+    pub default: TemplateOverrides,
+    /// Templates for specific clients, e.g. `"claude-code"`
+    #[serde(default)]
+    pub clients: BTreeMap<String, TemplateOverrides>,
+}
+
+/// Templates replacing the built-in ones; see [`FeedbackTemplates`] for their placeholders.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct TemplateOverrides {
+    pub comment: Option<String>,
+    pub comments: Option<String>,
+    pub comments_after_completion: Option<String>,
+    pub request_changes: Option<String>,
+    pub checkpoint: Option<String>,
+    pub return_to_agent: Option<String>,
+    pub awaiting_feedback: Option<String>,
+    pub no_feedback_yet: Option<String>,
+}
+// ANCHOR_END: feedback_templates_config
+
+impl FeedbackTemplates {
+    /// Where the user's templates are: [`FEEDBACK_TEMPLATES_ENV`] if set, otherwise
+    /// [`FEEDBACK_TEMPLATES_FILE`] in `$XDG_CONFIG_HOME` (or `~/.config`).
+    ///
+    /// # Arguments
+    /// * `var` - Looks up an environment variable, e.g. `|name| std::env::var(name).ok()`
+    ///
+    /// # Returns
+    /// * `Some(PathBuf)` - The templates file, which may not exist
+    /// * `None` - No configuration directory could be found
+    pub fn config_path(var: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+        let set = |name: &str| var(name).filter(|value| !value.is_empty());
+        if let Some(path) = set(FEEDBACK_TEMPLATES_ENV) {
+            return Some(PathBuf::from(path));
+        }
+        let config_dir = match set("XDG_CONFIG_HOME") {
+            Some(config_dir) => PathBuf::from(config_dir),
+            None => PathBuf::from(set("HOME")?).join(".config"),
+        };
+        Some(config_dir.join(FEEDBACK_TEMPLATES_FILE))
+    }
+
+    /// Templates for a client, from the user's templates file.
+    ///
+    /// # Arguments
+    /// * `path` - Templates file, usually [`FeedbackTemplates::config_path`]
+    /// * `client_name` - Name the MCP client reported when it connected, if known
+    ///
+    /// # Returns
+    /// * `Ok(FeedbackTemplates)` - Built-in templates with the configured ones applied
+    /// * `Err(FeedbackTemplatesError)` - File unreadable or invalid
+    pub fn load(path: &Path, client_name: Option<&str>) -> Result<Self, FeedbackTemplatesError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let config: FeedbackTemplatesConfig =
+            serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        Ok(Self::for_client(&config, client_name))
+    }
+
+    /// Templates for a client: the built-in ones, then the config's defaults, then its client's.
+    pub fn for_client(config: &FeedbackTemplatesConfig, client_name: Option<&str>) -> Self {
+        let mut templates = Self::default();
+        templates.apply(&config.default);
+
+        let client = client_name.and_then(|name| {
+            config
+                .clients
+                .iter()
+                .find(|(client, _)| client.eq_ignore_ascii_case(name))
+        });
+        if let Some((_, overrides)) = client {
+            templates.apply(overrides);
+        }
+        templates
+    }
+
+    fn apply(&mut self, overrides: &TemplateOverrides) {
+        let fields = [
+            (&mut self.comment, &overrides.comment),
+            (&mut self.comments, &overrides.comments),
+            (
+                &mut self.comments_after_completion,
+                &overrides.comments_after_completion,
+            ),
+            (&mut self.request_changes, &overrides.request_changes),
+            (&mut self.checkpoint, &overrides.checkpoint),
+            (&mut self.return_to_agent, &overrides.return_to_agent),
+            (&mut self.awaiting_feedback, &overrides.awaiting_feedback),
+            (&mut self.no_feedback_yet, &overrides.no_feedback_yet),
+        ];
+        for (template, replacement) in fields {
+            if let Some(replacement) = replacement {
+                *template = replacement.clone();
+            }
+        }
+    }
+}
+
+/// Fill in a template's `{placeholder}`s in a single pass, so placeholder-like text
+/// inside the values (e.g., a user comment mentioning `{review_id}`) is left alone.
+pub(crate) fn render_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after.find('}').and_then(|end| {
+            values
+                .iter()
+                .find(|(name, _)| *name == &after[..end])
+                .map(|(_, value)| (end, *value))
+        });
+        match value {
+            Some((end, value)) => {
+                rendered.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                rendered.push('{');
+                rest = after;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_templates_override_defaults() {
+        let config: FeedbackTemplatesConfig = serde_json::from_str(
+            r#"{
+                "default": { "return_to_agent": "Done with {review_id}." },
+                "clients": {
+                    "Claude-Code": { "return_to_agent": "Review {review_id} finished; carry on." }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let other = FeedbackTemplates::for_client(&config, Some("cursor"));
+        assert_eq!(other.return_to_agent, "Done with {review_id}.");
+        assert_eq!(other.checkpoint, FeedbackTemplates::default().checkpoint);
+
+        let claude = FeedbackTemplates::for_client(&config, Some("claude-code"));
+        assert_eq!(
+            render_template(&claude.return_to_agent, &[("review_id", "r1")]),
+            "Review r1 finished; carry on."
+        );
+    }
+
+    #[test]
+    fn test_config_path() {
+        let path = |vars: &[(&str, &str)]| {
+            let vars: BTreeMap<String, String> = vars
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect();
+            FeedbackTemplates::config_path(|name| vars.get(name).cloned())
+        };
+
+        assert_eq!(
+            path(&[("HOME", "/home/me")]),
+            Some(PathBuf::from(
+                "/home/me/.config/socratic-shell/feedback-templates.json"
+            ))
+        );
+        assert_eq!(
+            path(&[("HOME", "/home/me"), ("XDG_CONFIG_HOME", "/cfg")]),
+            Some(PathBuf::from("/cfg/socratic-shell/feedback-templates.json"))
+        );
+        assert_eq!(
+            path(&[
+                ("HOME", "/home/me"),
+                (FEEDBACK_TEMPLATES_ENV, "/etc/templates.json")
+            ]),
+            Some(PathBuf::from("/etc/templates.json"))
+        );
+        assert_eq!(path(&[]), None);
+    }
+
+    #[test]
+    fn test_render_keeps_other_braces() {
+        assert_eq!(
+            render_template(
+                "comment: { {reply_target}, body: '...' }",
+                &[("reply_target", "thread_id: 't1'")]
+            ),
+            "comment: { thread_id: 't1', body: '...' }"
+        );
+        assert_eq!(
+            render_template(
+                "{comments} on {review_id}",
+                &[("comments", "see {review_id}"), ("review_id", "r1")]
+            ),
+            "see {review_id} on r1"
+        );
+    }
+}
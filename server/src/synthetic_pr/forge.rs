@@ -0,0 +1,605 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::future::Future;
+
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use thiserror::Error;
+
+use crate::synthetic_pr::export::{comment_type_label, render_description, render_thread};
+use crate::synthetic_pr::{CommentThread, CommentType, ReviewState, UserResponse};
+
+/// Default base URL of the GitHub REST API.
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+
+/// Errors that can occur while publishing a review to a forge.
+#[derive(Error, Debug)]
+pub enum ForgeError {
+    #[error("Request to forge failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Forge rejected the request ({status}): {message}")]
+    Api { status: u16, message: String },
+
+    /// The pull request exists but its review comments were not attached; publishing
+    /// again reuses the pull request.
+    #[error(
+        "Pull request #{} ({}) was opened, but its review comments were not published: {source}",
+        pull_request.number,
+        pull_request.url
+    )]
+    Comments {
+        pull_request: PublishedPullRequest,
+        source: Box<ForgeError>,
+    },
+}
+
+/// Branches a pull request is opened between.
+///
+/// Both must already exist on the forge; publishing does not push anything.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PullRequestBranches {
+    /// Branch the changes would be merged into (e.g., "main")
+    pub base: String,
+    /// Branch containing the reviewed commits
+    pub head: String,
+}
+
+/// A review comment attached to a line of the pull request's diff.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForgeComment {
+    pub path: String,
+    /// Line (1-based) in the new version of the file; the last line of a multi-line comment
+    pub line: u32,
+    /// First line of a multi-line comment
+    #[serde(default)]
+    pub start_line: Option<u32>,
+    pub body: String,
+}
+
+/// Forge-neutral pull request built from a review, ready to be published by a [`ForgePublisher`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForgePullRequest {
+    pub title: String,
+    pub body: String,
+    pub branches: PullRequestBranches,
+    /// Threads on lines of the diff, published as review comments
+    pub comments: Vec<ForgeComment>,
+}
+
+impl ForgePullRequest {
+    /// Build the pull request for a review.
+    ///
+    /// Forges only accept review comments on lines of the diff, so threads on
+    /// lines the diff doesn't show, and outdated threads, are quoted in the pull
+    /// request body instead. Replies are included with their authors.
+    pub fn from_review(review: &ReviewState, branches: PullRequestBranches) -> Self {
+        let mut body = String::new();
+        render_description(&mut body, &review.description);
+        let _ = writeln!(
+            body,
+            "_Reviewed as a synthetic PR of `{}` (status: {:?})._",
+            review.commit_range, review.status
+        );
+
+        let mut comments = Vec::new();
+        let mut others = Vec::new();
+        for thread in &review.comment_threads {
+            let in_diff = !thread.outdated
+                && !thread.is_resolved()
+                && review
+                    .files_changed
+                    .iter()
+                    .filter(|file| file.path == thread.file_path)
+                    .flat_map(|file| &file.hunks)
+                    .any(|hunk| {
+                        hunk.contains_new_line(thread.line_number)
+                            && hunk.contains_new_line(*thread.line_range().end())
+                    });
+            if !in_diff {
+                others.push(thread);
+                continue;
+            }
+
+            let (icon, _) = comment_type_label(&thread.comment_type);
+            let mut comment = format!("{} {}", icon, thread.content);
+            for response in &thread.responses {
+                let _ = write!(comment, "\n\n**{}:** {}", response.author, response.content);
+            }
+            comments.push(ForgeComment {
+                path: thread.file_path.clone(),
+                line: *thread.line_range().end(),
+                start_line: thread.end_line_number.map(|_| thread.line_number),
+                body: comment,
+            });
+        }
+
+        if !others.is_empty() {
+            body.push_str("\n### Comments outside the diff\n\n");
+            for thread in others {
+                render_thread(&mut body, thread);
+            }
+        }
+
+        ForgePullRequest {
+            title: review.title.clone(),
+            body: body.trim_end().to_string(),
+            branches,
+            comments,
+        }
+    }
+}
+
+/// Pull request created on a forge.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PublishedPullRequest {
+    pub number: u64,
+    /// Web URL of the pull request
+    pub url: String,
+    /// Number of review comments attached to lines of the diff
+    pub comments_published: usize,
+}
+
+/// Backend that opens pull requests with review comments on a forge (GitHub, GitLab, ...).
+pub trait ForgePublisher {
+    /// Open the pull request and attach its review comments.
+    ///
+    /// An open pull request already proposing the same branches is reused, so
+    /// publishing again after a failure does not fail on the existing one.
+    ///
+    /// # Returns
+    /// * `Ok(PublishedPullRequest)` - Number and URL of the pull request
+    /// * `Err(ForgeError::Comments)` - The pull request was opened but its comments failed
+    /// * `Err(ForgeError)` - Network failure, or the forge rejected a request
+    fn publish(
+        &self,
+        pull_request: &ForgePullRequest,
+    ) -> impl Future<Output = Result<PublishedPullRequest, ForgeError>> + Send;
+}
+
+/// Publishes pull requests through the GitHub REST API.
+#[derive(Debug, Clone)]
+pub struct GitHubPublisher {
+    api_url: String,
+    owner: String,
+    repo: String,
+    token: String,
+    client: reqwest::Client,
+}
+
+/// Pull request as returned by the GitHub REST API (`GET /repos/{owner}/{repo}/pulls/{number}`).
+///
+/// Only the fields used by Dialectic are kept.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GitHubPullRequest {
+    pub number: u64,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub body: Option<String>,
+    pub html_url: String,
+    #[serde(default)]
+    pub base: Option<GitHubCommitRef>,
+    pub head: GitHubCommitRef,
+}
+
+/// Branch and commit a pull request is based on or proposes.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GitHubCommitRef {
+    pub sha: String,
+}
+
+/// Review comment as returned by the GitHub REST API
+/// (`GET /repos/{owner}/{repo}/pulls/{number}/comments`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct GitHubReviewComment {
+    pub id: u64,
+    pub path: String,
+    /// Line in the current diff; None when the comment no longer applies to it
+    #[serde(default)]
+    pub line: Option<u32>,
+    /// Line in the diff the comment was written on
+    #[serde(default)]
+    pub original_line: Option<u32>,
+    /// First line of a multi-line comment in the current diff
+    #[serde(default)]
+    pub start_line: Option<u32>,
+    /// First line of a multi-line comment in the diff it was written on
+    #[serde(default)]
+    pub original_start_line: Option<u32>,
+    /// "RIGHT" for lines of the new file, "LEFT" for removed lines
+    #[serde(default)]
+    pub side: Option<String>,
+    pub body: String,
+    pub user: GitHubUser,
+    pub created_at: DateTime<Utc>,
+    /// Comment this one replies to
+    #[serde(default)]
+    pub in_reply_to_id: Option<u64>,
+    pub commit_id: String,
+}
+
+/// Author of a GitHub comment.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GitHubUser {
+    pub login: String,
+}
+
+/// Convert GitHub review comments into comment threads.
+///
+/// Each top-level comment starts a thread (with id `github-<comment id>`, so
+/// importing again yields the same ids) and replies become its responses, oldest
+/// first. Comments GitHub no longer places in the diff, and comments on removed
+/// lines, are kept at their original line and marked outdated.
+pub fn comment_threads_from_github(comments: &[GitHubReviewComment]) -> Vec<CommentThread> {
+    let mut comments: Vec<&GitHubReviewComment> = comments.iter().collect();
+    comments.sort_by_key(|comment| comment.created_at);
+
+    let mut threads: Vec<CommentThread> = Vec::new();
+    let mut thread_of_comment: HashMap<u64, usize> = HashMap::new();
+    for comment in comments {
+        // Replies to replies belong to the root comment's thread
+        let parent = comment
+            .in_reply_to_id
+            .and_then(|id| thread_of_comment.get(&id).copied());
+        if let Some(index) = parent {
+            threads[index].responses.push(UserResponse {
+                author: comment.user.login.clone(),
+                content: comment.body.clone(),
+                timestamp: comment.created_at,
+            });
+            thread_of_comment.insert(comment.id, index);
+            continue;
+        }
+
+        let on_removed_line = comment.side.as_deref() == Some("LEFT");
+        let (start_line, line, outdated) = match comment.line {
+            Some(line) if !on_removed_line => (comment.start_line, line, false),
+            line => (
+                comment.original_start_line,
+                comment.original_line.or(line).unwrap_or(1),
+                true,
+            ),
+        };
+        // GitHub places multi-line comments at their last line
+        let (line_number, end_line_number) = match start_line {
+            Some(start) if start < line => (start, Some(line)),
+            _ => (line, None),
+        };
+        thread_of_comment.insert(comment.id, threads.len());
+        threads.push(CommentThread {
+            thread_id: format!("github-{}", comment.id),
+            file_path: comment.path.clone(),
+            line_number,
+            end_line_number,
+            comment_type: CommentType::Comment,
+            content: comment.body.clone(),
+            responses: vec![],
+            commit_sha: Some(comment.commit_id.clone()),
+            author: Some(comment.user.login.clone()),
+            anchor: None,
+            outdated,
+            marker_status: None,
+        });
+    }
+    threads
+}
+
+impl GitHubPublisher {
+    /// Create a publisher for `owner/repo` on github.com.
+    ///
+    /// # Arguments
+    /// * `owner` - User or organization owning the repository
+    /// * `repo` - Repository name
+    /// * `token` - Token allowed to create pull requests in the repository
+    pub fn new(owner: &str, repo: &str, token: &str) -> Self {
+        GitHubPublisher {
+            api_url: GITHUB_API_URL.to_string(),
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            token: token.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Use a different API base URL (e.g., GitHub Enterprise, or a local server in tests).
+    ///
+    /// The token is sent to this URL: take it from the user's configuration, never from
+    /// tool parameters.
+    pub fn with_api_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Request builder for a repository endpoint, with GitHub's headers and authentication.
+    fn request(&self, method: reqwest::Method, endpoint: &str) -> reqwest::RequestBuilder {
+        let url = format!(
+            "{}/repos/{}/{}/{}",
+            self.api_url, self.owner, self.repo, endpoint
+        );
+        self.client
+            .request(method, url)
+            .bearer_auth(&self.token)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .header(reqwest::header::USER_AGENT, "dialectic-mcp-server")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+    }
+
+    /// POST a JSON body to a repository endpoint, turning error statuses into [`ForgeError::Api`].
+    async fn post(
+        &self,
+        endpoint: &str,
+        body: &serde_json::Value,
+    ) -> Result<reqwest::Response, ForgeError> {
+        let response = self
+            .request(reqwest::Method::POST, endpoint)
+            .json(body)
+            .send()
+            .await?;
+        Self::check_status(response).await
+    }
+
+    /// Open pull request from `branches.head` into `branches.base`, if there is one.
+    async fn find_open_pull_request(
+        &self,
+        branches: &PullRequestBranches,
+    ) -> Result<Option<GitHubPullRequest>, ForgeError> {
+        let head = format!("{}:{}", self.owner, branches.head);
+        let response = self
+            .request(reqwest::Method::GET, "pulls")
+            .query(&[
+                ("head", head.as_str()),
+                ("base", branches.base.as_str()),
+                ("state", "open"),
+            ])
+            .send()
+            .await?;
+        let pull_requests: Vec<GitHubPullRequest> =
+            Self::check_status(response).await?.json().await?;
+        Ok(pull_requests.into_iter().next())
+    }
+
+    /// Turn error statuses into [`ForgeError::Api`], with GitHub's error message.
+    async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, ForgeError> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let text = response.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<serde_json::Value>(&text)
+            .ok()
+            .and_then(|error| error["message"].as_str().map(str::to_string))
+            .unwrap_or(text);
+        Err(ForgeError::Api {
+            status: status.as_u16(),
+            message,
+        })
+    }
+}
+
+impl ForgePublisher for GitHubPublisher {
+    async fn publish(
+        &self,
+        pull_request: &ForgePullRequest,
+    ) -> Result<PublishedPullRequest, ForgeError> {
+        let created: GitHubPullRequest =
+            match self.find_open_pull_request(&pull_request.branches).await? {
+                Some(existing) => existing,
+                None => {
+                    self.post(
+                        "pulls",
+                        &json!({
+                            "title": pull_request.title,
+                            "body": pull_request.body,
+                            "base": pull_request.branches.base,
+                            "head": pull_request.branches.head,
+                        }),
+                    )
+                    .await?
+                    .json()
+                    .await?
+                }
+            };
+
+        // All line comments go in one review so reviewers get a single notification
+        if !pull_request.comments.is_empty() {
+            let comments: Vec<_> = pull_request
+                .comments
+                .iter()
+                .map(|comment| {
+                    let mut json = json!({
+                        "path": comment.path,
+                        "line": comment.line,
+                        "side": "RIGHT",
+                        "body": comment.body,
+                    });
+                    if let Some(start_line) = comment.start_line {
+                        json["start_line"] = json!(start_line);
+                        json["start_side"] = json!("RIGHT");
+                    }
+                    json
+                })
+                .collect();
+            let review = self
+                .post(
+                    &format!("pulls/{}/reviews", created.number),
+                    &json!({
+                        "commit_id": created.head.sha,
+                        "event": "COMMENT",
+                        "comments": comments,
+                    }),
+                )
+                .await;
+            if let Err(error) = review {
+                return Err(ForgeError::Comments {
+                    pull_request: PublishedPullRequest {
+                        number: created.number,
+                        url: created.html_url,
+                        comments_published: 0,
+                    },
+                    source: Box::new(error),
+                });
+            }
+        }
+
+        Ok(PublishedPullRequest {
+            number: created.number,
+            url: created.html_url,
+            comments_published: pull_request.comments.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synthetic_pr::{ChangeStatus, DiffHunk, FileChange, ReviewStatus};
+
+    #[test]
+    fn test_pull_request_from_review() {
+        let thread = |thread_id: &str, line_number, outdated| CommentThread {
+            thread_id: thread_id.to_string(),
+            outdated,
+            ..CommentThread::for_test(
+                "src/lib.rs",
+                line_number,
+                CommentType::Question,
+                &format!("Question {}", thread_id),
+            )
+        };
+        let mut in_diff = thread("a", 11, false);
+        in_diff.responses.push(UserResponse {
+            author: "user".to_string(),
+            content: "Yes".to_string(),
+            timestamp: Utc::now(),
+        });
+        // Multi-line comments can only be attached when all their lines are in the diff
+        let mut multi_line = thread("d", 12, false);
+        multi_line.end_line_number = Some(13);
+        let mut partly_in_diff = thread("e", 13, false);
+        partly_in_diff.end_line_number = Some(15);
+
+        let review = ReviewState {
+            title: "Add lib".to_string(),
+            description: serde_json::json!("Adds the library."),
+            commit_range: "HEAD~1..HEAD".to_string(),
+            status: ReviewStatus::Approved,
+            files_changed: vec![FileChange {
+                path: "src/lib.rs".to_string(),
+                status: ChangeStatus::Modified,
+                additions: 2,
+                deletions: 0,
+                hunks: vec![DiffHunk {
+                    header: "@@ -10,2 +10,4 @@".to_string(),
+                    old_start: 10,
+                    old_lines: 2,
+                    new_start: 10,
+                    new_lines: 4,
+                    lines: vec![],
+                }],
+                index: None,
+                test_links: vec![],
+            }],
+            comment_threads: vec![
+                in_diff,
+                thread("b", 30, false),
+                thread("c", 12, true),
+                multi_line,
+                partly_in_diff,
+            ],
+            ..ReviewState::for_test()
+        };
+
+        let pull_request = ForgePullRequest::from_review(
+            &review,
+            PullRequestBranches {
+                base: "main".to_string(),
+                head: "feature".to_string(),
+            },
+        );
+
+        assert_eq!(pull_request.title, "Add lib");
+        assert_eq!(
+            pull_request.comments,
+            vec![
+                ForgeComment {
+                    path: "src/lib.rs".to_string(),
+                    line: 11,
+                    start_line: None,
+                    body: "❓ Question a\n\n**user:** Yes".to_string(),
+                },
+                ForgeComment {
+                    path: "src/lib.rs".to_string(),
+                    line: 13,
+                    start_line: Some(12),
+                    body: "❓ Question d".to_string(),
+                },
+            ]
+        );
+
+        // Threads the diff doesn't show, and outdated ones, end up in the body
+        assert!(pull_request.body.starts_with("Adds the library."));
+        assert!(pull_request.body.contains("Question b"));
+        assert!(pull_request.body.contains("Question c"));
+        assert!(pull_request.body.contains("`src/lib.rs:13-15`"));
+        assert!(!pull_request.body.contains("Question a"));
+    }
+
+    #[test]
+    fn test_comment_threads_from_github() {
+        let comments: Vec<GitHubReviewComment> = serde_json::from_str(include_str!(
+            "../../tests/fixtures/github_review_comments.json"
+        ))
+        .unwrap();
+        let threads = comment_threads_from_github(&comments);
+
+        let summary: Vec<_> = threads
+            .iter()
+            .map(|t| {
+                (
+                    t.thread_id.as_str(),
+                    t.file_path.as_str(),
+                    t.line_range(),
+                    t.outdated,
+                    t.author.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("github-104", "src/auth.rs", 5..=5, true, Some("mona")),
+                ("github-101", "src/auth.rs", 10..=10, false, Some("octocat")),
+                (
+                    "github-103",
+                    "src/payment.rs",
+                    9..=10,
+                    false,
+                    Some("octocat")
+                ),
+            ]
+        );
+
+        // Replies, including replies to replies, land in the root thread in order
+        let replies: Vec<_> = threads[1]
+            .responses
+            .iter()
+            .map(|r| (r.author.as_str(), r.content.as_str()))
+            .collect();
+        assert_eq!(
+            replies,
+            vec![
+                ("hubot", "+1, and compare in constant time."),
+                ("octocat", "Agreed."),
+            ]
+        );
+        assert!(matches!(threads[1].comment_type, CommentType::Comment));
+        assert_eq!(
+            threads[1].commit_sha.as_deref(),
+            Some("6dcb09b5b57875f334f61aebed695e2e4193db5e")
+        );
+    }
+}
@@ -1,5 +1,104 @@
-use crate::synthetic_pr::{ChangeStatus, DiffHunk, DiffLine, DiffLineType, FileChange};
-use git2::{Delta, DiffOptions, Oid, Repository};
+use crate::synthetic_pr::{
+    ChangeStatus, CommentAnchor, CommentThread, DiffHunk, DiffLine, DiffLineType, FileChange,
+    FileIndex, ReviewCommit,
+};
+use chrono::{DateTime, Utc};
+use git2::{
+    Blame, BlameOptions, Delta, Diff, DiffOptions, Index, IndexEntry, IndexTime, Oid, Patch,
+    Repository, Sort,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// How whitespace differences are treated when generating a diff.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IgnoreWhitespace {
+    /// Report every whitespace change
+    #[default]
+    None,
+    /// Ignore whitespace at the end of lines
+    Eol,
+    /// Ignore changes in the amount of whitespace
+    Change,
+    /// Ignore all whitespace when comparing lines
+    All,
+}
+
+impl std::str::FromStr for IgnoreWhitespace {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(IgnoreWhitespace::None),
+            "eol" => Ok(IgnoreWhitespace::Eol),
+            "change" => Ok(IgnoreWhitespace::Change),
+            "all" => Ok(IgnoreWhitespace::All),
+            _ => Err(format!("Unknown whitespace mode: {}", s)),
+        }
+    }
+}
+
+/// Options controlling how diffs are generated for reviews and walkthroughs.
+///
+/// Every field has a default matching the historical behavior (3 context lines,
+/// whitespace-sensitive, untracked files included, no path filter), so callers
+/// only need to specify what they want to change.
+// ANCHOR: diff_settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct DiffSettings {
+    /// Number of unchanged lines shown around each hunk
+    pub context_lines: u32,
+    /// Whitespace handling: "none", "eol", "change" or "all"
+    pub ignore_whitespace: IgnoreWhitespace,
+    /// Whether untracked files appear in working tree diffs
+    pub include_untracked: bool,
+    /// Git pathspecs limiting the diff (e.g., "server/src/"); empty means all paths
+    pub paths: Vec<String>,
+}
+// ANCHOR_END: diff_settings
+
+impl Default for DiffSettings {
+    fn default() -> Self {
+        Self {
+            context_lines: 3,
+            ignore_whitespace: IgnoreWhitespace::None,
+            include_untracked: true,
+            paths: Vec::new(),
+        }
+    }
+}
+
+impl DiffSettings {
+    /// Build the libgit2 options corresponding to these settings.
+    fn to_diff_options(&self) -> DiffOptions {
+        let mut diff_opts = DiffOptions::new();
+        diff_opts.include_untracked(self.include_untracked);
+        diff_opts.context_lines(self.context_lines);
+
+        match self.ignore_whitespace {
+            IgnoreWhitespace::None => {}
+            IgnoreWhitespace::Eol => {
+                diff_opts.ignore_whitespace_eol(true);
+            }
+            IgnoreWhitespace::Change => {
+                diff_opts.ignore_whitespace_change(true);
+            }
+            IgnoreWhitespace::All => {
+                diff_opts.ignore_whitespace(true);
+            }
+        }
+
+        for path in &self.paths {
+            diff_opts.pathspec(path);
+        }
+
+        diff_opts
+    }
+}
 
 /// Git service for synthetic pull request operations.
 ///
@@ -23,6 +122,11 @@ impl GitService {
         Ok(GitService { repo })
     }
 
+    /// Root of the repository's working tree, or `None` for a bare repository.
+    pub fn workdir(&self) -> Option<&Path> {
+        self.repo.workdir()
+    }
+
     /// Parse a commit range string into base and head OIDs.
     ///
     /// Supports various Git commit range formats:
@@ -54,23 +158,53 @@ impl GitService {
         }
     }
 
+    /// Best common ancestor of two commits, e.g. where a pull request's head forked
+    /// from its base.
+    ///
+    /// # Returns
+    /// * `Ok(Oid)` - The merge base
+    /// * `Err(git2::Error)` - Commit not found, or the commits share no history
+    pub fn merge_base(&self, one: Oid, two: Oid) -> Result<Oid, git2::Error> {
+        self.repo.merge_base(one, two)
+    }
+
     /// Generate diff with file-level statistics between two commits or HEAD and working tree.
     ///
+    /// Uses the default [`DiffSettings`]; see [`GitService::generate_diff_with_settings`]
+    /// to customize context lines, whitespace handling, untracked files or paths.
+    ///
     /// # Arguments
     /// * `base_oid` - Base commit for comparison
     /// * `head_oid` - Head commit, or None to compare with working tree
     ///
     /// # Returns
-    /// * `Ok(Vec<FileChange>)` - List of files with change statistics and hunks field (currently None)
+    /// * `Ok(Vec<FileChange>)` - List of files with change statistics and diff hunks
     /// * `Err(git2::Error)` - Git operation failed
     pub fn generate_diff(
         &self,
         base_oid: Oid,
         head_oid: Option<Oid>,
     ) -> Result<Vec<FileChange>, git2::Error> {
-        let mut diff_opts = DiffOptions::new();
-        diff_opts.include_untracked(true);
-        diff_opts.context_lines(3);
+        self.generate_diff_with_settings(base_oid, head_oid, &DiffSettings::default())
+    }
+
+    /// Generate diff between two commits or a commit and the working tree using custom settings.
+    ///
+    /// # Arguments
+    /// * `base_oid` - Base commit for comparison
+    /// * `head_oid` - Head commit, or None to compare with working tree
+    /// * `settings` - Context lines, whitespace mode, untracked files and pathspecs to apply
+    ///
+    /// # Returns
+    /// * `Ok(Vec<FileChange>)` - List of files with change statistics and diff hunks
+    /// * `Err(git2::Error)` - Git operation failed
+    pub fn generate_diff_with_settings(
+        &self,
+        base_oid: Oid,
+        head_oid: Option<Oid>,
+        settings: &DiffSettings,
+    ) -> Result<Vec<FileChange>, git2::Error> {
+        let mut diff_opts = settings.to_diff_options();
 
         let diff = match head_oid {
             Some(head_oid) => {
@@ -84,13 +218,500 @@ impl GitService {
                 )?
             }
             None => {
-                // Compare HEAD with working tree
+                // Compare base with working tree (like `git diff <base>`), consulting the
+                // index so files committed after `base` are not mistaken for untracked ones
                 let head_tree = self.repo.find_commit(base_oid)?.tree()?;
                 self.repo
-                    .diff_tree_to_workdir(Some(&head_tree), Some(&mut diff_opts))?
+                    .diff_tree_to_workdir_with_index(Some(&head_tree), Some(&mut diff_opts))?
+            }
+        };
+
+        Self::collect_file_changes(&diff)
+    }
+
+    /// Snapshot the code under review as a tree object.
+    ///
+    /// For a commit range this is the head commit's tree. For working tree reviews the
+    /// uncommitted changes (and untracked files, if the settings include them) are
+    /// overlaid on HEAD's tree in an in-memory index and written to the object
+    /// database, without touching the real index or the working tree.
+    ///
+    /// # Arguments
+    /// * `head_oid` - Head commit, or None to snapshot the working tree
+    /// * `settings` - Diff settings of the review; only `include_untracked` is used
+    ///
+    /// # Returns
+    /// * `Ok(Oid)` - Tree id of the snapshot
+    /// * `Err(git2::Error)` - Git operation failed
+    pub fn snapshot_tree(
+        &self,
+        head_oid: Option<Oid>,
+        settings: &DiffSettings,
+    ) -> Result<Oid, git2::Error> {
+        if let Some(head_oid) = head_oid {
+            return Ok(self.repo.find_commit(head_oid)?.tree_id());
+        }
+
+        let head_tree = match self.repo.head() {
+            Ok(head) => head.peel_to_tree()?,
+            Err(_) => {
+                // Unborn branch: start from the empty tree
+                let empty_tree = self.repo.treebuilder(None)?.write()?;
+                self.repo.find_tree(empty_tree)?
             }
         };
 
+        let mut diff_opts = DiffOptions::new();
+        diff_opts.include_untracked(settings.include_untracked);
+        diff_opts.recurse_untracked_dirs(settings.include_untracked);
+        let diff = self
+            .repo
+            .diff_tree_to_workdir_with_index(Some(&head_tree), Some(&mut diff_opts))?;
+
+        // Start from HEAD in an in-memory index and overlay the working tree files
+        let workdir = self
+            .repo
+            .workdir()
+            .ok_or_else(|| git2::Error::from_str("Repository has no working directory"))?;
+        let mut index = Index::new()?;
+        index.read_tree(&head_tree)?;
+        for delta in diff.deltas() {
+            let file = delta.new_file();
+            let Some(path) = file.path() else {
+                continue;
+            };
+            if delta.status() == Delta::Deleted {
+                index.remove_path(path)?;
+                continue;
+            }
+
+            let content = std::fs::read(workdir.join(path))
+                .map_err(|e| git2::Error::from_str(&e.to_string()))?;
+            let entry = IndexEntry {
+                ctime: IndexTime::new(0, 0),
+                mtime: IndexTime::new(0, 0),
+                dev: 0,
+                ino: 0,
+                mode: u32::from(file.mode()),
+                uid: 0,
+                gid: 0,
+                file_size: content.len() as u32,
+                id: self.repo.blob(&content)?,
+                flags: 0,
+                flags_extended: 0,
+                path: path.to_string_lossy().replace('\\', "/").into_bytes(),
+            };
+            index.add(&entry)?;
+        }
+
+        index.write_tree_to(&self.repo)
+    }
+
+    /// Keep a review round's snapshot from being garbage collected.
+    ///
+    /// Working tree snapshots aren't reachable from any commit, so each round's tree is
+    /// pinned under `refs/socratic-shell/reviews/<review id>/<round>`.
+    ///
+    /// # Arguments
+    /// * `review_id` - Review the round belongs to
+    /// * `round` - Round number
+    /// * `tree_oid` - Snapshot taken by [`GitService::snapshot_tree`]
+    pub fn pin_snapshot(
+        &self,
+        review_id: &str,
+        round: u32,
+        tree_oid: Oid,
+    ) -> Result<(), git2::Error> {
+        self.repo.reference(
+            &format!("refs/socratic-shell/reviews/{}/{}", review_id, round),
+            tree_oid,
+            true,
+            "review round snapshot",
+        )?;
+        Ok(())
+    }
+
+    /// Diff two tree snapshots, e.g. consecutive review rounds.
+    ///
+    /// # Arguments
+    /// * `old_tree` - Earlier snapshot
+    /// * `new_tree` - Later snapshot
+    /// * `settings` - Context lines, whitespace mode and pathspecs to apply
+    ///
+    /// # Returns
+    /// * `Ok(Vec<FileChange>)` - Files that changed between the snapshots
+    /// * `Err(git2::Error)` - Git operation failed
+    pub fn diff_trees(
+        &self,
+        old_tree: Oid,
+        new_tree: Oid,
+        settings: &DiffSettings,
+    ) -> Result<Vec<FileChange>, git2::Error> {
+        let old_tree = self.repo.find_tree(old_tree)?;
+        let new_tree = self.repo.find_tree(new_tree)?;
+        let mut diff_opts = settings.to_diff_options();
+        let diff =
+            self.repo
+                .diff_tree_to_tree(Some(&old_tree), Some(&new_tree), Some(&mut diff_opts))?;
+        Self::collect_file_changes(&diff)
+    }
+
+    /// List the commits in a review range, oldest first, each with its own diff.
+    ///
+    /// Merge commits are diffed against their first parent. When `head_oid` is None
+    /// (working tree review), commits from `base_oid` up to HEAD are listed, followed
+    /// by an entry with `sha: None` for uncommitted changes if there are any.
+    ///
+    /// # Arguments
+    /// * `base_oid` - Base commit of the range (excluded from the list)
+    /// * `head_oid` - Head commit, or None to include HEAD and the working tree
+    /// * `settings` - Diff settings applied to every per-commit diff
+    ///
+    /// # Returns
+    /// * `Ok(Vec<ReviewCommit>)` - Commits in topological order, oldest first
+    /// * `Err(git2::Error)` - Revision walk or diff failed
+    pub fn commits_in_range(
+        &self,
+        base_oid: Oid,
+        head_oid: Option<Oid>,
+        settings: &DiffSettings,
+    ) -> Result<Vec<ReviewCommit>, git2::Error> {
+        let tip = match head_oid {
+            Some(head_oid) => head_oid,
+            None => self.repo.head()?.peel_to_commit()?.id(),
+        };
+
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+        revwalk.push(tip)?;
+        revwalk.hide(base_oid)?;
+
+        let mut commits = Vec::new();
+        for oid in revwalk {
+            let commit = self.repo.find_commit(oid?)?;
+            let parent_tree = match commit.parent(0) {
+                Ok(parent) => Some(parent.tree()?),
+                Err(_) => None,
+            };
+            let mut diff_opts = settings.to_diff_options();
+            let diff = self.repo.diff_tree_to_tree(
+                parent_tree.as_ref(),
+                Some(&commit.tree()?),
+                Some(&mut diff_opts),
+            )?;
+
+            let author = commit.author();
+            commits.push(ReviewCommit {
+                sha: Some(commit.id().to_string()),
+                author: format!(
+                    "{} <{}>",
+                    author.name().unwrap_or("unknown"),
+                    author.email().unwrap_or("unknown")
+                ),
+                message: commit.message().unwrap_or("").trim_end().to_string(),
+                timestamp: DateTime::from_timestamp(commit.time().seconds(), 0)
+                    .unwrap_or_default(),
+                files_changed: Self::collect_file_changes(&diff)?,
+            });
+        }
+
+        if head_oid.is_none() {
+            let uncommitted = self.generate_diff_with_settings(tip, None, settings)?;
+            if !uncommitted.is_empty() {
+                commits.push(ReviewCommit {
+                    sha: None,
+                    author: String::new(),
+                    message: "Uncommitted changes".to_string(),
+                    timestamp: Utc::now(),
+                    files_changed: uncommitted,
+                });
+            }
+        }
+
+        Ok(commits)
+    }
+
+    /// Attribute each comment thread to the commit in the range that introduced its line.
+    ///
+    /// Uses blame limited to the review range. Threads on uncommitted lines, or on lines
+    /// that predate the range, keep `commit_sha: None`, as do resolved insight comments.
+    /// Files that cannot be blamed (e.g., untracked files) are skipped.
+    ///
+    /// # Arguments
+    /// * `threads` - Comment threads whose line numbers refer to the head of the range
+    /// * `base_oid` - Base commit of the range
+    /// * `head_oid` - Head commit, or None when threads refer to the working tree
+    pub fn attribute_comment_threads(
+        &self,
+        threads: &mut [CommentThread],
+        base_oid: Oid,
+        head_oid: Option<Oid>,
+    ) {
+        let mut threads_by_file: HashMap<String, Vec<&mut CommentThread>> = HashMap::new();
+        // Resolved markers are gone from the head, so there is no line to blame
+        for thread in threads.iter_mut().filter(|t| !t.is_resolved()) {
+            threads_by_file
+                .entry(thread.file_path.clone())
+                .or_default()
+                .push(thread);
+        }
+
+        for (path, file_threads) in threads_by_file {
+            let _ = self.with_blame_in_range(&path, base_oid, head_oid, |blame| {
+                for thread in file_threads {
+                    let Some(hunk) = blame.get_line(thread.line_number as usize) else {
+                        continue;
+                    };
+
+                    let commit_id = hunk.final_commit_id();
+                    if !commit_id.is_zero() && !hunk.is_boundary() && commit_id != base_oid {
+                        thread.commit_sha = Some(commit_id.to_string());
+                    }
+                }
+            });
+        }
+    }
+
+    /// Anchor comment threads that have no anchor yet to the code they point at.
+    ///
+    /// Threads whose line does not exist at the head of the range, and resolved insight
+    /// comments, are left unanchored.
+    ///
+    /// # Arguments
+    /// * `threads` - Comment threads whose line numbers refer to the head of the range
+    /// * `head_oid` - Head commit, or None when threads refer to the working tree
+    pub fn anchor_comment_threads(&self, threads: &mut [CommentThread], head_oid: Option<Oid>) {
+        let mut files: HashMap<String, Option<(Oid, String)>> = HashMap::new();
+        for thread in threads
+            .iter_mut()
+            .filter(|t| t.anchor.is_none() && !t.is_resolved())
+        {
+            let file = files
+                .entry(thread.file_path.clone())
+                .or_insert_with(|| self.file_at(&thread.file_path, head_oid).ok().flatten());
+            if let Some((blob_oid, content)) = file {
+                thread.anchor =
+                    CommentAnchor::capture(content, thread.line_number, blob_oid.to_string());
+            }
+        }
+    }
+
+    /// Move anchored comment threads to where their code is at the head of the range.
+    ///
+    /// Each thread's line is first mapped through the diff between the content it was
+    /// anchored in and the current content, then confirmed (or searched for) using the
+    /// anchor's fingerprint. Threads whose code can't be found are marked outdated and
+    /// keep their last known line; their responses are untouched.
+    ///
+    /// # Arguments
+    /// * `threads` - Comment threads to re-anchor
+    /// * `head_oid` - Head commit, or None to re-anchor against the working tree
+    pub fn reanchor_comment_threads(&self, threads: &mut [CommentThread], head_oid: Option<Oid>) {
+        let mut files: HashMap<String, Option<(Oid, String)>> = HashMap::new();
+        for thread in threads.iter_mut() {
+            let Some(anchor) = &thread.anchor else {
+                continue;
+            };
+            let file = files
+                .entry(thread.file_path.clone())
+                .or_insert_with(|| self.file_at(&thread.file_path, head_oid).ok().flatten());
+
+            let Some((blob_oid, content)) = file else {
+                thread.outdated = true;
+                continue;
+            };
+            if anchor.blob_oid == blob_oid.to_string() {
+                continue;
+            }
+
+            let hint = Oid::from_str(&anchor.blob_oid)
+                .ok()
+                .and_then(|old_oid| self.map_line(old_oid, content, thread.line_number).ok())
+                .flatten()
+                .unwrap_or(thread.line_number);
+
+            match anchor.relocate(content, hint) {
+                Some(line_number) => {
+                    // Multi-line comments move as a whole
+                    thread.end_line_number = thread
+                        .end_line_number
+                        .map(|end| line_number + (end - thread.line_number));
+                    thread.line_number = line_number;
+                    thread.anchor =
+                        CommentAnchor::capture(content, line_number, blob_oid.to_string());
+                    thread.outdated = false;
+                }
+                None => thread.outdated = true,
+            }
+        }
+    }
+
+    /// Pair the changed test files with the changed source files they exercise,
+    /// reading the tests as they are at the head of the range.
+    ///
+    /// # Arguments
+    /// * `files` - Changed files of the range, given their `test_links`
+    /// * `head_oid` - Head commit, or None to read the working tree
+    /// * `referenced_by` - References the IDE found for the changed files, if any
+    pub fn link_tests(
+        &self,
+        files: &mut [FileChange],
+        head_oid: Option<Oid>,
+        referenced_by: &BTreeMap<String, Vec<String>>,
+    ) {
+        // Unlike `file_at`, reading the working tree writes nothing to the object database
+        let read_file = |path: &str| match head_oid {
+            Some(head_oid) => self.file_at_commit(path, head_oid).ok().flatten(),
+            None => std::fs::read_to_string(self.repo.workdir()?.join(path)).ok(),
+        };
+        crate::synthetic_pr::link_tests(files, read_file, referenced_by);
+    }
+
+    /// Content of a file as committed at `commit_oid`.
+    ///
+    /// # Returns
+    /// * `Ok(Some(content))` - The file's content, lossily decoded as UTF-8
+    /// * `Ok(None)` - The commit has no such file
+    /// * `Err(git2::Error)` - Commit not found
+    pub fn file_at_commit(&self, path: &str, commit_oid: Oid) -> Result<Option<String>, git2::Error> {
+        Ok(self.file_at(path, Some(commit_oid))?.map(|(_, content)| content))
+    }
+
+    /// Content of a file in a tree, e.g. a review round's snapshot.
+    ///
+    /// # Returns
+    /// * `Ok(Some(content))` - The file's content, lossily decoded as UTF-8
+    /// * `Ok(None)` - The tree has no such file
+    /// * `Err(git2::Error)` - Tree not found
+    pub fn file_in_tree(&self, path: &str, tree_oid: Oid) -> Result<Option<String>, git2::Error> {
+        let tree = self.repo.find_tree(tree_oid)?;
+        let Ok(entry) = tree.get_path(Path::new(path)) else {
+            return Ok(None);
+        };
+        let blob = self.repo.find_blob(entry.id())?;
+        Ok(Some(String::from_utf8_lossy(blob.content()).into_owned()))
+    }
+
+    /// Read a file at a commit (or from the working tree when `head_oid` is None).
+    ///
+    /// Working tree contents are written to the object database so that anchors
+    /// captured from them can later be diffed against newer contents. Only round
+    /// snapshots keep such blobs from being pruned; re-anchoring falls back to the
+    /// anchor's fingerprint when one is gone.
+    ///
+    /// # Returns
+    /// * `Ok(Some((blob_oid, content)))` - The file's blob id and text
+    /// * `Ok(None)` - The file does not exist there
+    /// * `Err(git2::Error)` - Git operation failed
+    fn file_at(
+        &self,
+        path: &str,
+        head_oid: Option<Oid>,
+    ) -> Result<Option<(Oid, String)>, git2::Error> {
+        match head_oid {
+            Some(head_oid) => {
+                let tree = self.repo.find_commit(head_oid)?.tree()?;
+                let Ok(entry) = tree.get_path(Path::new(path)) else {
+                    return Ok(None);
+                };
+                let blob = self.repo.find_blob(entry.id())?;
+                Ok(Some((
+                    blob.id(),
+                    String::from_utf8_lossy(blob.content()).into_owned(),
+                )))
+            }
+            None => {
+                let workdir = self
+                    .repo
+                    .workdir()
+                    .ok_or_else(|| git2::Error::from_str("Repository has no working directory"))?;
+                let Ok(content) = std::fs::read(workdir.join(path)) else {
+                    return Ok(None);
+                };
+                let blob_oid = self.repo.blob(&content)?;
+                Ok(Some((
+                    blob_oid,
+                    String::from_utf8_lossy(&content).into_owned(),
+                )))
+            }
+        }
+    }
+
+    /// Map a (1-based) line of a stored blob to the same line in new content.
+    ///
+    /// # Returns
+    /// * `Ok(Some(line))` - The line is unchanged and now lives at `line`
+    /// * `Ok(None)` - The line was modified or removed
+    fn map_line(
+        &self,
+        old_blob: Oid,
+        new_content: &str,
+        line: u32,
+    ) -> Result<Option<u32>, git2::Error> {
+        let old_blob = self.repo.find_blob(old_blob)?;
+        let mut opts = DiffOptions::new();
+        opts.context_lines(0);
+        let patch = Patch::from_blob_and_buffer(
+            &old_blob,
+            None,
+            new_content.as_bytes(),
+            None,
+            Some(&mut opts),
+        )?;
+
+        let mut offset: i64 = 0;
+        for hunk_idx in 0..patch.num_hunks() {
+            let (hunk, _) = patch.hunk(hunk_idx)?;
+            let (old_start, old_lines) = (hunk.old_start(), hunk.old_lines());
+            if old_lines == 0 {
+                // Pure insertion after `old_start`
+                if line <= old_start {
+                    break;
+                }
+            } else if line < old_start {
+                break;
+            } else if line < old_start + old_lines {
+                return Ok(None);
+            }
+            offset += hunk.new_lines() as i64 - old_lines as i64;
+        }
+
+        Ok(Some((line as i64 + offset) as u32))
+    }
+
+    /// Blame a file between `base_oid` and the head of the range (or the working tree)
+    /// and pass the result to `op`.
+    fn with_blame_in_range<R>(
+        &self,
+        path: &str,
+        base_oid: Oid,
+        head_oid: Option<Oid>,
+        op: impl FnOnce(&Blame<'_>) -> R,
+    ) -> Result<R, git2::Error> {
+        let mut opts = BlameOptions::new();
+        opts.oldest_commit(base_oid);
+        if let Some(head_oid) = head_oid {
+            opts.newest_commit(head_oid);
+        }
+
+        let blame = self.repo.blame_file(Path::new(path), Some(&mut opts))?;
+        match head_oid {
+            Some(_) => Ok(op(&blame)),
+            None => {
+                // Layer the working tree contents on top so uncommitted lines show up as such
+                let workdir = self
+                    .repo
+                    .workdir()
+                    .ok_or_else(|| git2::Error::from_str("Repository has no working directory"))?;
+                let contents = std::fs::read(workdir.join(path))
+                    .map_err(|e| git2::Error::from_str(&e.to_string()))?;
+                Ok(op(&blame.blame_buffer(&contents)?))
+            }
+        }
+    }
+
+    /// Convert a libgit2 diff into file changes with hunks and line statistics.
+    fn collect_file_changes(diff: &Diff<'_>) -> Result<Vec<FileChange>, git2::Error> {
         use std::cell::RefCell;
 
         // Use RefCell for interior mutability since all closures are captured simultaneously
@@ -120,12 +741,25 @@ impl GitService {
                     (None, None) => return true,
                 };
 
+                // Uncommitted files may not have been hashed, leaving a zero id
+                let oid = |file: git2::DiffFile<'_>| {
+                    (file.exists() && !file.id().is_zero()).then(|| file.id().to_string())
+                };
+                let index = FileIndex {
+                    old_oid: oid(delta.old_file()),
+                    new_oid: oid(delta.new_file()),
+                    old_mode: i32::from(delta.old_file().mode()) as u32,
+                    new_mode: i32::from(delta.new_file().mode()) as u32,
+                };
+
                 file_changes.borrow_mut().push(FileChange {
                     path,
                     status,
                     additions: 0,
                     deletions: 0,
                     hunks: Vec::new(),
+                    index: Some(index),
+                    test_links: Vec::new(),
                 });
 
                 true
@@ -157,12 +791,19 @@ impl GitService {
                     '+' => DiffLineType::Added,
                     '-' => DiffLineType::Removed,
                     ' ' => DiffLineType::Context,
+                    // "\ No newline at end of file" markers follow the line they apply to
+                    '=' | '>' | '<' => {
+                        if let Some(last) = current_hunk.lines.last_mut() {
+                            last.missing_newline = true;
+                        }
+                        return true;
+                    }
                     _ => DiffLineType::Context,
                 };
 
-                let content = String::from_utf8_lossy(line.content())
-                    .trim_end()
-                    .to_string();
+                // Keep trailing whitespace so exported patches still apply
+                let content = String::from_utf8_lossy(line.content());
+                let content = content.strip_suffix('\n').unwrap_or(&content).to_string();
 
                 let (old_line_number, new_line_number) = match line_type {
                     DiffLineType::Added => (None, Some(line.new_lineno().unwrap_or(0) as usize)),
@@ -186,6 +827,7 @@ impl GitService {
                     content,
                     old_line_number,
                     new_line_number,
+                    missing_newline: false,
                 });
 
                 true
@@ -1,11 +1,21 @@
-use crate::synthetic_pr::{CommentParser, GitService, ReviewState, ReviewStatus};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::synthetic_pr::{
+    AGENT_AUTHOR, CommentParser, CommentThread, DiffSettings, ExportFormat, FeedbackMode,
+    FileOrdering, FileRanking, ForgePublisher, ForgePullRequest, GitHubPullRequest,
+    GitHubReviewComment, GitService, PolicyEvaluation, PublishedPullRequest, PullRequestBranches,
+    ReviewComment, ReviewEvent, ReviewEventKind, ReviewMetrics, ReviewPolicy, ReviewRound,
+    ReviewState, ReviewStatus, ReviewStore, ReviewSummary, StripOptions, StripPlan, USER_AUTHOR,
+    parse_suggestion_blocks,
+};
 use chrono::Utc;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// MCP tool parameters for creating a new synthetic pull request.
 // ANCHOR: request_review_params
-#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
 pub struct RequestReviewParams {
     /// Git commit range to review (e.g., "HEAD", "HEAD~2", "abc123..def456")
     pub commit_range: String,
@@ -15,6 +25,19 @@ pub struct RequestReviewParams {
     pub description: serde_json::Value,
     /// Optional repository path (defaults to current directory)
     pub repo_path: Option<String>,
+    /// Optional diff settings: context lines, whitespace handling, untracked files and
+    /// path filters (e.g., `{"paths": ["server/src/"], "include_untracked": false}`)
+    #[serde(default)]
+    pub diff_settings: DiffSettings,
+    /// "block" (default) waits for the user's first feedback; "pending_review" waits until
+    /// they complete the review and delivers all their comments at once; "poll" returns
+    /// right away, and the feedback is collected with `poll_review_feedback`
+    #[serde(default)]
+    pub feedback_mode: FeedbackMode,
+    /// Group the files, ranked riskiest first, into sections (source, tests,
+    /// configuration, documentation, generated files)
+    #[serde(default)]
+    pub group_files: bool,
 }
 
 /// User feedback type from VSCode extension
@@ -47,6 +70,9 @@ pub struct UserFeedback {
 #[serde(tag = "feedback_type", rename_all = "snake_case")]
 pub enum FeedbackData {
     Comment {
+        /// Thread the user replied in, if the comment was a reply
+        #[serde(default)]
+        thread_id: Option<String>,
         file_path: Option<String>,
         line_number: Option<u32>,
         comment_text: String,
@@ -60,17 +86,45 @@ pub enum FeedbackData {
 
 /// Data generated from the working directory and sent over IPC to the extension
 /// as the basis for a review.
-#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ReviewData {
     pub review_id: String,
     pub title: String,
     pub description: serde_json::Value,
     pub commit_range: String,
+    /// Cumulative diff over the whole range
     pub files_changed: Vec<crate::synthetic_pr::FileChange>,
+    /// Commits in the range, oldest first, each with its own diff
+    pub commits: Vec<crate::synthetic_pr::ReviewCommit>,
+    /// Review rounds, oldest first; the last round's interdiff shows what changed
+    /// since the reviewer last looked
+    pub rounds: Vec<ReviewRound>,
     pub comment_threads: Vec<crate::synthetic_pr::CommentThread>,
+    /// Changes suggested in comments, with whether they were applied
+    pub suggestions: Vec<crate::synthetic_pr::Suggestion>,
+    /// `files_changed` in the order to review them, riskiest first, with the reasons
+    pub file_ranking: FileRanking,
     pub status: String,
 }
 
+impl From<&ReviewState> for ReviewData {
+    fn from(review: &ReviewState) -> Self {
+        ReviewData {
+            review_id: review.review_id.clone(),
+            title: review.title.clone(),
+            description: review.description.clone(),
+            commit_range: review.commit_range.clone(),
+            files_changed: review.files_changed.clone(),
+            commits: review.commits.clone(),
+            rounds: review.rounds.clone(),
+            comment_threads: review.comment_threads.clone(),
+            suggestions: review.suggestions.clone(),
+            file_ranking: FileRanking::for_review(review),
+            status: "success".to_string(),
+        }
+    }
+}
+
 // ANCHOR: update_review_params
 /// MCP tool parameters for updating an existing synthetic pull request.
 #[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
@@ -80,18 +134,30 @@ pub struct UpdateReviewParams {
 
     /// What kind of update should be performed.
     pub action: UpdateReviewAction,
+
+    /// Optional repository path (defaults to current directory)
+    #[serde(default)]
+    pub repo_path: Option<String>,
+
+    /// "block" (default) waits for the user's feedback; "pending_review" waits until
+    /// they complete the review and delivers all their comments at once; "poll" returns
+    /// right away, and the feedback is collected with `poll_review_feedback`
+    #[serde(default)]
+    pub feedback_mode: FeedbackMode,
 }
 
 #[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub enum UpdateReviewAction {
     /// Wait for user feedback from VSCode extension
     WaitForFeedback,
-    /// Add a new comment thread to the review
-    AddComment { comment: serde_json::Value },
+    /// Reply to a comment thread (`thread_id`) or start a new one (`file_path` and `line_number`)
+    AddComment { comment: ReviewComment },
     /// Mark the review as approved
     Approve,
     /// Request changes to the review
     RequestChanges,
+    /// Mark an approved review as merged once its changes are committed
+    Merge,
 }
 
 /// Response data from synthetic pull request update operations.
@@ -102,6 +168,168 @@ pub struct UpdateReviewResponse {
     pub user_action: Option<String>,
     pub message: Option<String>,
     pub user_feedback: Option<UserFeedback>,
+    /// Review after the update, as sent to the extension
+    pub review: ReviewData,
+}
+
+/// MCP tool parameters for querying the status of a synthetic pull request.
+// ANCHOR: get_review_status_params
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct GetReviewStatusParams {
+    /// Review to query (defaults to the most recently updated review)
+    #[serde(default)]
+    pub review_id: Option<String>,
+    /// Optional repository path (defaults to current directory)
+    #[serde(default)]
+    pub repo_path: Option<String>,
+}
+// ANCHOR_END: get_review_status_params
+
+/// MCP tool parameters for listing the reviews of a repository.
+// ANCHOR: list_reviews_params
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ListReviewsParams {
+    /// Optional repository path (defaults to current directory)
+    #[serde(default)]
+    pub repo_path: Option<String>,
+}
+// ANCHOR_END: list_reviews_params
+
+/// MCP tool parameters for collecting feedback on a review shown in poll mode.
+// ANCHOR: poll_review_feedback_params
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PollReviewFeedbackParams {
+    /// Review to collect feedback for
+    pub review_id: String,
+    /// How long to wait for feedback when none is queued yet (0 returns right away;
+    /// capped by the server's maximum poll wait)
+    #[serde(default)]
+    pub wait_seconds: u64,
+    /// Optional repository path (defaults to current directory)
+    #[serde(default)]
+    pub repo_path: Option<String>,
+}
+// ANCHOR_END: poll_review_feedback_params
+
+/// MCP tool parameters for exporting a synthetic pull request.
+// ANCHOR: export_review_params
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ExportReviewParams {
+    /// Review to export (defaults to the most recently updated review)
+    #[serde(default)]
+    pub review_id: Option<String>,
+    /// Output format: "mbox" (patch series), "markdown" (report) or "sarif"
+    pub format: ExportFormat,
+    /// Optional repository path (defaults to current directory)
+    #[serde(default)]
+    pub repo_path: Option<String>,
+}
+// ANCHOR_END: export_review_params
+
+/// MCP tool parameters for publishing a synthetic pull request to GitHub.
+// ANCHOR: publish_review_params
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PublishReviewParams {
+    /// Review to publish (defaults to the most recently updated review)
+    #[serde(default)]
+    pub review_id: Option<String>,
+    /// GitHub repository as "owner/repo"
+    pub repository: String,
+    /// Base and head branches of the pull request; both must already be pushed
+    #[serde(flatten)]
+    pub branches: PullRequestBranches,
+    /// Optional repository path (defaults to current directory)
+    #[serde(default)]
+    pub repo_path: Option<String>,
+}
+// ANCHOR_END: publish_review_params
+
+/// MCP tool parameters for importing a GitHub pull request as a synthetic PR.
+// ANCHOR: import_review_params
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ImportReviewParams {
+    /// JSON file holding the pull request, as returned by
+    /// `gh api repos/{owner}/{repo}/pulls/{number}`
+    pub pull_request_path: String,
+    /// JSON file holding its review comments, as returned by
+    /// `gh api repos/{owner}/{repo}/pulls/{number}/comments`
+    pub comments_path: String,
+    /// Optional repository path (defaults to current directory)
+    #[serde(default)]
+    pub repo_path: Option<String>,
+    /// "block" (default) waits for the user's first feedback; "pending_review" waits until
+    /// they complete the review and delivers all their comments at once; "poll" returns
+    /// right away, and the feedback is collected with `poll_review_feedback`
+    #[serde(default)]
+    pub feedback_mode: FeedbackMode,
+}
+// ANCHOR_END: import_review_params
+
+/// MCP tool parameters for removing a review's insight comments from the working tree.
+// ANCHOR: strip_insight_comments_params
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct StripInsightCommentsParams {
+    /// Review listing the comments (defaults to the most recently updated review)
+    #[serde(default)]
+    pub review_id: Option<String>,
+    /// Which comments to strip, and whether to keep answered questions
+    #[serde(flatten)]
+    pub options: StripOptions,
+    /// Write the edits to the working tree; when false, only report them as a diff
+    #[serde(default)]
+    pub apply: bool,
+    /// Optional repository path (defaults to current directory)
+    #[serde(default)]
+    pub repo_path: Option<String>,
+}
+// ANCHOR_END: strip_insight_comments_params
+
+/// Response data for stripping insight comments from the working tree.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct StripInsightCommentsResponse {
+    pub review_id: String,
+    /// Unified diff of the edits
+    pub diff: String,
+    pub files_changed: Vec<String>,
+    /// Threads whose comment is removed
+    pub removed: Vec<String>,
+    /// Answered questions whose comment becomes a regular comment
+    pub converted: Vec<String>,
+    /// Threads whose comment is no longer in the working tree
+    pub not_found: Vec<String>,
+    /// True when the edits were written to the working tree
+    pub applied: bool,
+}
+
+/// MCP tool parameters for applying a change suggested in a review comment.
+// ANCHOR: apply_suggestion_params
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ApplySuggestionParams {
+    /// Review the suggestion was made in
+    pub review_id: String,
+    /// Suggestion to apply
+    #[serde(default)]
+    pub suggestion_id: Option<String>,
+    /// Thread whose most recent suggestion not applied yet should be applied, when
+    /// `suggestion_id` is omitted
+    #[serde(default)]
+    pub thread_id: Option<String>,
+    /// Optional repository path (defaults to current directory)
+    #[serde(default)]
+    pub repo_path: Option<String>,
+}
+// ANCHOR_END: apply_suggestion_params
+
+/// Response data for applying a suggested change.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ApplySuggestionResponse {
+    pub review_id: String,
+    pub suggestion_id: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Unified diff of the edit written to the working tree
+    pub diff: String,
 }
 
 /// Response data for synthetic pull request status queries.
@@ -114,6 +342,12 @@ pub struct ReviewStatusResponse {
     pub comment_threads: Option<usize>,
     pub created_at: Option<chrono::DateTime<Utc>>,
     pub updated_at: Option<chrono::DateTime<Utc>>,
+    /// What happened to the review and who did it, oldest first
+    pub history: Vec<ReviewEvent>,
+    /// The review checked against the repository's review policy
+    pub policy: Option<PolicyEvaluation>,
+    /// Change sizes, thread counts, time in each status, rounds and churn
+    pub metrics: Option<ReviewMetrics>,
 }
 
 /// MCP tool: Create a synthetic pull request from Git commit range with AI insight comments.
@@ -134,55 +368,160 @@ pub struct ReviewStatusResponse {
 pub async fn harvest_review_data(
     params: RequestReviewParams,
 ) -> Result<ReviewData, Box<dyn std::error::Error>> {
+    // Opening the store first migrates any legacy review file out of the working
+    // tree before we diff it
+    let store = ReviewStore::for_repo(Some(params.repo_path.as_deref().unwrap_or(".")))?;
+    let review_state = create_review(&params, vec![])?;
+
+    // Save review state alongside any other reviews of this repository
+    store.save(&review_state)?;
+
+    // Return response for VSCode extension
+    Ok(ReviewData::from(&review_state))
+}
+
+/// Build a new review of a commit range, with the insight comments found in its diff.
+///
+/// `extra_threads` (e.g., comments imported from a forge) are added after the
+/// insight comments and anchored along with them.
+fn create_review(
+    params: &RequestReviewParams,
+    extra_threads: Vec<CommentThread>,
+) -> Result<ReviewState, Box<dyn std::error::Error>> {
     // Use provided repo path or default to current directory
     let repo_path = params.repo_path.as_deref().unwrap_or(".");
-
-    // Initialize services with explicit repo path
     let git_service = GitService::new(repo_path)?;
-    let comment_parser = CommentParser::new();
 
     // Parse commit range and generate diff with hunks
     let (base_oid, head_oid) = git_service.parse_commit_range(&params.commit_range)?;
-    let file_changes = git_service.generate_diff(base_oid, head_oid)?;
+    let comment_parser = CommentParser::for_commit(&git_service, base_oid)?;
+    let mut file_changes =
+        git_service.generate_diff_with_settings(base_oid, head_oid, &params.diff_settings)?;
+    git_service.link_tests(&mut file_changes, head_oid, &BTreeMap::new());
+
+    let commits = git_service.commits_in_range(base_oid, head_oid, &params.diff_settings)?;
 
     // Parse AI comments from diff hunks (only changed lines)
-    let comment_threads = comment_parser.parse_file_changes(&file_changes)?;
+    let mut comment_threads = comment_parser.parse_file_changes(&file_changes)?;
+    git_service.attribute_comment_threads(&mut comment_threads, base_oid, head_oid);
+    comment_threads.extend(extra_threads);
+    git_service.anchor_comment_threads(&mut comment_threads, head_oid);
+
+    let review_id = uuid::Uuid::new_v4().to_string();
+    let tree_oid = git_service.snapshot_tree(head_oid, &params.diff_settings)?;
+    git_service.pin_snapshot(&review_id, 1, tree_oid)?;
+    let first_round = ReviewRound {
+        number: 1,
+        tree_oid: tree_oid.to_string(),
+        created_at: Utc::now(),
+        interdiff: vec![],
+    };
 
-    // Create review state
-    let review_state = ReviewState {
-        review_id: uuid::Uuid::new_v4().to_string(),
+    let created_at = Utc::now();
+    Ok(ReviewState {
+        review_id,
         title: params.title.clone(),
         description: params.description.clone(),
         commit_range: params.commit_range.clone(),
+        diff_settings: params.diff_settings.clone(),
         status: ReviewStatus::Pending,
-        files_changed: file_changes.clone(),
-        comment_threads: comment_threads.clone(),
-        created_at: Utc::now(),
-        updated_at: Utc::now(),
-    };
-
-    // Save review state to file
-    review_state.save_to_file(None::<&str>)?;
-
-    // Return response for VSCode extension
-    Ok(ReviewData {
-        review_id: review_state.review_id,
-        title: review_state.title,
-        description: review_state.description,
-        commit_range: review_state.commit_range,
         files_changed: file_changes,
+        commits,
+        rounds: vec![first_round],
         comment_threads,
-        status: "success".to_string(),
+        events: vec![ReviewEvent {
+            kind: ReviewEventKind::Created,
+            actor: AGENT_AUTHOR.to_string(),
+            timestamp: created_at,
+            thread_id: None,
+        }],
+        suggestions: vec![],
+        file_ordering: FileOrdering {
+            group_files: params.group_files,
+            ..Default::default()
+        },
+        created_at,
+        updated_at: created_at,
     })
 }
 
+/// MCP tool: Create a synthetic pull request from a GitHub pull request and its review comments.
+///
+/// Reviews the pull request's commits since it forked from its base (which must be
+/// available locally, e.g. after `git fetch origin pull/<number>/head`) and turns the human reviewers' comments
+/// into comment threads, so the agent can answer them with `update_review`.
+///
+/// # Arguments
+/// * `params` - Files holding the GitHub API responses, and the repository path
+///
+/// # Returns
+/// * `Ok(ReviewData)` - Review data with insight comments and the imported threads
+/// * `Err(Box<dyn std::error::Error>)` - Unreadable files, commits missing locally, or file system error
+pub async fn import_review(
+    params: ImportReviewParams,
+) -> Result<ReviewData, Box<dyn std::error::Error>> {
+    let pull_request: GitHubPullRequest =
+        serde_json::from_str(&std::fs::read_to_string(&params.pull_request_path)?)?;
+    let comments: Vec<GitHubReviewComment> =
+        serde_json::from_str(&std::fs::read_to_string(&params.comments_path)?)?;
+
+    let repo_path = params.repo_path.as_deref().unwrap_or(".");
+    let store = ReviewStore::for_repo(Some(repo_path))?;
+
+    let base = pull_request
+        .base
+        .as_ref()
+        .ok_or("Pull request JSON has no base commit")?;
+    // Like GitHub, show what the head adds since it forked from the base, not what the
+    // base gained since
+    let git_service = GitService::new(repo_path)?;
+    let merge_base = git_service
+        .parse_commit_range(&format!("{}..{}", base.sha, pull_request.head.sha))
+        .and_then(|(base_oid, head_oid)| {
+            git_service.merge_base(base_oid, head_oid.unwrap_or(base_oid))
+        })
+        .map_err(|e| {
+            format!(
+                "Commits of pull request #{} are not available locally, fetch them first: {}",
+                pull_request.number, e
+            )
+        })?;
+    let commit_range = format!("{}..{}", merge_base, pull_request.head.sha);
+
+    let description = match pull_request.body.as_deref() {
+        Some(body) if !body.is_empty() => {
+            format!("{}\n\nImported from {}", body, pull_request.html_url)
+        }
+        _ => format!("Imported from {}", pull_request.html_url),
+    };
+    let review_state = create_review(
+        &RequestReviewParams {
+            commit_range,
+            title: format!("{} (#{})", pull_request.title, pull_request.number),
+            description: serde_json::Value::String(description),
+            repo_path: Some(repo_path.to_string()),
+            diff_settings: DiffSettings::default(),
+            feedback_mode: params.feedback_mode,
+            group_files: false,
+        },
+        crate::synthetic_pr::comment_threads_from_github(&comments),
+    )?;
+    store.save(&review_state)?;
+
+    Ok(ReviewData::from(&review_state))
+}
+
 /// MCP tool: Update an existing synthetic pull request or wait for user feedback.
 ///
 /// Supports iterative review workflows between AI assistants and developers:
 /// - `wait_for_feedback`: Blocks until user provides review feedback
-/// - `add_comment`: Adds new comment threads to existing review
+/// - `add_comment`: Replies to a comment thread as the agent, or starts a new one
 /// - `approve`: Marks review as approved and ready for merge
 /// - `request_changes`: Indicates review needs modifications
+/// - `merge`: Marks an approved review as merged
+///
+/// Status changes follow [`ReviewStatus::allowed_transitions`] and are recorded in
+/// the review's history.
 ///
 /// # Arguments
 /// * `params` - Update parameters including review ID and action
@@ -193,102 +532,388 @@ pub async fn harvest_review_data(
 pub async fn update_review(
     params: UpdateReviewParams,
 ) -> Result<UpdateReviewResponse, Box<dyn std::error::Error>> {
-    match params.action {
-        UpdateReviewAction::WaitForFeedback => {
-            // In a real implementation, this would block until VSCode extension provides feedback
-            // For now, return a placeholder response
-            Ok(UpdateReviewResponse {
-                status: "waiting".to_string(),
-                review_id: params.review_id,
-                user_action: Some("pending".to_string()),
-                message: Some("Waiting for user feedback...".to_string()),
-                user_feedback: None,
-            })
-        }
-        UpdateReviewAction::AddComment { comment: _ } => {
-            // Load existing review, add comment, save back
-            let mut review = ReviewState::load_from_file(None::<&str>)?;
-
-            // Add new comment thread (simplified implementation)
-            // In practice, this would handle thread replies, etc.
-
-            review.updated_at = Utc::now();
-            review.save_to_file(None::<&str>)?;
-
-            Ok(UpdateReviewResponse {
-                status: "comment_added".to_string(),
-                review_id: params.review_id,
-                user_action: None,
-                message: Some("Comment added successfully".to_string()),
-                user_feedback: None,
-            })
+    let store = ReviewStore::for_repo(params.repo_path.as_deref())?;
+    let mut review = store.load(&params.review_id)?;
+
+    // The agent may have edited files since the last round; follow the code first so
+    // new comments and replies refer to the current contents
+    refresh_review(&mut review, params.repo_path.as_deref())?;
+
+    let (status, user_action, message) = match params.action {
+        UpdateReviewAction::WaitForFeedback => (
+            "waiting",
+            Some("pending"),
+            Some("Waiting for user feedback...".to_string()),
+        ),
+        UpdateReviewAction::AddComment { comment } => {
+            let thread_id = review.add_comment(&comment, AGENT_AUTHOR)?;
+            record_suggestions(
+                &mut review,
+                &thread_id,
+                &comment.body,
+                AGENT_AUTHOR,
+                params.repo_path.as_deref(),
+            )?;
+            (
+                "comment_added",
+                None,
+                Some(format!("Comment added to thread {}", thread_id)),
+            )
         }
         UpdateReviewAction::Approve => {
-            let mut review = ReviewState::load_from_file(None::<&str>)?;
-            review.status = ReviewStatus::Approved;
-            review.updated_at = Utc::now();
-            review.save_to_file(None::<&str>)?;
-
-            Ok(UpdateReviewResponse {
-                status: "approved".to_string(),
-                review_id: params.review_id,
-                user_action: Some("approved".to_string()),
-                message: None,
-                user_feedback: None,
-            })
+            let policy =
+                load_review_policy(params.repo_path.as_deref(), &review)?.evaluate(&review);
+            if !policy.passed {
+                return Err(format!(
+                    "Cannot approve: the review policy is not met.\n{}\n\
+                    Address these (or ask the user to) and approve again.",
+                    policy.explain()
+                )
+                .into());
+            }
+            review.transition(ReviewStatus::Approved, AGENT_AUTHOR)?;
+            ("approved", Some("approved"), None)
         }
         UpdateReviewAction::RequestChanges => {
-            let mut review = ReviewState::load_from_file(None::<&str>)?;
-            review.status = ReviewStatus::ChangesRequested;
-            review.updated_at = Utc::now();
-            review.save_to_file(None::<&str>)?;
-
-            Ok(UpdateReviewResponse {
-                status: "changes_requested".to_string(),
-                review_id: params.review_id,
-                user_action: Some("changes_requested".to_string()),
-                message: None,
-                user_feedback: None,
-            })
+            review.transition(ReviewStatus::ChangesRequested, AGENT_AUTHOR)?;
+            ("changes_requested", Some("changes_requested"), None)
+        }
+        UpdateReviewAction::Merge => {
+            review.transition(ReviewStatus::Merged, AGENT_AUTHOR)?;
+            ("merged", Some("merged"), None)
+        }
+    };
+
+    // Anchor any thread the action started
+    anchor_new_threads(&mut review, params.repo_path.as_deref())?;
+    store.save(&review)?;
+
+    Ok(UpdateReviewResponse {
+        status: status.to_string(),
+        review_id: params.review_id,
+        user_action: user_action.map(str::to_string),
+        message,
+        user_feedback: None,
+        review: ReviewData::from(&review),
+    })
+}
+
+/// Record feedback the user left in the IDE in the review.
+///
+/// Replies land in the thread the user answered in (or the thread anchored at the
+/// same file and line); other comments start a new thread. Completing the review
+/// records the user's decision: requesting changes moves the review to
+/// `ChangesRequested` and checkpointing approves it. Returning to the agent leaves
+/// the status alone.
+///
+/// # Arguments
+/// * `repo_path` - Optional repository path (defaults to current directory)
+/// * `feedback` - Feedback received from the VSCode extension, naming the review it belongs to
+///
+/// # Returns
+/// * `Ok(Some(String))` - ID of the thread the comment was recorded in
+/// * `Ok(None)` - Feedback was not a comment
+/// * `Err(Box<dyn std::error::Error>)` - No review to record into, a status change the
+///   review does not allow, or file system error
+pub fn record_user_feedback(
+    repo_path: Option<&str>,
+    feedback: &UserFeedback,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let store = ReviewStore::for_repo(repo_path)?;
+
+    let (thread_id, file_path, line_number, comment_text) = match &feedback.feedback {
+        FeedbackData::Comment {
+            thread_id,
+            file_path,
+            line_number,
+            comment_text,
+            ..
+        } => (thread_id, file_path, line_number, comment_text),
+        FeedbackData::CompleteReview {
+            completion_action, ..
+        } => {
+            let status = match completion_action {
+                CompletionAction::RequestChanges => ReviewStatus::ChangesRequested,
+                CompletionAction::Checkpoint => ReviewStatus::Approved,
+                CompletionAction::Return => return Ok(None),
+            };
+            let mut review = store.load(&feedback.review_id)?;
+            if review.status != status {
+                review.transition(status, USER_AUTHOR)?;
+                store.save(&review)?;
+            }
+            return Ok(None);
         }
+    };
+
+    let mut review = store.load(&feedback.review_id)?;
+
+    let thread_id = thread_id.clone().or_else(|| {
+        let (file_path, line_number) = (file_path.as_deref()?, (*line_number)?);
+        review
+            .find_thread_at(file_path, line_number)
+            .map(|thread| thread.thread_id.clone())
+    });
+
+    let thread_id = review.add_comment(
+        &ReviewComment {
+            thread_id,
+            file_path: file_path.clone(),
+            line_number: *line_number,
+            body: comment_text.clone(),
+        },
+        USER_AUTHOR,
+    )?;
+    record_suggestions(
+        &mut review,
+        &thread_id,
+        comment_text,
+        USER_AUTHOR,
+        repo_path,
+    )?;
+    anchor_new_threads(&mut review, repo_path)?;
+    store.save(&review)?;
+
+    Ok(Some(thread_id))
+}
+
+/// Bring a stored review up to date with the repository.
+///
+/// Regenerates the diff, moves comment threads to where their code is now (marking
+/// the ones whose code is gone as outdated), picks up insight comments added since
+/// the review was last refreshed, and starts a new round if the code changed.
+fn refresh_review(
+    review: &mut ReviewState,
+    repo_path: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let git_service = GitService::new(repo_path.unwrap_or("."))?;
+    let (base_oid, head_oid) = git_service.parse_commit_range(&review.commit_range)?;
+
+    git_service.reanchor_comment_threads(&mut review.comment_threads, head_oid);
+
+    review.files_changed =
+        git_service.generate_diff_with_settings(base_oid, head_oid, &review.diff_settings)?;
+    git_service.link_tests(
+        &mut review.files_changed,
+        head_oid,
+        &review.file_ordering.referenced_by,
+    );
+    review.commits = git_service.commits_in_range(base_oid, head_oid, &review.diff_settings)?;
+
+    let mut new_threads = CommentParser::for_commit(&git_service, base_oid)?
+        .parse_file_changes(&review.files_changed)?;
+
+    // Resolved markers aren't anchored; they stay current only while still removed
+    for thread in review
+        .comment_threads
+        .iter_mut()
+        .filter(|t| t.is_resolved())
+    {
+        thread.outdated = !new_threads.iter().any(|new| {
+            new.is_resolved()
+                && new.file_path == thread.file_path
+                && new.line_number == thread.line_number
+                && new.content == thread.content
+        });
+    }
+
+    // Insight comments already covered by a live thread keep that thread (and its id)
+    new_threads.retain(|new| {
+        !review.comment_threads.iter().any(|thread| {
+            !thread.outdated
+                && thread.author.is_none()
+                && thread.file_path == new.file_path
+                && thread.line_number == new.line_number
+                && thread.is_resolved() == new.is_resolved()
+        })
+    });
+    git_service.attribute_comment_threads(&mut new_threads, base_oid, head_oid);
+    review.comment_threads.extend(new_threads);
+
+    git_service.anchor_comment_threads(&mut review.comment_threads, head_oid);
+
+    let tree_oid = git_service.snapshot_tree(head_oid, &review.diff_settings)?;
+    let previous = review.rounds.last();
+    if previous.is_none_or(|round| round.tree_oid != tree_oid.to_string()) {
+        let interdiff = match previous {
+            Some(round) => {
+                let old_tree = git2::Oid::from_str(&round.tree_oid)?;
+                match git_service.diff_trees(old_tree, tree_oid, &review.diff_settings) {
+                    Ok(interdiff) => interdiff,
+                    // Snapshots taken before rounds were pinned may have been pruned
+                    Err(e) if e.code() == git2::ErrorCode::NotFound => vec![],
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            None => vec![],
+        };
+        let number = review.rounds.len() as u32 + 1;
+        git_service.pin_snapshot(&review.review_id, number, tree_oid)?;
+        review.rounds.push(ReviewRound {
+            number,
+            tree_oid: tree_oid.to_string(),
+            created_at: Utc::now(),
+            interdiff,
+        });
     }
+
+    Ok(())
+}
+
+/// Anchor threads added since the review was last refreshed (e.g., new comments).
+fn anchor_new_threads(
+    review: &mut ReviewState,
+    repo_path: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let git_service = GitService::new(repo_path.unwrap_or("."))?;
+    let (_, head_oid) = git_service.parse_commit_range(&review.commit_range)?;
+    git_service.anchor_comment_threads(&mut review.comment_threads, head_oid);
+    Ok(())
+}
+
+/// Record the ```suggestion blocks of a comment just added to a thread, replacing lines
+/// as they read in the review's latest round.
+fn record_suggestions(
+    review: &mut ReviewState,
+    thread_id: &str,
+    body: &str,
+    author: &str,
+    repo_path: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if parse_suggestion_blocks(body).is_empty() {
+        return Ok(());
+    }
+
+    let git_service = GitService::new(repo_path.unwrap_or("."))?;
+    let snapshot = match review.rounds.last() {
+        Some(round) => Some(git2::Oid::from_str(&round.tree_oid)?),
+        None => None,
+    };
+    review.record_suggestions(thread_id, body, author, |path| match snapshot {
+        Some(tree_oid) => git_service.file_in_tree(path, tree_oid).ok().flatten(),
+        // Reviews stored before rounds were recorded only have the working tree
+        None => std::fs::read_to_string(git_service.workdir()?.join(path)).ok(),
+    });
+    Ok(())
+}
+
+/// The review policy committed at the base of the review's range.
+fn load_review_policy(
+    repo_path: Option<&str>,
+    review: &ReviewState,
+) -> Result<ReviewPolicy, Box<dyn std::error::Error>> {
+    let git_service = GitService::new(repo_path.unwrap_or("."))?;
+    let (base_oid, _) = git_service.parse_commit_range(&review.commit_range)?;
+    Ok(ReviewPolicy::load(&git_service, base_oid)?)
+}
+
+/// Load a stored review as the data shown in the extension, without refreshing it.
+///
+/// # Arguments
+/// * `repo_path` - Optional repository path (defaults to current directory)
+/// * `review_id` - Review to load
+///
+/// # Returns
+/// * `Ok(ReviewData)` - The review as last stored
+/// * `Err(Box<dyn std::error::Error>)` - Unknown review id or file system error
+pub fn load_review_data(
+    repo_path: Option<&str>,
+    review_id: &str,
+) -> Result<ReviewData, Box<dyn std::error::Error>> {
+    let store = ReviewStore::for_repo(repo_path)?;
+    Ok(ReviewData::from(&store.load(review_id)?))
+}
+
+/// Record which other files reference the symbols each changed file of a review
+/// defines, so the review's file ranking and test links take them into account.
+///
+/// # Arguments
+/// * `repo_path` - Optional repository path (defaults to current directory)
+/// * `review_id` - Review whose files were looked up
+/// * `referenced_by` - For each changed file, the files the IDE found references in,
+///   relative to the repository root or absolute; the file itself is left out
+///
+/// # Returns
+/// * `Ok(ReviewData)` - The review with its files ranked and its tests linked again
+/// * `Err(Box<dyn std::error::Error>)` - Unknown review id or file system error
+pub fn record_file_references(
+    repo_path: Option<&str>,
+    review_id: &str,
+    referenced_by: BTreeMap<String, Vec<String>>,
+) -> Result<ReviewData, Box<dyn std::error::Error>> {
+    let git_service = GitService::new(repo_path.unwrap_or("."))?;
+    let workdir = git_service.workdir().map(Path::to_path_buf);
+    let store = ReviewStore::for_repo(repo_path)?;
+    let mut review = store.load(review_id)?;
+
+    review.file_ordering.referenced_by = referenced_by
+        .into_iter()
+        .map(|(file, references)| {
+            let mut references: Vec<String> = references
+                .iter()
+                .map(|path| match &workdir {
+                    Some(workdir) => Path::new(path)
+                        .strip_prefix(workdir)
+                        .map_or_else(|_| path.clone(), |path| path.display().to_string()),
+                    None => path.clone(),
+                })
+                .filter(|path| *path != file)
+                .collect();
+            references.sort();
+            references.dedup();
+            (file, references)
+        })
+        .filter(|(_, references)| !references.is_empty())
+        .collect();
+
+    // Tests the IDE found referencing a changed file exercise it
+    let (_, head_oid) = git_service.parse_commit_range(&review.commit_range)?;
+    git_service.link_tests(
+        &mut review.files_changed,
+        head_oid,
+        &review.file_ordering.referenced_by,
+    );
+    store.save(&review)?;
+
+    Ok(ReviewData::from(&review))
 }
 
-/// Get the status of the current synthetic pull request.
+/// Get the status of a synthetic pull request.
 ///
-/// Provides summary information about the active review including file counts,
+/// Provides summary information about the review including file counts,
 /// comment thread counts, timestamps, and current workflow status.
 ///
 /// # Arguments
 /// * `repo_path` - Optional repository path (defaults to current directory)
+/// * `review_id` - Review to query; the most recently updated review if None
 ///
 /// # Returns
-/// * `Ok(ReviewStatusResponse)` - Current review status or "no_active_review"
-/// * `Err(Box<dyn std::error::Error>)` - File system error
+/// * `Ok(ReviewStatusResponse)` - Review status, or "no_active_review" if there is none
+/// * `Err(Box<dyn std::error::Error>)` - Unknown review id or file system error
 pub async fn get_review_status(
     repo_path: Option<&str>,
+    review_id: Option<&str>,
 ) -> Result<ReviewStatusResponse, Box<dyn std::error::Error>> {
-    let repo_path = repo_path.unwrap_or(".");
-    let review_file_path = if repo_path == "." {
-        ".socratic-shell-review.json".to_string()
-    } else {
-        format!("{}/.socratic-shell-review.json", repo_path)
+    let store = ReviewStore::for_repo(repo_path)?;
+    let review = match review_id {
+        Some(review_id) => Some(store.load(review_id)?),
+        None => store.load_latest()?,
     };
 
-    match std::fs::read_to_string(&review_file_path) {
-        Ok(content) => {
-            let review: ReviewState = serde_json::from_str(&content)?;
-            Ok(ReviewStatusResponse {
-                review_id: Some(review.review_id),
-                title: Some(review.title),
-                status: format!("{:?}", review.status),
-                files_changed: Some(review.files_changed.len()),
-                comment_threads: Some(review.comment_threads.len()),
-                created_at: Some(review.created_at),
-                updated_at: Some(review.updated_at),
-            })
-        }
-        Err(_) => Ok(ReviewStatusResponse {
+    match review {
+        Some(review) => Ok(ReviewStatusResponse {
+            policy: Some(load_review_policy(repo_path, &review)?.evaluate(&review)),
+            metrics: Some(ReviewMetrics::for_review(&review, Utc::now())),
+            review_id: Some(review.review_id),
+            title: Some(review.title),
+            status: format!("{:?}", review.status),
+            files_changed: Some(review.files_changed.len()),
+            comment_threads: Some(review.comment_threads.len()),
+            created_at: Some(review.created_at),
+            updated_at: Some(review.updated_at),
+            history: review.events,
+        }),
+        None => Ok(ReviewStatusResponse {
             review_id: None,
             title: None,
             status: "no_active_review".to_string(),
@@ -296,6 +921,163 @@ pub async fn get_review_status(
             comment_threads: None,
             created_at: None,
             updated_at: None,
+            history: vec![],
+            policy: None,
+            metrics: None,
         }),
     }
 }
+
+/// List the synthetic pull requests stored for a repository.
+///
+/// # Arguments
+/// * `repo_path` - Optional repository path (defaults to current directory)
+///
+/// # Returns
+/// * `Ok(Vec<ReviewSummary>)` - All reviews, most recently updated first
+/// * `Err(Box<dyn std::error::Error>)` - File system error
+pub async fn list_reviews(
+    repo_path: Option<&str>,
+) -> Result<Vec<ReviewSummary>, Box<dyn std::error::Error>> {
+    Ok(ReviewStore::for_repo(repo_path)?.list()?)
+}
+
+/// Export a synthetic pull request as a patch series, Markdown report or SARIF log.
+///
+/// # Arguments
+/// * `params` - Review to export, output format and repository path
+///
+/// # Returns
+/// * `Ok(String)` - The exported document
+/// * `Err(Box<dyn std::error::Error>)` - No review to export, unknown review id or file system error
+pub async fn export_review(
+    params: ExportReviewParams,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let store = ReviewStore::for_repo(params.repo_path.as_deref())?;
+    let review = match &params.review_id {
+        Some(review_id) => store.load(review_id)?,
+        None => store.load_latest()?.ok_or("No review to export")?,
+    };
+    Ok(crate::synthetic_pr::render_review(&review, params.format))
+}
+
+/// MCP tool: Remove (or convert) a review's insight comments from the working tree.
+///
+/// Meant to run once the review is done, before the code is committed, so the 💡/❓
+/// markers don't end up in the history. The edits are reported as a diff and only
+/// written when `params.apply` is set.
+///
+/// # Arguments
+/// * `params` - Review, which comments to strip, and whether to apply the edits
+///
+/// # Returns
+/// * `Ok(StripInsightCommentsResponse)` - Diff of the edits and the threads they cover
+/// * `Err(Box<dyn std::error::Error>)` - No review to strip, unknown review id, bare repository or file system error
+pub async fn strip_insight_comments(
+    params: StripInsightCommentsParams,
+) -> Result<StripInsightCommentsResponse, Box<dyn std::error::Error>> {
+    let store = ReviewStore::for_repo(params.repo_path.as_deref())?;
+    let review = match &params.review_id {
+        Some(review_id) => store.load(review_id)?,
+        None => store
+            .load_latest()?
+            .ok_or("No review to strip insight comments from")?,
+    };
+
+    let git_service = GitService::new(params.repo_path.as_deref().unwrap_or("."))?;
+    let workdir = git_service
+        .workdir()
+        .ok_or("Repository has no working tree")?;
+    let (base_oid, _) = git_service.parse_commit_range(&review.commit_range)?;
+    let parser = CommentParser::for_commit(&git_service, base_oid)?;
+    let plan = StripPlan::for_review(&review, &parser, workdir, &params.options)?;
+    let diff = plan.diff()?;
+    if params.apply {
+        plan.apply(workdir)?;
+    }
+
+    Ok(StripInsightCommentsResponse {
+        review_id: review.review_id,
+        diff,
+        files_changed: plan.edits.into_iter().map(|edit| edit.path).collect(),
+        removed: plan.removed,
+        converted: plan.converted,
+        not_found: plan.not_found,
+        applied: params.apply,
+    })
+}
+
+/// MCP tool: Apply a change suggested in a review comment to the working tree.
+///
+/// The lines the suggestion replaces must still read as they did when it was made;
+/// otherwise nothing is written. Applying is recorded in the review's history.
+///
+/// # Arguments
+/// * `params` - Review, and the suggestion (or the thread holding it) to apply
+///
+/// # Returns
+/// * `Ok(ApplySuggestionResponse)` - Where the suggestion was applied, and the diff
+/// * `Err(Box<dyn std::error::Error>)` - Unknown review or suggestion, suggestion already
+///   applied, lines changed since, bare repository or file system error
+pub async fn apply_suggestion(
+    params: ApplySuggestionParams,
+) -> Result<ApplySuggestionResponse, Box<dyn std::error::Error>> {
+    let store = ReviewStore::for_repo(params.repo_path.as_deref())?;
+    let mut review = store.load(&params.review_id)?;
+
+    let suggestion_id = match (params.suggestion_id, &params.thread_id) {
+        (Some(suggestion_id), _) => suggestion_id,
+        (None, Some(thread_id)) => review
+            .pending_suggestion_in(thread_id)?
+            .suggestion_id
+            .clone(),
+        (None, None) => return Err("Pass a suggestion_id or a thread_id".into()),
+    };
+
+    let git_service = GitService::new(params.repo_path.as_deref().unwrap_or("."))?;
+    let workdir = git_service
+        .workdir()
+        .ok_or("Repository has no working tree")?;
+    let edit = review.apply_suggestion(&suggestion_id, AGENT_AUTHOR, workdir)?;
+    store.save(&review)?;
+
+    let suggestion = review
+        .suggestions
+        .iter()
+        .find(|s| s.suggestion_id == suggestion_id)
+        .ok_or("Applied suggestion missing from the review")?;
+    Ok(ApplySuggestionResponse {
+        review_id: review.review_id.clone(),
+        suggestion_id,
+        file_path: edit.path.clone(),
+        start_line: suggestion.start_line,
+        end_line: suggestion.end_line,
+        diff: edit.diff()?,
+    })
+}
+
+/// Publish a synthetic pull request to a forge as a real pull request with review comments.
+///
+/// # Arguments
+/// * `publisher` - Forge backend to publish with
+/// * `repo_path` - Optional repository path (defaults to current directory)
+/// * `review_id` - Review to publish; the most recently updated review if None
+/// * `branches` - Base and head branches of the pull request
+///
+/// # Returns
+/// * `Ok(PublishedPullRequest)` - Number and URL of the new pull request
+/// * `Err(Box<dyn std::error::Error>)` - No review to publish, or the forge request failed
+pub async fn publish_review<P: ForgePublisher>(
+    publisher: &P,
+    repo_path: Option<&str>,
+    review_id: Option<&str>,
+    branches: PullRequestBranches,
+) -> Result<PublishedPullRequest, Box<dyn std::error::Error>> {
+    let store = ReviewStore::for_repo(repo_path)?;
+    let review = match review_id {
+        Some(review_id) => store.load(review_id)?,
+        None => store.load_latest()?.ok_or("No review to publish")?,
+    };
+    let pull_request = ForgePullRequest::from_review(&review, branches);
+    Ok(publisher.publish(&pull_request).await?)
+}
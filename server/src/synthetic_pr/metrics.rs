@@ -0,0 +1,249 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::synthetic_pr::{
+    ChangeStatus, CommentType, FileChange, ReviewEventKind, ReviewState, ReviewStatus,
+};
+
+// ANCHOR: review_metrics
+/// Summary statistics of a review, for tracking how changes go through review over time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ReviewMetrics {
+    /// Lines added and deleted per file, in diff order
+    pub files: Vec<FileMetrics>,
+    pub total_additions: u32,
+    pub total_deletions: u32,
+    /// Comment threads per type (`Explanation`, `Question`, ..., or a custom type's name)
+    pub threads_by_type: BTreeMap<String, usize>,
+    /// Live threads the user hasn't replied to yet (agent replies alone don't count)
+    pub open_threads: usize,
+    /// Live threads with at least one reply from the user
+    pub answered_threads: usize,
+    /// Threads whose code is gone, or insight comments the changes removed
+    pub closed_threads: usize,
+    /// Seconds spent in each status so far (`Pending`, `ChangesRequested`, `Approved`);
+    /// a merged review stops the clock
+    pub seconds_in_status: BTreeMap<String, i64>,
+    /// Review rounds, 1 until the code changes during the review
+    pub rounds: usize,
+    /// Lines added or deleted after the review started, summed over the rounds' interdiffs
+    pub churn: u32,
+    /// `churn` relative to the lines changed by the reviewed diff (0 when nothing was reworked)
+    pub churn_ratio: f64,
+}
+
+/// Size of the change to one file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FileMetrics {
+    pub path: String,
+    pub status: ChangeStatus,
+    pub additions: u32,
+    pub deletions: u32,
+}
+// ANCHOR_END: review_metrics
+
+impl ReviewMetrics {
+    /// Compute the metrics of a review.
+    ///
+    /// # Arguments
+    /// * `review` - Review to summarize
+    /// * `now` - End of the time spent in the current status
+    pub fn for_review(review: &ReviewState, now: DateTime<Utc>) -> Self {
+        let files: Vec<FileMetrics> = review
+            .files_changed
+            .iter()
+            .map(|file| FileMetrics {
+                path: file.path.clone(),
+                status: file.status.clone(),
+                additions: file.additions,
+                deletions: file.deletions,
+            })
+            .collect();
+        let total_additions = files.iter().map(|file| file.additions).sum();
+        let total_deletions = files.iter().map(|file| file.deletions).sum();
+
+        let mut threads_by_type = BTreeMap::new();
+        let (mut open_threads, mut answered_threads, mut closed_threads) = (0, 0, 0);
+        for thread in &review.comment_threads {
+            *threads_by_type
+                .entry(type_name(&thread.comment_type))
+                .or_insert(0) += 1;
+            if thread.outdated || thread.is_resolved() {
+                closed_threads += 1;
+            } else if thread.answered_by_user() {
+                answered_threads += 1;
+            } else {
+                open_threads += 1;
+            }
+        }
+
+        let churn = review
+            .rounds
+            .iter()
+            .flat_map(|round| &round.interdiff)
+            .map(lines_changed)
+            .sum();
+        let reviewed: u32 = total_additions + total_deletions;
+        let churn_ratio = if reviewed == 0 {
+            0.0
+        } else {
+            f64::from(churn) / f64::from(reviewed)
+        };
+
+        ReviewMetrics {
+            files,
+            total_additions,
+            total_deletions,
+            threads_by_type,
+            open_threads,
+            answered_threads,
+            closed_threads,
+            seconds_in_status: seconds_in_status(review, now),
+            rounds: review.rounds.len(),
+            churn,
+            churn_ratio,
+        }
+    }
+}
+
+fn lines_changed(file: &FileChange) -> u32 {
+    file.additions + file.deletions
+}
+
+/// Name of a comment type as it is serialized.
+fn type_name(comment_type: &CommentType) -> String {
+    match comment_type {
+        CommentType::Custom(name) => name.clone(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Time between the status changes recorded in the review's history, per status.
+fn seconds_in_status(review: &ReviewState, now: DateTime<Utc>) -> BTreeMap<String, i64> {
+    let mut seconds = BTreeMap::new();
+    let mut status = ReviewStatus::Pending;
+    let mut since = review.created_at;
+
+    let changes = review.events.iter().filter_map(|event| {
+        let to = match event.kind {
+            ReviewEventKind::ChangesRequested => ReviewStatus::ChangesRequested,
+            ReviewEventKind::Approved => ReviewStatus::Approved,
+            ReviewEventKind::Merged => ReviewStatus::Merged,
+            ReviewEventKind::Created
+            | ReviewEventKind::Comment
+            | ReviewEventKind::SuggestionApplied => return None,
+        };
+        Some((to, event.timestamp))
+    });
+    for (to, at) in changes.chain(std::iter::once((ReviewStatus::Merged, now))) {
+        if status == ReviewStatus::Merged {
+            break;
+        }
+        *seconds.entry(format!("{:?}", status)).or_insert(0) += (at - since).num_seconds().max(0);
+        status = to;
+        since = at;
+    }
+    seconds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synthetic_pr::{
+        AGENT_AUTHOR, CommentThread, MarkerStatus, ReviewComment, ReviewRound, USER_AUTHOR,
+    };
+    use chrono::Duration;
+
+    fn file(path: &str, additions: u32, deletions: u32) -> FileChange {
+        FileChange {
+            deletions,
+            ..FileChange::for_test(path, additions, &[])
+        }
+    }
+
+    fn thread(
+        line_number: u32,
+        comment_type: CommentType,
+        marker_status: Option<MarkerStatus>,
+    ) -> CommentThread {
+        CommentThread {
+            marker_status,
+            ..CommentThread::for_test("src/lib.rs", line_number, comment_type, "...")
+        }
+    }
+
+    #[test]
+    fn test_review_metrics() {
+        let created_at = Utc::now() - Duration::hours(3);
+        let round = |number: u32, interdiff: Vec<FileChange>| ReviewRound {
+            number,
+            tree_oid: String::new(),
+            created_at,
+            interdiff,
+        };
+        let mut review = ReviewState {
+            files_changed: vec![file("src/lib.rs", 30, 10), file("Cargo.lock", 8, 2)],
+            rounds: vec![round(1, vec![]), round(2, vec![file("src/lib.rs", 4, 1)])],
+            comment_threads: vec![
+                thread(1, CommentType::Question, Some(MarkerStatus::Introduced)),
+                thread(2, CommentType::Question, Some(MarkerStatus::Introduced)),
+                thread(3, CommentType::Fixme, Some(MarkerStatus::Resolved)),
+                thread(4, CommentType::Custom("SAFETY".to_string()), None),
+            ],
+            created_at,
+            updated_at: created_at,
+            ..ReviewState::for_test()
+        };
+        // The user answered the first question; only the agent replied to the second
+        for (index, author) in [(0, USER_AUTHOR), (1, AGENT_AUTHOR)] {
+            let thread_id = review.comment_threads[index].thread_id.clone();
+            review
+                .add_comment(
+                    &ReviewComment {
+                        thread_id: Some(thread_id),
+                        file_path: None,
+                        line_number: None,
+                        body: "Yes".to_string(),
+                    },
+                    author,
+                )
+                .unwrap();
+        }
+        review
+            .transition(ReviewStatus::ChangesRequested, USER_AUTHOR)
+            .unwrap();
+        review
+            .transition(ReviewStatus::Approved, AGENT_AUTHOR)
+            .unwrap();
+        // One hour pending, then an hour with changes requested
+        review.events[2].timestamp = created_at + Duration::hours(1);
+        review.events[3].timestamp = created_at + Duration::hours(2);
+
+        let metrics = ReviewMetrics::for_review(&review, created_at + Duration::minutes(150));
+        assert_eq!((metrics.total_additions, metrics.total_deletions), (38, 12));
+        assert_eq!(metrics.threads_by_type["Question"], 2);
+        assert_eq!(metrics.threads_by_type["SAFETY"], 1);
+        assert_eq!(
+            (
+                metrics.open_threads,
+                metrics.answered_threads,
+                metrics.closed_threads
+            ),
+            (2, 1, 1)
+        );
+        assert_eq!(
+            metrics.seconds_in_status,
+            BTreeMap::from([
+                ("Pending".to_string(), 3600),
+                ("ChangesRequested".to_string(), 3600),
+                ("Approved".to_string(), 1800),
+            ])
+        );
+        assert_eq!(metrics.rounds, 2);
+        assert_eq!(metrics.churn, 5);
+        assert_eq!(metrics.churn_ratio, 0.1);
+    }
+}
@@ -1,9 +1,55 @@
+pub mod anchor;
 pub mod git_service;
 pub mod comment_parser;
+pub mod comment_config;
 pub mod review_state;
+pub mod review_store;
+pub mod export;
+pub mod feedback_message;
+pub mod feedback_queue;
+pub mod feedback_templates;
+pub mod forge;
+pub mod metrics;
+pub mod policy;
+pub mod ranking;
+pub mod strip;
+pub mod suggestion;
+pub mod test_links;
 pub mod mcp_tools;
 
-pub use git_service::GitService;
+pub use anchor::CommentAnchor;
+pub use git_service::{DiffSettings, GitService, IgnoreWhitespace};
 pub use comment_parser::CommentParser;
+pub use comment_config::{
+    BlockDelimiters, COMMENT_CONFIG_FILE, CommentConfig, CommentConfigError, CommentSyntax,
+    MarkerRule,
+};
 pub use review_state::*;
+pub use export::{ExportFormat, render_review};
+pub use feedback_message::{FeedbackItem, FeedbackResult, FeedbackStatus, SuggestedToolCall};
+pub use feedback_queue::{
+    FeedbackMode, FeedbackQueue, FeedbackTimeouts, MAX_POLL_WAIT_ENV, PendingComments,
+    REVIEW_TIMEOUT_ENV, RecordedFeedback,
+};
+pub use feedback_templates::{
+    FEEDBACK_TEMPLATES_ENV, FEEDBACK_TEMPLATES_FILE, FeedbackTemplates, FeedbackTemplatesConfig,
+    FeedbackTemplatesError, TemplateOverrides,
+};
+pub use forge::{
+    ForgeComment, ForgeError, ForgePublisher, ForgePullRequest, GITHUB_API_URL, GitHubCommitRef,
+    GitHubPublisher, GitHubPullRequest, GitHubReviewComment, GitHubUser, PublishedPullRequest,
+    PullRequestBranches, comment_threads_from_github,
+};
+pub use metrics::{FileMetrics, ReviewMetrics};
+pub use policy::{
+    PolicyCheck, PolicyError, PolicyEvaluation, REVIEW_POLICY_FILE, ReviewPolicy, SignOffRule,
+};
+pub use ranking::{
+    FileKind, FileOrdering, FileRanking, FileSection, MAX_SYMBOLS_PER_FILE, RankedFile,
+    defined_symbols,
+};
+pub use strip::{FileEdit, StripError, StripOptions, StripPlan};
+pub use suggestion::{Suggestion, SuggestionError, parse_suggestion_blocks};
+pub use test_links::{TestLink, changed_symbols, link_tests};
+pub use review_store::{LEGACY_REVIEW_FILE, ReviewStore, ReviewStoreError, ReviewSummary};
 pub use mcp_tools::*;
@@ -0,0 +1,335 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use git2::Oid;
+
+use crate::synthetic_pr::{
+    CommentThread, CommentType, GitService, MarkerStatus, ReviewState, USER_AUTHOR,
+};
+
+/// Review policy file, relative to the repository root.
+pub const REVIEW_POLICY_FILE: &str = ".socratic-shell/review-policy.json";
+
+/// Errors that can occur while loading the review policy.
+#[derive(Error, Debug)]
+pub enum PolicyError {
+    #[error("Failed to read review policy: {0}")]
+    Git(#[from] git2::Error),
+
+    #[error("Invalid review policy: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+// ANCHOR: review_policy
+/// Conditions a review must meet before the agent may approve it.
+///
+/// Loaded from [`REVIEW_POLICY_FILE`] as committed at the base of the reviewed range;
+/// every rule is off unless configured, so a repository without the file can approve
+/// any review.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ReviewPolicy {
+    /// Every open ❓ question needs a response from the user
+    #[serde(default)]
+    pub questions_answered: bool,
+
+    /// Comment types the changes may not introduce, named as in the comment marker
+    /// configuration (e.g. `["fixme"]`)
+    #[serde(default)]
+    pub forbid_introduced: Vec<String>,
+
+    /// Paths whose changes need the user's explicit sign-off
+    #[serde(default)]
+    pub sign_off: Vec<SignOffRule>,
+}
+
+/// Changed files needing the user's sign-off: a comment by the user on the file
+/// (starting a thread or replying in one) that contains `phrase`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SignOffRule {
+    /// Files or directories, relative to the repository root (e.g. `"migrations/"`)
+    pub paths: Vec<String>,
+
+    /// Text signing a file off, matched case-insensitively
+    #[serde(default = "default_sign_off_phrase")]
+    pub phrase: String,
+}
+// ANCHOR_END: review_policy
+
+fn default_sign_off_phrase() -> String {
+    "LGTM".to_string()
+}
+
+/// Outcome of checking a review against its repository's policy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PolicyEvaluation {
+    /// True when every configured rule is met
+    pub passed: bool,
+    /// One check per configured rule
+    pub checks: Vec<PolicyCheck>,
+}
+
+/// Outcome of one policy rule.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PolicyCheck {
+    /// Rule checked: `questions_answered`, `forbid_introduced` or `sign_off`
+    pub rule: String,
+    pub passed: bool,
+    /// What keeps the rule from being met, one entry per thread or file
+    pub violations: Vec<String>,
+}
+
+impl ReviewPolicy {
+    /// Load the policy committed at `commit_oid`.
+    ///
+    /// Reviews are checked against the policy of their base commit, never the working
+    /// tree, so neither the changes under review nor the agent can relax it. The threads
+    /// it checks are parsed with the comment marker config of the same commit
+    /// ([`crate::synthetic_pr::CommentConfig::load`]).
+    ///
+    /// # Returns
+    /// * `Ok(ReviewPolicy)` - The configured policy, or an empty one without a policy file
+    /// * `Err(PolicyError)` - Commit not found, or the policy is invalid
+    pub fn load(git_service: &GitService, commit_oid: Oid) -> Result<Self, PolicyError> {
+        match git_service.file_at_commit(REVIEW_POLICY_FILE, commit_oid)? {
+            Some(content) => Ok(serde_json::from_str(&content)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Check a review against the policy.
+    ///
+    /// Outdated threads and resolved markers are left out: their code is gone.
+    pub fn evaluate(&self, review: &ReviewState) -> PolicyEvaluation {
+        let live = || {
+            review
+                .comment_threads
+                .iter()
+                .filter(|thread| !thread.outdated && !thread.is_resolved())
+        };
+
+        let mut checks = Vec::new();
+        if self.questions_answered {
+            let violations = live()
+                .filter(|thread| {
+                    thread.comment_type == CommentType::Question && !thread.answered_by_user()
+                })
+                .map(|thread| format!("unanswered question {}", describe(thread)))
+                .collect();
+            checks.push(PolicyCheck::new("questions_answered", violations));
+        }
+
+        if !self.forbid_introduced.is_empty() {
+            let forbidden: Vec<CommentType> = self
+                .forbid_introduced
+                .iter()
+                .map(|name| CommentType::from_name(name))
+                .collect();
+            let violations = live()
+                .filter(|thread| {
+                    thread.marker_status == Some(MarkerStatus::Introduced)
+                        && forbidden.contains(&thread.comment_type)
+                })
+                .map(|thread| format!("{:?} introduced {}", thread.comment_type, describe(thread)))
+                .collect();
+            checks.push(PolicyCheck::new("forbid_introduced", violations));
+        }
+
+        for rule in &self.sign_off {
+            let violations = review
+                .files_changed
+                .iter()
+                .filter(|file| rule.covers(&file.path) && !rule.signed_off(review, &file.path))
+                .map(|file| {
+                    format!(
+                        "`{}` needs the user's sign-off (a comment on the file saying '{}')",
+                        file.path, rule.phrase
+                    )
+                })
+                .collect();
+            checks.push(PolicyCheck::new("sign_off", violations));
+        }
+
+        PolicyEvaluation {
+            passed: checks.iter().all(|check| check.passed),
+            checks,
+        }
+    }
+}
+
+impl SignOffRule {
+    /// Whether a file is one of the rule's paths or inside one of its directories.
+    fn covers(&self, file_path: &str) -> bool {
+        self.paths.iter().any(|path| {
+            let dir = path.trim_end_matches('/');
+            file_path == dir
+                || file_path
+                    .strip_prefix(dir)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+
+    /// Whether the user left a comment containing the phrase on the file.
+    fn signed_off(&self, review: &ReviewState, file_path: &str) -> bool {
+        let phrase = self.phrase.to_lowercase();
+        let says_phrase = |text: &str| text.to_lowercase().contains(&phrase);
+        review
+            .comment_threads
+            .iter()
+            .filter(|thread| thread.file_path == file_path)
+            .any(|thread| {
+                (thread.author.as_deref() == Some(USER_AUTHOR) && says_phrase(&thread.content))
+                    || thread.responses.iter().any(|response| {
+                        response.author == USER_AUTHOR && says_phrase(&response.content)
+                    })
+            })
+    }
+}
+
+impl PolicyCheck {
+    fn new(rule: &str, violations: Vec<String>) -> Self {
+        PolicyCheck {
+            rule: rule.to_string(),
+            passed: violations.is_empty(),
+            violations,
+        }
+    }
+}
+
+impl PolicyEvaluation {
+    /// Why the review can't be approved, one line per violation; empty when the policy is met.
+    pub fn explain(&self) -> String {
+        self.checks
+            .iter()
+            .flat_map(|check| {
+                check
+                    .violations
+                    .iter()
+                    .map(move |violation| format!("- {}: {}", check.rule, violation))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Where a thread is and how it starts, e.g. ``at `src/cache.rs:4` "Is a minute enough?"``.
+fn describe(thread: &CommentThread) -> String {
+    let first_line = thread.content.lines().next().unwrap_or_default();
+    format!(
+        "at `{}:{}` \"{}\"",
+        thread.file_path, thread.line_number, first_line
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synthetic_pr::{AGENT_AUTHOR, FileChange, ReviewComment};
+
+    fn thread(
+        file_path: &str,
+        line_number: u32,
+        comment_type: CommentType,
+        content: &str,
+    ) -> CommentThread {
+        CommentThread {
+            marker_status: Some(MarkerStatus::Introduced),
+            ..CommentThread::for_test(file_path, line_number, comment_type, content)
+        }
+    }
+
+    fn review() -> ReviewState {
+        let file = |path: &str| FileChange::for_test(path, 1, &[]);
+        ReviewState {
+            files_changed: vec![file("src/db.rs"), file("migrations/001_init.sql")],
+            comment_threads: vec![
+                thread("src/db.rs", 3, CommentType::Question, "Pool size ok?"),
+                thread("src/db.rs", 9, CommentType::Fixme, "Retry on deadlock"),
+                thread("src/db.rs", 12, CommentType::Todo, "Add metrics"),
+            ],
+            ..ReviewState::for_test()
+        }
+    }
+
+    #[test]
+    fn test_policy_violations() {
+        let policy: ReviewPolicy = serde_json::from_str(
+            r#"{
+                "questions_answered": true,
+                "forbid_introduced": ["fixme"],
+                "sign_off": [{ "paths": ["migrations/"] }]
+            }"#,
+        )
+        .unwrap();
+
+        let mut review = review();
+        let evaluation = policy.evaluate(&review);
+        assert!(!evaluation.passed);
+        expect_test::expect![[r#"
+            - questions_answered: unanswered question at `src/db.rs:3` "Pool size ok?"
+            - forbid_introduced: Fixme introduced at `src/db.rs:9` "Retry on deadlock"
+            - sign_off: `migrations/001_init.sql` needs the user's sign-off (a comment on the file saying 'LGTM')"#]].assert_eq(&evaluation.explain());
+
+        // Answering, fixing and signing off meets the policy
+        review
+            .add_comment(
+                &ReviewComment {
+                    thread_id: Some("src/db.rs:3".to_string()),
+                    file_path: None,
+                    line_number: None,
+                    body: "Yes".to_string(),
+                },
+                USER_AUTHOR,
+            )
+            .unwrap();
+        review.comment_threads[1].marker_status = Some(MarkerStatus::Resolved);
+        review
+            .add_comment(
+                &ReviewComment {
+                    thread_id: None,
+                    file_path: Some("migrations/001_init.sql".to_string()),
+                    line_number: Some(1),
+                    body: "lgtm, schema checked".to_string(),
+                },
+                USER_AUTHOR,
+            )
+            .unwrap();
+        let evaluation = policy.evaluate(&review);
+        assert!(evaluation.passed, "{}", evaluation.explain());
+        assert_eq!(evaluation.checks.len(), 3);
+    }
+
+    #[test]
+    fn test_agent_cannot_answer_its_own_questions() {
+        let policy = ReviewPolicy {
+            questions_answered: true,
+            ..Default::default()
+        };
+        let mut review = review();
+        review
+            .add_comment(
+                &ReviewComment {
+                    thread_id: Some("src/db.rs:3".to_string()),
+                    file_path: None,
+                    line_number: None,
+                    body: "Yes, 10 is plenty".to_string(),
+                },
+                AGENT_AUTHOR,
+            )
+            .unwrap();
+
+        let evaluation = policy.evaluate(&review);
+        assert!(!evaluation.passed);
+        expect_test::expect![[
+            r#"- questions_answered: unanswered question at `src/db.rs:3` "Pool size ok?""#
+        ]]
+        .assert_eq(&evaluation.explain());
+    }
+
+    #[test]
+    fn test_empty_policy_passes() {
+        let evaluation = ReviewPolicy::default().evaluate(&review());
+        assert!(evaluation.passed);
+        assert!(evaluation.checks.is_empty());
+    }
+}
@@ -0,0 +1,444 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::synthetic_pr::{CommentType, DiffLineType, FileChange, ReviewState};
+
+/// Most symbols per file whose references are looked up in the IDE.
+pub const MAX_SYMBOLS_PER_FILE: usize = 10;
+
+/// Points per 10 lines changed, and the most a file's size can add.
+const SIZE_POINTS_CAP: i32 = 20;
+/// Points per live ❓ question on the file.
+const QUESTION_POINTS: i32 = 10;
+/// Points per live FIXME on the file.
+const FIXME_POINTS: i32 = 8;
+/// Points per other file referencing the file's symbols, and the most they can add.
+const REFERENCE_POINTS: i32 = 3;
+const REFERENCE_POINTS_CAP: i32 = 15;
+
+/// How a review's files are ordered: the option it was requested with, and what
+/// the IDE found about the files.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct FileOrdering {
+    /// Group the ranked files into sections (source, tests, configuration, ...)
+    #[serde(default)]
+    pub group_files: bool,
+
+    /// For each changed file, the other files referencing the symbols it defines, as
+    /// found by the IDE's findReferences; empty when the IDE couldn't be asked
+    #[serde(default)]
+    pub referenced_by: BTreeMap<String, Vec<String>>,
+}
+
+// ANCHOR: file_ranking
+/// The changed files in the order a reviewer should look at them, riskiest first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FileRanking {
+    /// Every changed file, riskiest first; ties keep the diff order
+    pub files: Vec<RankedFile>,
+    /// The ranked files grouped by kind, in review order; empty unless the review
+    /// was requested with `group_files`
+    pub sections: Vec<FileSection>,
+}
+
+/// A changed file with its risk score and what the score is made of.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RankedFile {
+    pub path: String,
+    pub kind: FileKind,
+    /// Sum of the points listed in `reasons`; higher is riskier
+    pub score: i32,
+    /// Why the file ranks where it does, e.g. "2 open ❓ questions (+20)"
+    pub reasons: Vec<String>,
+}
+
+/// Files of one kind, riskiest first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FileSection {
+    pub kind: FileKind,
+    /// Heading to show, e.g. "Tests"
+    pub title: String,
+    pub files: Vec<String>,
+}
+
+/// What a changed file is, judged from its path. Sections come in this order.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+pub enum FileKind {
+    Source,
+    Test,
+    Configuration,
+    Documentation,
+    /// Lock files and other files tools write
+    Generated,
+}
+// ANCHOR_END: file_ranking
+
+impl FileKind {
+    /// Kind of the file at `path`, relative to the repository root.
+    pub fn of(path: &str) -> Self {
+        let path = Path::new(path);
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let in_dir = |names: &[&str]| {
+            path.parent().is_some_and(|parent| {
+                parent
+                    .components()
+                    .any(|dir| names.iter().any(|name| dir.as_os_str() == *name))
+            })
+        };
+
+        const LOCK_FILES: &[&str] = &[
+            "Cargo.lock",
+            "package-lock.json",
+            "yarn.lock",
+            "pnpm-lock.yaml",
+            "poetry.lock",
+            "Gemfile.lock",
+            "composer.lock",
+            "go.sum",
+        ];
+        if LOCK_FILES.contains(&file_name) || file_name.contains(".min.") {
+            return FileKind::Generated;
+        }
+
+        if in_dir(&["tests", "test", "__tests__", "spec"])
+            || stem.starts_with("test_")
+            || [".test", ".spec", "_test", "_tests", "_spec"]
+                .iter()
+                .any(|suffix| stem.ends_with(suffix))
+        {
+            return FileKind::Test;
+        }
+
+        if matches!(extension.as_str(), "md" | "mdx" | "rst" | "adoc" | "txt") || in_dir(&["docs"])
+        {
+            return FileKind::Documentation;
+        }
+
+        if matches!(
+            extension.as_str(),
+            "toml" | "json" | "yaml" | "yml" | "ini" | "cfg" | "lock" | "xml"
+        ) || file_name.starts_with('.')
+            || matches!(file_name, "Dockerfile" | "Makefile" | "build.rs")
+            || in_dir(&[".github"])
+        {
+            return FileKind::Configuration;
+        }
+
+        FileKind::Source
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            FileKind::Source => "Source",
+            FileKind::Test => "Tests",
+            FileKind::Configuration => "Build and configuration",
+            FileKind::Documentation => "Documentation",
+            FileKind::Generated => "Generated files",
+        }
+    }
+
+    /// Points for the kind of file and how to describe it.
+    fn points(self) -> (i32, &'static str) {
+        match self {
+            FileKind::Source => (10, "source code"),
+            FileKind::Test => (0, "test code"),
+            FileKind::Configuration => (2, "build or configuration file"),
+            FileKind::Documentation => (-5, "documentation"),
+            FileKind::Generated => (-20, "generated file"),
+        }
+    }
+}
+
+impl FileRanking {
+    /// Rank the changed files of a review.
+    ///
+    /// A file's score adds up its kind (source code before tests, generated files
+    /// last), the size of its change, the live ❓ questions and FIXMEs on it, and how
+    /// many other files reference the symbols it defines.
+    pub fn for_review(review: &ReviewState) -> Self {
+        let mut files: Vec<RankedFile> = review
+            .files_changed
+            .iter()
+            .map(|file| rank_file(review, file))
+            .collect();
+        // Stable, so ties keep the diff order
+        files.sort_by_key(|file| std::cmp::Reverse(file.score));
+
+        let mut sections: Vec<FileSection> = Vec::new();
+        if review.file_ordering.group_files {
+            let mut by_kind: BTreeMap<FileKind, Vec<String>> = BTreeMap::new();
+            for file in &files {
+                by_kind
+                    .entry(file.kind)
+                    .or_default()
+                    .push(file.path.clone());
+            }
+            sections = by_kind
+                .into_iter()
+                .map(|(kind, files)| FileSection {
+                    kind,
+                    title: kind.title().to_string(),
+                    files,
+                })
+                .collect();
+        }
+
+        FileRanking { files, sections }
+    }
+}
+
+fn rank_file(review: &ReviewState, file: &FileChange) -> RankedFile {
+    let kind = FileKind::of(&file.path);
+    let mut score = 0;
+    let mut reasons = Vec::new();
+    let mut add = |points: i32, reason: String| {
+        score += points;
+        reasons.push(format!("{} ({:+})", reason, points));
+    };
+
+    let (points, description) = kind.points();
+    add(points, description.to_string());
+
+    let lines_changed = file.additions + file.deletions;
+    if lines_changed > 0 {
+        let points = i32::try_from(lines_changed / 10)
+            .unwrap_or(i32::MAX)
+            .min(SIZE_POINTS_CAP);
+        add(points, format!("{} lines changed", lines_changed));
+    }
+
+    let live_threads = |comment_type: CommentType| {
+        review
+            .comment_threads
+            .iter()
+            .filter(|thread| {
+                thread.file_path == file.path
+                    && thread.comment_type == comment_type
+                    && !thread.outdated
+                    && !thread.is_resolved()
+            })
+            .count() as i32
+    };
+    let questions = live_threads(CommentType::Question);
+    if questions > 0 {
+        add(
+            questions * QUESTION_POINTS,
+            format!("{} open ❓ {}", questions, plural(questions, "question")),
+        );
+    }
+    let fixmes = live_threads(CommentType::Fixme);
+    if fixmes > 0 {
+        add(
+            fixmes * FIXME_POINTS,
+            format!("{} {}", fixmes, plural(fixmes, "FIXME")),
+        );
+    }
+
+    let referenced_by = review
+        .file_ordering
+        .referenced_by
+        .get(&file.path)
+        .map_or(0, |files| files.len() as i32);
+    if referenced_by > 0 {
+        add(
+            (referenced_by * REFERENCE_POINTS).min(REFERENCE_POINTS_CAP),
+            format!(
+                "referenced from {} other {}",
+                referenced_by,
+                plural(referenced_by, "file")
+            ),
+        );
+    }
+
+    RankedFile {
+        path: file.path.clone(),
+        kind,
+        score,
+        reasons,
+    }
+}
+
+fn plural(count: i32, noun: &str) -> String {
+    if count == 1 {
+        noun.to_string()
+    } else {
+        format!("{}s", noun)
+    }
+}
+
+/// Names of the symbols (functions, types, classes, ...) a change defines on its
+/// added lines, at most [`MAX_SYMBOLS_PER_FILE`], for looking up their references.
+///
+/// Only source files are looked at; the definitions are found with a keyword
+/// heuristic that covers Rust, TypeScript/JavaScript, Python and similar languages.
+pub fn defined_symbols(file: &FileChange) -> Vec<String> {
+    if FileKind::of(&file.path) != FileKind::Source {
+        return vec![];
+    }
+
+    let added = file
+        .hunks
+        .iter()
+        .flat_map(|hunk| &hunk.lines)
+        .filter(|line| matches!(line.line_type, DiffLineType::Added))
+        .map(|line| line.content.as_str());
+    definitions_in(added)
+}
+
+/// Names defined on the given lines, in order and without duplicates, at most
+/// [`MAX_SYMBOLS_PER_FILE`].
+pub(crate) fn definitions_in<'a>(lines: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let definition = Regex::new(
+        r"\b(?:fn|struct|enum|trait|type|union|class|interface|def|function|func)\s+([A-Za-z_][A-Za-z0-9_]*)",
+    )
+    .expect("valid definition regex");
+
+    let mut symbols: Vec<String> = Vec::new();
+    for line in lines {
+        for captures in definition.captures_iter(line) {
+            let name = captures[1].to_string();
+            if !symbols.contains(&name) {
+                symbols.push(name);
+            }
+            if symbols.len() == MAX_SYMBOLS_PER_FILE {
+                return symbols;
+            }
+        }
+    }
+    symbols
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synthetic_pr::{CommentThread, MarkerStatus};
+
+    fn file(path: &str, additions: u32, added_lines: &[&str]) -> FileChange {
+        FileChange::for_test(path, additions, added_lines)
+    }
+
+    fn thread(file_path: &str, comment_type: CommentType) -> CommentThread {
+        CommentThread {
+            marker_status: Some(MarkerStatus::Introduced),
+            ..CommentThread::for_test(file_path, 1, comment_type, "...")
+        }
+    }
+
+    fn review(group_files: bool) -> ReviewState {
+        ReviewState {
+            files_changed: vec![
+                file("Cargo.lock", 120, &[]),
+                file("README.md", 10, &[]),
+                file("tests/cache_test.rs", 60, &["fn test_expiry() {"]),
+                file("src/util.rs", 5, &["pub fn clamp(x: u32) -> u32 {"]),
+                file(
+                    "src/cache.rs",
+                    45,
+                    &["pub struct Cache {", "fn evict(&mut self) {"],
+                ),
+            ],
+            comment_threads: vec![
+                thread("src/cache.rs", CommentType::Question),
+                thread("src/cache.rs", CommentType::Fixme),
+                thread("src/util.rs", CommentType::Explanation),
+            ],
+            file_ordering: FileOrdering {
+                group_files,
+                referenced_by: BTreeMap::from([(
+                    "src/util.rs".to_string(),
+                    vec!["src/cache.rs".to_string(), "src/main.rs".to_string()],
+                )]),
+            },
+            ..ReviewState::for_test()
+        }
+    }
+
+    #[test]
+    fn test_rank_files() {
+        let ranking = FileRanking::for_review(&review(false));
+        assert!(ranking.sections.is_empty());
+
+        let summary: Vec<String> = ranking
+            .files
+            .iter()
+            .map(|file| format!("{} {}: {}", file.score, file.path, file.reasons.join(", ")))
+            .collect();
+        expect_test::expect![[r#"
+            [
+                "32 src/cache.rs: source code (+10), 45 lines changed (+4), 1 open ❓ question (+10), 1 FIXME (+8)",
+                "16 src/util.rs: source code (+10), 5 lines changed (+0), referenced from 2 other files (+6)",
+                "6 tests/cache_test.rs: test code (+0), 60 lines changed (+6)",
+                "-4 README.md: documentation (-5), 10 lines changed (+1)",
+                "-8 Cargo.lock: generated file (-20), 120 lines changed (+12)",
+            ]
+        "#]]
+        .assert_debug_eq(&summary);
+    }
+
+    #[test]
+    fn test_group_files_into_sections() {
+        let ranking = FileRanking::for_review(&review(true));
+        let sections: Vec<(&str, &[String])> = ranking
+            .sections
+            .iter()
+            .map(|section| (section.title.as_str(), section.files.as_slice()))
+            .collect();
+        assert_eq!(
+            sections,
+            vec![
+                (
+                    "Source",
+                    &["src/cache.rs".to_string(), "src/util.rs".to_string()][..]
+                ),
+                ("Tests", &["tests/cache_test.rs".to_string()][..]),
+                ("Documentation", &["README.md".to_string()][..]),
+                ("Generated files", &["Cargo.lock".to_string()][..]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_file_kinds_and_defined_symbols() {
+        assert_eq!(FileKind::of("server/src/lib.rs"), FileKind::Source);
+        assert_eq!(FileKind::of("src/parser.test.ts"), FileKind::Test);
+        assert_eq!(FileKind::of("test_utils.py"), FileKind::Test);
+        assert_eq!(FileKind::of("server/Cargo.toml"), FileKind::Configuration);
+        assert_eq!(
+            FileKind::of(".github/workflows/ci.yml"),
+            FileKind::Configuration
+        );
+        assert_eq!(
+            FileKind::of("md/design/ranking.md"),
+            FileKind::Documentation
+        );
+        assert_eq!(
+            FileKind::of("extension/package-lock.json"),
+            FileKind::Generated
+        );
+
+        let review = review(false);
+        assert_eq!(
+            defined_symbols(&review.files_changed[4]),
+            vec!["Cache", "evict"]
+        );
+        assert!(defined_symbols(&review.files_changed[2]).is_empty());
+    }
+}
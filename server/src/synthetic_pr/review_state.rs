@@ -1,6 +1,31 @@
 use chrono::{DateTime, Utc};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::synthetic_pr::{CommentAnchor, DiffSettings, FileOrdering, Suggestion, TestLink};
+
+/// Author name recorded for comments written by the agent.
+pub const AGENT_AUTHOR: &str = "agent";
+
+/// Author name recorded for comments written by the user in the IDE.
+pub const USER_AUTHOR: &str = "user";
+
+/// Errors that can occur while modifying a review.
+#[derive(Error, Debug)]
+pub enum ReviewError {
+    #[error("Comment thread not found: {thread_id}")]
+    ThreadNotFound { thread_id: String },
+
+    #[error("A new comment thread needs both a file_path and a line_number")]
+    MissingLocation,
+
+    #[error("Cannot move a {from:?} review to {to:?}")]
+    InvalidTransition {
+        from: ReviewStatus,
+        to: ReviewStatus,
+    },
+}
 
 /// Complete state of a synthetic pull request review.
 ///
@@ -12,15 +37,33 @@ pub struct ReviewState {
     pub title: String,
     pub description: serde_json::Value,
     pub commit_range: String,
+    /// Diff settings used to generate `files_changed`, reused when the review is refreshed
+    #[serde(default)]
+    pub diff_settings: DiffSettings,
     pub status: ReviewStatus,
     pub files_changed: Vec<FileChange>,
+    /// Commits in the range, oldest first, for commit-by-commit review
+    #[serde(default)]
+    pub commits: Vec<ReviewCommit>,
+    /// Snapshots of the reviewed code, one per review round, oldest first
+    #[serde(default)]
+    pub rounds: Vec<ReviewRound>,
     pub comment_threads: Vec<CommentThread>,
+    /// Append-only history of what happened to the review, oldest first
+    #[serde(default)]
+    pub events: Vec<ReviewEvent>,
+    /// Changes suggested in comments with ```` ```suggestion ```` blocks, oldest first
+    #[serde(default)]
+    pub suggestions: Vec<Suggestion>,
+    /// Options and IDE findings used to rank `files_changed` for reviewers
+    #[serde(default)]
+    pub file_ordering: FileOrdering,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
 /// Current status of a synthetic pull request review workflow.
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum ReviewStatus {
     Pending,
     ChangesRequested,
@@ -28,8 +71,67 @@ pub enum ReviewStatus {
     Merged,
 }
 
-/// Type of line in a diff
+impl ReviewStatus {
+    /// Statuses a review in this status may move to.
+    ///
+    /// Changes can be requested on a pending or approved review, a review can be
+    /// approved until it is merged, and only an approved review can be merged.
+    /// A merged review is final.
+    pub fn allowed_transitions(&self) -> &'static [ReviewStatus] {
+        match self {
+            ReviewStatus::Pending => &[ReviewStatus::ChangesRequested, ReviewStatus::Approved],
+            ReviewStatus::ChangesRequested => &[ReviewStatus::Approved],
+            ReviewStatus::Approved => &[ReviewStatus::ChangesRequested, ReviewStatus::Merged],
+            ReviewStatus::Merged => &[],
+        }
+    }
+
+    /// Whether a review in this status may move to `to`.
+    pub fn can_transition_to(&self, to: &ReviewStatus) -> bool {
+        self.allowed_transitions().contains(to)
+    }
+}
+
+/// Entry in a review's history: what happened, who did it and when.
+// ANCHOR: review_event
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ReviewEvent {
+    pub kind: ReviewEventKind,
+    /// Who caused the event (e.g., [`AGENT_AUTHOR`] or [`USER_AUTHOR`])
+    pub actor: String,
+    pub timestamp: DateTime<Utc>,
+    /// Thread the event refers to, for comments and applied suggestions
+    #[serde(default)]
+    pub thread_id: Option<String>,
+}
+
+/// Kind of event recorded in a review's history.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum ReviewEventKind {
+    Created,
+    Comment,
+    ChangesRequested,
+    Approved,
+    Merged,
+    /// A suggested change was applied to the working tree
+    SuggestionApplied,
+}
+// ANCHOR_END: review_event
+
+impl ReviewEventKind {
+    /// Event recorded when a review moves to the given status.
+    fn for_status(status: &ReviewStatus) -> Option<Self> {
+        match status {
+            ReviewStatus::Pending => None,
+            ReviewStatus::ChangesRequested => Some(ReviewEventKind::ChangesRequested),
+            ReviewStatus::Approved => Some(ReviewEventKind::Approved),
+            ReviewStatus::Merged => Some(ReviewEventKind::Merged),
+        }
+    }
+}
+
+/// Type of line in a diff
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum DiffLineType {
     /// Added line
     Added,
@@ -50,6 +152,9 @@ pub struct DiffLine {
     pub old_line_number: Option<usize>,
     /// Line number in the new file (None for removed lines)
     pub new_line_number: Option<usize>,
+    /// True for the last line of a file that doesn't end with a newline
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub missing_newline: bool,
 }
 
 /// Represents a diff hunk (contiguous block of changes)
@@ -69,6 +174,20 @@ pub struct DiffHunk {
     pub lines: Vec<DiffLine>,
 }
 
+impl DiffHunk {
+    /// Whether a (1-based) line of the new file falls within this hunk.
+    pub fn contains_new_line(&self, line_number: u32) -> bool {
+        let line = line_number as usize;
+        line >= self.new_start && line < self.new_start + self.new_lines
+    }
+
+    /// Whether a (1-based) line of the old file falls within this hunk.
+    pub fn contains_old_line(&self, line_number: u32) -> bool {
+        let line = line_number as usize;
+        line >= self.old_start && line < self.old_start + self.old_lines
+    }
+}
+
 /// Represents a single file change in a synthetic pull request.
 ///
 /// Contains file path, change type, line-level statistics, and detailed diff hunks
@@ -81,6 +200,62 @@ pub struct FileChange {
     pub deletions: u32,
     /// Diff hunks containing line-by-line changes
     pub hunks: Vec<DiffHunk>,
+    /// Blob ids and modes of the file before and after the change; None for reviews
+    /// stored before they were recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<FileIndex>,
+    /// Changed test files exercising this source file, or the changed source files
+    /// this test file exercises; only set on a review's `files_changed`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub test_links: Vec<TestLink>,
+}
+
+/// Blob ids and file modes on either side of a file change, as in a diff's `index` line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct FileIndex {
+    /// Blob before the change; None for added files, or when it isn't known
+    pub old_oid: Option<String>,
+    /// Blob after the change; None for deleted files, or uncommitted files not hashed yet
+    pub new_oid: Option<String>,
+    /// Mode before the change (e.g. `0o100644`); 0 for added files
+    pub old_mode: u32,
+    /// Mode after the change; 0 for deleted files
+    pub new_mode: u32,
+}
+
+/// A single commit within the range of a synthetic pull request.
+///
+/// Lets reviewers step through the changes commit-by-commit instead of only
+/// seeing the cumulative diff in `ReviewState::files_changed`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ReviewCommit {
+    /// Full commit SHA, or None for uncommitted changes in the working tree
+    pub sha: Option<String>,
+    /// Author as "Name <email>"
+    pub author: String,
+    /// Full commit message
+    pub message: String,
+    /// Commit time (or capture time for uncommitted changes)
+    pub timestamp: DateTime<Utc>,
+    /// Changes introduced by this commit relative to its first parent
+    pub files_changed: Vec<FileChange>,
+}
+
+/// One round of a review: the code as the reviewer saw it at that point.
+///
+/// A new round starts whenever the reviewed code changes between tool calls, e.g.
+/// after the agent addresses requested changes, so reviewers can look at just
+/// what changed in response instead of re-reading the whole diff.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ReviewRound {
+    /// Round number, starting at 1
+    pub number: u32,
+    /// Git tree snapshotting the reviewed code (working tree changes included)
+    pub tree_oid: String,
+    /// When the round started
+    pub created_at: DateTime<Utc>,
+    /// Changes since the previous round (empty for the first round)
+    pub interdiff: Vec<FileChange>,
 }
 
 /// Type of change made to a file in the Git diff.
@@ -99,14 +274,66 @@ pub enum ChangeStatus {
 pub struct CommentThread {
     pub thread_id: String,
     pub file_path: String,
+    /// First line of the comment (in the old version of the file for resolved markers)
     pub line_number: u32,
+    /// Last line of a comment spanning several lines (continuation lines or a block
+    /// comment); None for single-line comments
+    #[serde(default)]
+    pub end_line_number: Option<u32>,
     pub comment_type: CommentType,
     pub content: String,
     pub responses: Vec<UserResponse>,
+    /// Commit in the review range that introduced this comment, if known
+    #[serde(default)]
+    pub commit_sha: Option<String>,
+    /// Who started the thread; None for insight comments harvested from the code
+    #[serde(default)]
+    pub author: Option<String>,
+    /// Content the thread is attached to, used to follow it as the code changes
+    #[serde(default)]
+    pub anchor: Option<CommentAnchor>,
+    /// True when the anchored code can no longer be found; `line_number` is its last known position
+    #[serde(default)]
+    pub outdated: bool,
+    /// How the reviewed changes affected the insight comment; None for discussion threads
+    #[serde(default)]
+    pub marker_status: Option<MarkerStatus>,
+}
+
+impl CommentThread {
+    /// Lines covered by the comment, first to last.
+    pub fn line_range(&self) -> std::ops::RangeInclusive<u32> {
+        self.line_number..=self.end_line_number.unwrap_or(self.line_number)
+    }
+
+    /// Whether the thread is an insight comment the reviewed changes removed.
+    pub fn is_resolved(&self) -> bool {
+        self.marker_status == Some(MarkerStatus::Resolved)
+    }
+
+    /// Whether the user replied to the thread; agent replies alone don't count as an answer.
+    pub fn answered_by_user(&self) -> bool {
+        self.responses
+            .iter()
+            .any(|response| response.author == USER_AUTHOR)
+    }
 }
 
+// ANCHOR: marker_status
+/// Whether an insight comment was introduced, carried over or removed by the reviewed changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum MarkerStatus {
+    /// Added by the changes
+    Introduced,
+    /// Already in the code before the changes (on a context line, or moved unchanged)
+    PreExisting,
+    /// Removed by the changes, e.g. a TODO that was done; lines refer to the old file
+    Resolved,
+}
+// ANCHOR_END: marker_status
+
 /// Type of AI insight comment for categorization and display.
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum CommentType {
     /// 💡 Explanations of implementation decisions and design choices
     Explanation,
@@ -116,6 +343,12 @@ pub enum CommentType {
     Todo,
     /// FIXME items indicating known issues that need addressing
     Fixme,
+    /// 💬 Discussion started during the review by the user or the agent
+    Comment,
+    /// Type defined by a marker rule in the comment marker configuration (e.g. `SAFETY:`),
+    /// serialized as its bare name
+    #[serde(untagged)]
+    Custom(String),
 }
 
 /// A user response to an AI insight comment in a review thread.
@@ -126,6 +359,21 @@ pub struct UserResponse {
     pub timestamp: DateTime<Utc>,
 }
 
+/// A comment added to a review, either as a reply or as a new thread.
+// ANCHOR: review_comment
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ReviewComment {
+    /// Thread to reply to; when omitted, a new thread is started at `file_path`/`line_number`
+    pub thread_id: Option<String>,
+    /// File for a new thread, relative to the repository root
+    pub file_path: Option<String>,
+    /// Line (1-based) for a new thread
+    pub line_number: Option<u32>,
+    /// Comment text (markdown)
+    pub body: String,
+}
+// ANCHOR_END: review_comment
+
 /// Intermediate representation of a parsed AI insight comment.
 ///
 /// Used during comment extraction before creating full CommentThread objects.
@@ -136,39 +384,377 @@ pub struct ParsedComment {
 }
 
 impl ReviewState {
-    /// Save the review state to a JSON file for persistence across sessions.
+    /// Add a comment to the review, replying to an existing thread or starting a new one.
     ///
     /// # Arguments
-    /// * `directory` - Optional directory where to create the file. Uses current directory if None.
+    /// * `comment` - Reply target or location, plus the comment body
+    /// * `author` - Who wrote the comment (e.g., [`AGENT_AUTHOR`] or [`USER_AUTHOR`])
     ///
     /// # Returns
-    /// * `Ok(())` - Successfully saved to `.socratic-shell-review.json`
-    /// * `Err(std::io::Error)` - File write or serialization error
-    pub fn save_to_file<P: AsRef<std::path::Path>>(&self, directory: Option<P>) -> Result<(), std::io::Error> {
-        let json = serde_json::to_string_pretty(self)?;
-        let file_path = match directory {
-            Some(dir) => dir.as_ref().join(".socratic-shell-review.json"),
-            None => std::path::PathBuf::from(".socratic-shell-review.json"),
+    /// * `Ok(String)` - ID of the thread the comment was added to
+    /// * `Err(ReviewError)` - Unknown thread, or no location for a new thread
+    pub fn add_comment(
+        &mut self,
+        comment: &ReviewComment,
+        author: &str,
+    ) -> Result<String, ReviewError> {
+        let now = Utc::now();
+
+        let thread_id = match &comment.thread_id {
+            Some(thread_id) => {
+                let thread = self
+                    .comment_threads
+                    .iter_mut()
+                    .find(|t| &t.thread_id == thread_id)
+                    .ok_or_else(|| ReviewError::ThreadNotFound {
+                        thread_id: thread_id.clone(),
+                    })?;
+
+                thread.responses.push(UserResponse {
+                    author: author.to_string(),
+                    content: comment.body.clone(),
+                    timestamp: now,
+                });
+                thread_id.clone()
+            }
+            None => {
+                let (Some(file_path), Some(line_number)) =
+                    (&comment.file_path, comment.line_number)
+                else {
+                    return Err(ReviewError::MissingLocation);
+                };
+
+                let thread_id = uuid::Uuid::new_v4().to_string();
+                self.comment_threads.push(CommentThread {
+                    thread_id: thread_id.clone(),
+                    file_path: file_path.clone(),
+                    line_number,
+                    end_line_number: None,
+                    comment_type: CommentType::Comment,
+                    content: comment.body.clone(),
+                    responses: vec![],
+                    commit_sha: None,
+                    author: Some(author.to_string()),
+                    anchor: None,
+                    outdated: false,
+                    marker_status: None,
+                });
+                thread_id
+            }
         };
-        std::fs::write(file_path, json)?;
-        Ok(())
+
+        self.record_event(ReviewEventKind::Comment, author, Some(thread_id.clone()));
+        Ok(thread_id)
     }
 
-    /// Load review state from the JSON persistence file.
+    /// Move the review to a new status, recording who did it.
     ///
     /// # Arguments
-    /// * `directory` - Optional directory where to look for the file. Uses current directory if None.
+    /// * `to` - Status to move to
+    /// * `actor` - Who made the change (e.g., [`AGENT_AUTHOR`] or [`USER_AUTHOR`])
     ///
     /// # Returns
-    /// * `Ok(ReviewState)` - Successfully loaded from `.socratic-shell-review.json`
-    /// * `Err(Box<dyn std::error::Error>)` - File not found, read error, or parse error
-    pub fn load_from_file<P: AsRef<std::path::Path>>(directory: Option<P>) -> Result<Self, Box<dyn std::error::Error>> {
-        let file_path = match directory {
-            Some(dir) => dir.as_ref().join(".socratic-shell-review.json"),
-            None => std::path::PathBuf::from(".socratic-shell-review.json"),
+    /// * `Ok(())` - Status changed and the event recorded
+    /// * `Err(ReviewError::InvalidTransition)` - The transition table does not allow it
+    pub fn transition(&mut self, to: ReviewStatus, actor: &str) -> Result<(), ReviewError> {
+        let kind = ReviewEventKind::for_status(&to)
+            .filter(|_| self.status.can_transition_to(&to))
+            .ok_or_else(|| ReviewError::InvalidTransition {
+                from: self.status.clone(),
+                to: to.clone(),
+            })?;
+
+        self.status = to;
+        self.record_event(kind, actor, None);
+        Ok(())
+    }
+
+    /// Append an event to the review's history and bump `updated_at`.
+    pub(crate) fn record_event(&mut self, kind: ReviewEventKind, actor: &str, thread_id: Option<String>) {
+        let timestamp = Utc::now();
+        self.events.push(ReviewEvent {
+            kind,
+            actor: actor.to_string(),
+            timestamp,
+            thread_id,
+        });
+        self.updated_at = timestamp;
+    }
+
+    /// Find the live thread covering a given file and line, if any.
+    ///
+    /// Outdated threads and resolved markers are skipped: the lines of a resolved marker
+    /// refer to the old version of the file.
+    pub fn find_thread_at(&self, file_path: &str, line_number: u32) -> Option<&CommentThread> {
+        self.comment_threads.iter().find(|t| {
+            !t.outdated
+                && !t.is_resolved()
+                && t.file_path == file_path
+                && t.line_range().contains(&line_number)
+        })
+    }
+}
+
+#[cfg(test)]
+impl ReviewState {
+    /// Pending review `review-1` of `HEAD` with no changes, threads or history, for tests
+    /// to fill in with struct update syntax.
+    pub(crate) fn for_test() -> Self {
+        let now = Utc::now();
+        ReviewState {
+            review_id: "review-1".to_string(),
+            title: "Test review".to_string(),
+            description: serde_json::Value::Null,
+            commit_range: "HEAD".to_string(),
+            diff_settings: DiffSettings::default(),
+            status: ReviewStatus::Pending,
+            files_changed: vec![],
+            commits: vec![],
+            rounds: vec![],
+            comment_threads: vec![],
+            events: vec![],
+            suggestions: vec![],
+            file_ordering: FileOrdering::default(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+#[cfg(test)]
+impl FileChange {
+    /// Modified `path` with `additions` added lines and no deletions; `added_lines` (if any) form
+    /// its single hunk at the top of the file.
+    pub(crate) fn for_test(path: &str, additions: u32, added_lines: &[&str]) -> Self {
+        let hunks = if added_lines.is_empty() {
+            vec![]
+        } else {
+            vec![DiffHunk {
+                header: format!("@@ -1,0 +1,{} @@", added_lines.len()),
+                old_start: 1,
+                old_lines: 0,
+                new_start: 1,
+                new_lines: added_lines.len(),
+                lines: added_lines
+                    .iter()
+                    .enumerate()
+                    .map(|(index, content)| DiffLine {
+                        line_type: DiffLineType::Added,
+                        content: content.to_string(),
+                        old_line_number: None,
+                        new_line_number: Some(index + 1),
+                        missing_newline: false,
+                    })
+                    .collect(),
+            }]
+        };
+        FileChange {
+            path: path.to_string(),
+            status: ChangeStatus::Modified,
+            additions,
+            deletions: 0,
+            hunks,
+            index: None,
+            test_links: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+impl CommentThread {
+    /// Live single-line thread `file_path:line_number` with no responses, author or marker status.
+    pub(crate) fn for_test(
+        file_path: &str,
+        line_number: u32,
+        comment_type: CommentType,
+        content: &str,
+    ) -> Self {
+        CommentThread {
+            thread_id: format!("{}:{}", file_path, line_number),
+            file_path: file_path.to_string(),
+            line_number,
+            end_line_number: None,
+            comment_type,
+            content: content.to_string(),
+            responses: vec![],
+            commit_sha: None,
+            author: None,
+            anchor: None,
+            outdated: false,
+            marker_status: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_review() -> ReviewState {
+        ReviewState {
+            comment_threads: vec![CommentThread::for_test(
+                "src/auth.rs",
+                3,
+                CommentType::Question,
+                "Should we add rate limiting here?",
+            )],
+            ..ReviewState::for_test()
+        }
+    }
+
+    #[test]
+    fn test_add_comment_replies_to_thread() {
+        let mut review = test_review();
+
+        let thread_id = review
+            .add_comment(
+                &ReviewComment {
+                    thread_id: Some("src/auth.rs:3".to_string()),
+                    file_path: None,
+                    line_number: None,
+                    body: "Yes, a token bucket per client".to_string(),
+                },
+                AGENT_AUTHOR,
+            )
+            .unwrap();
+
+        assert_eq!(thread_id, "src/auth.rs:3");
+        assert_eq!(review.comment_threads.len(), 1);
+        let responses = &review.comment_threads[0].responses;
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].author, AGENT_AUTHOR);
+        assert_eq!(responses[0].content, "Yes, a token bucket per client");
+    }
+
+    #[test]
+    fn test_add_comment_starts_new_thread() {
+        let mut review = test_review();
+
+        let thread_id = review
+            .add_comment(
+                &ReviewComment {
+                    thread_id: None,
+                    file_path: Some("src/payment.rs".to_string()),
+                    line_number: Some(12),
+                    body: "This retries forever".to_string(),
+                },
+                USER_AUTHOR,
+            )
+            .unwrap();
+
+        let thread = review.find_thread_at("src/payment.rs", 12).unwrap();
+        assert_eq!(thread.thread_id, thread_id);
+        assert!(matches!(thread.comment_type, CommentType::Comment));
+        assert_eq!(thread.author.as_deref(), Some(USER_AUTHOR));
+        assert_eq!(thread.content, "This retries forever");
+    }
+
+    #[test]
+    fn test_find_thread_at_skips_resolved_markers() {
+        let mut review = test_review();
+        review.comment_threads[0].marker_status = Some(MarkerStatus::Resolved);
+
+        // Line 3 of the old file: the new file's line 3 has no thread
+        assert!(review.find_thread_at("src/auth.rs", 3).is_none());
+
+        review.comment_threads[0].marker_status = Some(MarkerStatus::PreExisting);
+        assert!(review.find_thread_at("src/auth.rs", 3).is_some());
+    }
+
+    #[test]
+    fn test_add_comment_errors() {
+        let mut review = test_review();
+
+        let unknown_thread = ReviewComment {
+            thread_id: Some("nope".to_string()),
+            file_path: None,
+            line_number: None,
+            body: "Hello?".to_string(),
         };
-        let content = std::fs::read_to_string(file_path)?;
-        let review: ReviewState = serde_json::from_str(&content)?;
-        Ok(review)
+        assert!(matches!(
+            review.add_comment(&unknown_thread, AGENT_AUTHOR),
+            Err(ReviewError::ThreadNotFound { .. })
+        ));
+
+        let no_location = ReviewComment {
+            thread_id: None,
+            file_path: Some("src/auth.rs".to_string()),
+            line_number: None,
+            body: "Somewhere".to_string(),
+        };
+        assert!(matches!(
+            review.add_comment(&no_location, AGENT_AUTHOR),
+            Err(ReviewError::MissingLocation)
+        ));
+    }
+
+    #[test]
+    fn test_status_transitions() {
+        let mut review = test_review();
+
+        review
+            .transition(ReviewStatus::ChangesRequested, USER_AUTHOR)
+            .unwrap();
+        review
+            .transition(ReviewStatus::Approved, AGENT_AUTHOR)
+            .unwrap();
+        review
+            .transition(ReviewStatus::Merged, AGENT_AUTHOR)
+            .unwrap();
+        assert_eq!(review.status, ReviewStatus::Merged);
+
+        // A merged review is final, and rejected transitions leave no trace
+        assert!(matches!(
+            review.transition(ReviewStatus::Approved, AGENT_AUTHOR),
+            Err(ReviewError::InvalidTransition {
+                from: ReviewStatus::Merged,
+                to: ReviewStatus::Approved,
+            })
+        ));
+        assert_eq!(review.status, ReviewStatus::Merged);
+        assert_eq!(review.events.len(), 3);
+
+        // Pending is only ever the initial status, and merging needs an approval
+        assert!(!ReviewStatus::Approved.can_transition_to(&ReviewStatus::Pending));
+        assert!(!ReviewStatus::Pending.can_transition_to(&ReviewStatus::Merged));
+    }
+
+    #[test]
+    fn test_events_record_history() {
+        let mut review = test_review();
+
+        let thread_id = review
+            .add_comment(
+                &ReviewComment {
+                    thread_id: Some("src/auth.rs:3".to_string()),
+                    file_path: None,
+                    line_number: None,
+                    body: "Yes, please".to_string(),
+                },
+                USER_AUTHOR,
+            )
+            .unwrap();
+        review
+            .transition(ReviewStatus::Approved, USER_AUTHOR)
+            .unwrap();
+
+        let history: Vec<_> = review
+            .events
+            .iter()
+            .map(|e| (e.kind.clone(), e.actor.as_str(), e.thread_id.as_deref()))
+            .collect();
+        assert_eq!(
+            history,
+            vec![
+                (
+                    ReviewEventKind::Comment,
+                    USER_AUTHOR,
+                    Some(thread_id.as_str())
+                ),
+                (ReviewEventKind::Approved, USER_AUTHOR, None),
+            ]
+        );
+        assert!(review.events[0].timestamp <= review.events[1].timestamp);
+        assert_eq!(review.updated_at, review.events[1].timestamp);
     }
 }
@@ -0,0 +1,383 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use git2::Repository;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::warn;
+
+use crate::synthetic_pr::{ReviewState, ReviewStatus};
+
+/// Directory (relative to the repository's `.git` directory) holding one JSON file per review.
+///
+/// Keeping reviews out of the working tree means they never show up in diffs or `git status`.
+pub const REVIEW_STORE_DIR: &str = "socratic-shell/reviews";
+
+/// Review file that older versions wrote into the root of the working tree.
+pub const LEGACY_REVIEW_FILE: &str = ".socratic-shell-review.json";
+
+/// Name a legacy review file that can't be parsed is moved to inside the store directory;
+/// it can't be mistaken for a review.
+const INVALID_LEGACY_FILE: &str = "legacy-review.json.invalid";
+
+/// Review id whose file would be the store's index, `index.json`.
+const INDEX_ID: &str = "index";
+
+/// Name of the index file inside the review store directory.
+const INDEX_FILE: &str = "index.json";
+
+/// Lock file held while a review and its index entry are written.
+const LOCK_FILE: &str = "index.lock";
+
+/// Errors that can occur while reading or writing the review store.
+#[derive(Error, Debug)]
+pub enum ReviewStoreError {
+    #[error("Review not found: {review_id}")]
+    NotFound { review_id: String },
+
+    #[error("Invalid review id: {review_id:?}")]
+    InvalidId { review_id: String },
+
+    #[error("Review store I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Review store serialization error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Failed to open repository for review store: {0}")]
+    Git(#[from] git2::Error),
+}
+
+/// Summary of a stored review, as kept in the store's index.
+// ANCHOR: review_summary
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ReviewSummary {
+    pub review_id: String,
+    pub title: String,
+    pub commit_range: String,
+    pub status: ReviewStatus,
+    pub comment_threads: usize,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+// ANCHOR_END: review_summary
+
+impl From<&ReviewState> for ReviewSummary {
+    fn from(review: &ReviewState) -> Self {
+        ReviewSummary {
+            review_id: review.review_id.clone(),
+            title: review.title.clone(),
+            commit_range: review.commit_range.clone(),
+            status: review.status.clone(),
+            comment_threads: review.comment_threads.len(),
+            created_at: review.created_at,
+            updated_at: review.updated_at,
+        }
+    }
+}
+
+/// Persistent store of reviews keyed by review id.
+///
+/// Each review lives in its own `<review_id>.json` file; `index.json` keeps a
+/// summary of every review so they can be listed without loading them all.
+#[derive(Debug, Clone)]
+pub struct ReviewStore {
+    directory: PathBuf,
+}
+
+impl ReviewStore {
+    /// Create a store rooted at the given directory (created on first save).
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        ReviewStore {
+            directory: directory.into(),
+        }
+    }
+
+    /// Open the review store for a repository, migrating any legacy review file.
+    ///
+    /// The store lives in the repository's `.git` directory, so it survives across
+    /// sessions but never appears in the working tree. A failed migration is logged
+    /// rather than returned, so it can't make the stored reviews unreachable.
+    ///
+    /// # Arguments
+    /// * `repo_path` - Optional path inside the repository (defaults to current directory)
+    ///
+    /// # Returns
+    /// * `Ok(ReviewStore)` - Store for the repository
+    /// * `Err(ReviewStoreError)` - Not a git repository
+    pub fn for_repo(repo_path: Option<&str>) -> Result<Self, ReviewStoreError> {
+        let repo = Repository::discover(repo_path.unwrap_or("."))?;
+        let store = Self::new(repo.path().join(REVIEW_STORE_DIR));
+        if let Some(workdir) = repo.workdir()
+            && let Err(e) = store.migrate_legacy_file(workdir)
+        {
+            warn!("Failed to migrate legacy review file: {}", e);
+        }
+        Ok(store)
+    }
+
+    /// Move a review written by older versions into the working tree into this store.
+    ///
+    /// A review already in the store wins over the legacy copy. The legacy file is
+    /// removed either way so it stops polluting diffs. A legacy file that can't be
+    /// parsed is moved into the store as `legacy-review.json.invalid`, so it is kept
+    /// for inspection but not tried again.
+    ///
+    /// # Returns
+    /// * `Ok(Some(review_id))` - A legacy review was found and migrated
+    /// * `Ok(None)` - There was no legacy file, or it couldn't be parsed and was moved aside
+    /// * `Err(ReviewStoreError)` - The legacy file could not be read, moved or migrated
+    pub fn migrate_legacy_file(&self, workdir: &Path) -> Result<Option<String>, ReviewStoreError> {
+        let legacy_path = workdir.join(LEGACY_REVIEW_FILE);
+        let content = match std::fs::read_to_string(&legacy_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let review: ReviewState = match serde_json::from_str(&content) {
+            Ok(review) => review,
+            Err(e) => {
+                let invalid_path = self.directory.join(INVALID_LEGACY_FILE);
+                std::fs::create_dir_all(&self.directory)?;
+                std::fs::rename(&legacy_path, &invalid_path)?;
+                warn!(
+                    "Moved unreadable legacy review file to {}: {}",
+                    invalid_path.display(),
+                    e
+                );
+                return Ok(None);
+            }
+        };
+        match self.load(&review.review_id) {
+            Ok(_) => {}
+            Err(ReviewStoreError::NotFound { .. }) => self.save(&review)?,
+            Err(e) => return Err(e),
+        }
+        std::fs::remove_file(&legacy_path)?;
+
+        Ok(Some(review.review_id))
+    }
+
+    /// Directory this store reads and writes.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Save a review, replacing any previous version with the same id.
+    ///
+    /// Saves hold an exclusive lock on the store while they update the index, so
+    /// concurrent saves (e.g., feedback recorded while the agent calls a tool) don't
+    /// drop each other's index entries.
+    ///
+    /// # Returns
+    /// * `Ok(())` - Review file and index entry written
+    /// * `Err(ReviewStoreError)` - Invalid review id, I/O or serialization error
+    pub fn save(&self, review: &ReviewState) -> Result<(), ReviewStoreError> {
+        let review_path = self.review_path(&review.review_id)?;
+        std::fs::create_dir_all(&self.directory)?;
+        // Released when the file is closed
+        let lock = std::fs::File::create(self.directory.join(LOCK_FILE))?;
+        lock.lock()?;
+
+        write_atomically(&review_path, &serde_json::to_string_pretty(review)?)?;
+
+        let mut index = self.list()?;
+        index.retain(|summary| summary.review_id != review.review_id);
+        index.push(ReviewSummary::from(review));
+        self.write_index(&mut index)
+    }
+
+    /// Load the review with the given id.
+    ///
+    /// # Returns
+    /// * `Ok(ReviewState)` - The stored review
+    /// * `Err(ReviewStoreError::NotFound)` - No review with that id
+    /// * `Err(ReviewStoreError)` - Invalid review id, I/O or parse error
+    pub fn load(&self, review_id: &str) -> Result<ReviewState, ReviewStoreError> {
+        let review_path = self.review_path(review_id)?;
+        let content = match std::fs::read_to_string(&review_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(ReviewStoreError::NotFound {
+                    review_id: review_id.to_string(),
+                });
+            }
+            Err(e) => return Err(e.into()),
+        };
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Load the most recently updated review, if any.
+    pub fn load_latest(&self) -> Result<Option<ReviewState>, ReviewStoreError> {
+        match self.list()?.first() {
+            Some(summary) => self.load(&summary.review_id).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// List all stored reviews, most recently updated first.
+    pub fn list(&self) -> Result<Vec<ReviewSummary>, ReviewStoreError> {
+        let content = match std::fs::read_to_string(self.directory.join(INDEX_FILE)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+        let mut index: Vec<ReviewSummary> = serde_json::from_str(&content)?;
+        index.sort_by_key(|summary| std::cmp::Reverse(summary.updated_at));
+        Ok(index)
+    }
+
+    fn write_index(&self, index: &mut [ReviewSummary]) -> Result<(), ReviewStoreError> {
+        index.sort_by_key(|summary| std::cmp::Reverse(summary.updated_at));
+        let json = serde_json::to_string_pretty(index)?;
+        write_atomically(&self.directory.join(INDEX_FILE), &json)?;
+        Ok(())
+    }
+
+    /// Path of a review's file, rejecting ids that could escape the store directory
+    /// or overwrite the index (also on case-insensitive file systems).
+    fn review_path(&self, review_id: &str) -> Result<PathBuf, ReviewStoreError> {
+        let valid = !review_id.is_empty()
+            && !review_id.eq_ignore_ascii_case(INDEX_ID)
+            && review_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(ReviewStoreError::InvalidId {
+                review_id: review_id.to_string(),
+            });
+        }
+        Ok(self.directory.join(format!("{}.json", review_id)))
+    }
+}
+
+/// Write a file via a temporary sibling and rename, so readers never see partial JSON.
+///
+/// Each write uses its own temporary file, so concurrent writers never rename each
+/// other's.
+fn write_atomically(path: &Path, contents: &str) -> std::io::Result<()> {
+    let tmp_path = path.with_extension(format!("json.{}.tmp", uuid::Uuid::new_v4()));
+    std::fs::write(&tmp_path, contents)?;
+    std::fs::rename(tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn review(review_id: &str, title: &str) -> ReviewState {
+        ReviewState {
+            review_id: review_id.to_string(),
+            title: title.to_string(),
+            ..ReviewState::for_test()
+        }
+    }
+
+    #[test]
+    fn test_reviews_are_kept_separately() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = ReviewStore::new(temp_dir.path());
+
+        store.save(&review("first", "First review")).unwrap();
+        store.save(&review("second", "Second review")).unwrap();
+
+        let mut first = store.load("first").unwrap();
+        assert_eq!(first.title, "First review");
+        assert_eq!(store.load("second").unwrap().title, "Second review");
+
+        // Updating one review moves it to the front of the index
+        first.transition(ReviewStatus::Approved, "user").unwrap();
+        store.save(&first).unwrap();
+
+        let index = store.list().unwrap();
+        let ids: Vec<_> = index.iter().map(|s| s.review_id.as_str()).collect();
+        assert_eq!(ids, vec!["first", "second"]);
+        assert!(matches!(index[0].status, ReviewStatus::Approved));
+        assert_eq!(store.load_latest().unwrap().unwrap().review_id, "first");
+    }
+
+    #[test]
+    fn test_concurrent_saves_keep_every_index_entry() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = ReviewStore::new(temp_dir.path());
+
+        std::thread::scope(|scope| {
+            for n in 0..16 {
+                let store = &store;
+                scope.spawn(move || {
+                    let review_id = format!("review-{}", n);
+                    for _ in 0..4 {
+                        store.save(&review(&review_id, "Concurrent")).unwrap();
+                    }
+                });
+            }
+        });
+
+        assert_eq!(store.list().unwrap().len(), 16);
+        let leftovers: Vec<_> = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .filter(|name| name.ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty(), "{:?}", leftovers);
+    }
+
+    #[test]
+    fn test_migrate_legacy_file() {
+        let workdir = tempfile::tempdir().unwrap();
+        let store_dir = tempfile::tempdir().unwrap();
+        let store = ReviewStore::new(store_dir.path());
+
+        assert_eq!(store.migrate_legacy_file(workdir.path()).unwrap(), None);
+
+        let legacy = review("legacy", "Legacy review");
+        let legacy_path = workdir.path().join(LEGACY_REVIEW_FILE);
+        std::fs::write(&legacy_path, serde_json::to_string(&legacy).unwrap()).unwrap();
+
+        let migrated = store.migrate_legacy_file(workdir.path()).unwrap();
+        assert_eq!(migrated.as_deref(), Some("legacy"));
+        assert!(!legacy_path.exists());
+        assert_eq!(store.load("legacy").unwrap().title, "Legacy review");
+        assert_eq!(store.list().unwrap().len(), 1);
+
+        // A corrupt legacy file is set aside instead of failing every migration
+        std::fs::write(&legacy_path, "{ not a review").unwrap();
+        assert_eq!(store.migrate_legacy_file(workdir.path()).unwrap(), None);
+        assert!(!legacy_path.exists());
+        assert_eq!(
+            std::fs::read_to_string(store_dir.path().join(INVALID_LEGACY_FILE)).unwrap(),
+            "{ not a review"
+        );
+        assert_eq!(store.migrate_legacy_file(workdir.path()).unwrap(), None);
+        assert_eq!(store.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_missing_and_invalid_reviews() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = ReviewStore::new(temp_dir.path());
+
+        assert!(store.list().unwrap().is_empty());
+        assert!(store.load_latest().unwrap().is_none());
+        assert!(matches!(
+            store.load("nope"),
+            Err(ReviewStoreError::NotFound { .. })
+        ));
+        assert!(matches!(
+            store.load("../index"),
+            Err(ReviewStoreError::InvalidId { .. })
+        ));
+
+        // A review named after the index must not replace it
+        store.save(&review("first", "First review")).unwrap();
+        for review_id in ["index", "INDEX"] {
+            assert!(matches!(
+                store.save(&review(review_id, "Clobbers the index")),
+                Err(ReviewStoreError::InvalidId { .. })
+            ));
+        }
+        assert_eq!(store.list().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,419 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use git2::Patch;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::synthetic_pr::comment_parser::ExtractedComment;
+use crate::synthetic_pr::{CommentParser, CommentSyntax, CommentThread, CommentType, ReviewState};
+
+/// Errors that can occur while stripping insight comments from the working tree.
+#[derive(Error, Debug)]
+pub enum StripError {
+    #[error("Failed to read or write a source file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to render the diff: {0}")]
+    Git(#[from] git2::Error),
+}
+
+// ANCHOR: strip_options
+/// Which insight comments to strip, and what to do with answered questions.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct StripOptions {
+    /// Comment types to strip (defaults to 💡 explanations and ❓ questions;
+    /// TODO and FIXME comments are usually meant to stay)
+    #[serde(default = "default_strip_types")]
+    pub comment_types: Vec<CommentType>,
+    /// Turn questions the user answered during the review into regular comments
+    /// holding the question and its answers, instead of removing them
+    #[serde(default)]
+    pub keep_answered_questions: bool,
+}
+// ANCHOR_END: strip_options
+
+fn default_strip_types() -> Vec<CommentType> {
+    vec![CommentType::Explanation, CommentType::Question]
+}
+
+impl Default for StripOptions {
+    fn default() -> Self {
+        StripOptions {
+            comment_types: default_strip_types(),
+            keep_answered_questions: false,
+        }
+    }
+}
+
+/// New contents of a file of the working tree.
+#[derive(Debug, Clone)]
+pub struct FileEdit {
+    /// Path relative to the root of the working tree
+    pub path: String,
+    pub original: String,
+    pub updated: String,
+}
+
+impl FileEdit {
+    /// The edit as a unified diff.
+    pub fn diff(&self) -> Result<String, git2::Error> {
+        let path = Path::new(&self.path);
+        let mut patch = Patch::from_buffers(
+            self.original.as_bytes(),
+            Some(path),
+            self.updated.as_bytes(),
+            Some(path),
+            None,
+        )?;
+        Ok(String::from_utf8_lossy(&patch.to_buf()?).into_owned())
+    }
+}
+
+/// Edits removing (or converting) a review's insight comments from the working tree.
+///
+/// Built without touching any file, so the edits can be shown with [`StripPlan::diff`]
+/// before [`StripPlan::apply`] writes them.
+#[derive(Debug, Clone, Default)]
+pub struct StripPlan {
+    pub edits: Vec<FileEdit>,
+    /// Threads whose comment is removed
+    pub removed: Vec<String>,
+    /// Threads whose comment becomes a regular comment
+    pub converted: Vec<String>,
+    /// Threads whose comment could not be found in the working tree (e.g., already removed)
+    pub not_found: Vec<String>,
+}
+
+impl StripPlan {
+    /// Plan the removal of a review's insight comments from the working tree.
+    ///
+    /// Covers the live insight threads (not outdated, not resolved, not started by
+    /// someone) of the selected types. Each comment is looked up in the current file
+    /// by its type and content, nearest to the thread's line, so edits made since
+    /// the review was last refreshed don't matter.
+    ///
+    /// # Arguments
+    /// * `review` - Review listing the insight comments
+    /// * `parser` - Parser for the review's comment markers, as configured at its base commit
+    /// * `workdir` - Root of the repository's working tree
+    /// * `options` - Which comments to strip, and whether to keep answered questions
+    ///
+    /// # Returns
+    /// * `Ok(StripPlan)` - Edits to make, and the threads they cover
+    /// * `Err(StripError)` - A file could not be read
+    pub fn for_review(
+        review: &ReviewState,
+        parser: &CommentParser,
+        workdir: &Path,
+        options: &StripOptions,
+    ) -> Result<Self, StripError> {
+        let mut plan = StripPlan::default();
+
+        let mut threads_by_file: BTreeMap<&str, Vec<&CommentThread>> = BTreeMap::new();
+        for thread in review.comment_threads.iter().filter(|t| {
+            t.author.is_none()
+                && !t.outdated
+                && !t.is_resolved()
+                && options.comment_types.contains(&t.comment_type)
+        }) {
+            threads_by_file
+                .entry(thread.file_path.as_str())
+                .or_default()
+                .push(thread);
+        }
+
+        for (path, threads) in threads_by_file {
+            let Ok(original) = std::fs::read_to_string(workdir.join(path)) else {
+                plan.not_found
+                    .extend(threads.iter().map(|t| t.thread_id.clone()));
+                continue;
+            };
+            let syntax = parser.syntax_for(path);
+            let lines: Vec<&str> = original.lines().collect();
+            let mut found = parser.extract_comments(&lines, &syntax);
+
+            let mut replacements = Vec::new();
+            for thread in threads {
+                let nearest = found
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| {
+                        c.comment.comment_type == thread.comment_type
+                            && c.comment.content == thread.content
+                    })
+                    .min_by_key(|(_, c)| (c.first as i64 + 1 - thread.line_number as i64).abs())
+                    .map(|(index, _)| index);
+                let Some(index) = nearest else {
+                    plan.not_found.push(thread.thread_id.clone());
+                    continue;
+                };
+
+                let comment = found.remove(index);
+                let convert = options.keep_answered_questions
+                    && thread.comment_type == CommentType::Question
+                    && thread.answered_by_user();
+                if convert {
+                    plan.converted.push(thread.thread_id.clone());
+                } else {
+                    plan.removed.push(thread.thread_id.clone());
+                }
+                replacements.push((comment, convert.then_some(thread)));
+            }
+            if replacements.is_empty() {
+                continue;
+            }
+
+            // Edit from the bottom up so earlier line indices stay valid
+            replacements.sort_by_key(|(comment, _)| std::cmp::Reverse(comment.first));
+            let mut updated: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+            for (comment, answered) in replacements {
+                let new_lines = replacement_lines(&lines, &comment, answered, &syntax);
+                updated.splice(comment.first..=comment.last, new_lines);
+            }
+
+            let newline = if original.contains("\r\n") {
+                "\r\n"
+            } else {
+                "\n"
+            };
+            let mut updated = updated.join(newline);
+            if original.ends_with('\n') && !updated.is_empty() {
+                updated.push_str(newline);
+            }
+            plan.edits.push(FileEdit {
+                path: path.to_string(),
+                original,
+                updated,
+            });
+        }
+
+        Ok(plan)
+    }
+
+    /// Unified diff of the planned edits, as `git diff` would show them once applied.
+    pub fn diff(&self) -> Result<String, StripError> {
+        let mut diff = String::new();
+        for edit in &self.edits {
+            diff.push_str(&edit.diff()?);
+        }
+        Ok(diff)
+    }
+
+    /// Write the planned edits to the working tree.
+    ///
+    /// # Arguments
+    /// * `workdir` - Root of the repository's working tree the plan was made for
+    pub fn apply(&self, workdir: &Path) -> Result<(), StripError> {
+        for edit in &self.edits {
+            std::fs::write(workdir.join(&edit.path), &edit.updated)?;
+        }
+        Ok(())
+    }
+}
+
+/// Lines replacing those of an insight comment: the code sharing its lines, preceded by
+/// the question and its answers as a regular comment when an answered question is kept.
+fn replacement_lines(
+    lines: &[&str],
+    comment: &ExtractedComment,
+    answered: Option<&CommentThread>,
+    syntax: &CommentSyntax,
+) -> Vec<String> {
+    let first_line = lines[comment.first];
+    let indent = &first_line[..first_line.len() - first_line.trim_start().len()];
+    let before = first_line[..comment.start_column].trim_end();
+    let after = comment
+        .end_column
+        .map(|column| lines[comment.last][column..].trim_start())
+        .unwrap_or_default();
+
+    let mut new_lines = Vec::new();
+    if let Some(thread) = answered {
+        let mut text: Vec<String> = thread.content.lines().map(str::to_string).collect();
+        for response in &thread.responses {
+            let mut answer = response.content.lines();
+            text.push(format!(
+                "{}: {}",
+                response.author,
+                answer.next().unwrap_or_default()
+            ));
+            text.extend(answer.map(str::to_string));
+        }
+
+        for line in text {
+            let line = line.trim_end();
+            new_lines.push(if comment.enclosed {
+                // Already inside a block comment; keep its `*` decoration if it has one
+                let decoration = if first_line.trim_start().starts_with('*') {
+                    "* "
+                } else {
+                    ""
+                };
+                format!("{}{}{}", indent, decoration, line)
+            } else if let Some(token) = syntax.line.first() {
+                format!("{}{} {}", indent, token, line)
+            } else if let Some(block) = syntax.block.first() {
+                format!("{}{} {} {}", indent, block.start, line, block.end)
+            } else {
+                continue;
+            });
+        }
+    }
+
+    let code = match (before.is_empty(), after.is_empty()) {
+        (true, true) => None,
+        (true, false) => Some(format!("{}{}", indent, after)),
+        (false, true) => Some(before.to_string()),
+        (false, false) => Some(format!("{} {}", before, after)),
+    };
+    new_lines.extend(code);
+    new_lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synthetic_pr::{AGENT_AUTHOR, ReviewStatus, UserResponse};
+    use chrono::Utc;
+
+    fn thread(
+        thread_id: &str,
+        line_number: u32,
+        comment_type: CommentType,
+        content: &str,
+    ) -> CommentThread {
+        CommentThread {
+            thread_id: thread_id.to_string(),
+            ..CommentThread::for_test("src/lib.rs", line_number, comment_type, content)
+        }
+    }
+
+    fn review(comment_threads: Vec<CommentThread>) -> ReviewState {
+        ReviewState {
+            status: ReviewStatus::Approved,
+            comment_threads,
+            ..ReviewState::for_test()
+        }
+    }
+
+    #[test]
+    fn test_strip_plan() {
+        let workdir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(workdir.path().join("src")).unwrap();
+        let source = "\
+pub fn load(key: &str) -> Value {
+    // 💡 Cached per tenant so evictions
+    // stay local to one customer.
+    let cache = tenant_cache();
+    let ttl = 30; // ❓ Is 30 seconds enough?
+    /* ❓ Should misses be logged? */ cache.get(key)
+    // TODO: Warm the cache on startup
+}
+";
+        std::fs::write(workdir.path().join("src/lib.rs"), source).unwrap();
+
+        let mut ttl = thread("ttl", 5, CommentType::Question, "Is 30 seconds enough?");
+        ttl.responses.push(UserResponse {
+            author: "user".to_string(),
+            content: "Yes, entries are refreshed on write".to_string(),
+            timestamp: Utc::now(),
+        });
+        // Only the agent replied, so the question is still open and gets removed
+        let mut misses = thread(
+            "misses",
+            6,
+            CommentType::Question,
+            "Should misses be logged?",
+        );
+        misses.responses.push(UserResponse {
+            author: AGENT_AUTHOR.to_string(),
+            content: "I think so".to_string(),
+            timestamp: Utc::now(),
+        });
+        let mut gone = thread("gone", 40, CommentType::Explanation, "Deleted meanwhile");
+        gone.file_path = "src/lib.rs".to_string();
+        let review = review(vec![
+            thread(
+                "tenant",
+                2,
+                CommentType::Explanation,
+                "Cached per tenant so evictions\nstay local to one customer.",
+            ),
+            ttl,
+            misses,
+            thread("todo", 7, CommentType::Todo, "Warm the cache on startup"),
+            gone,
+        ]);
+
+        let options = StripOptions {
+            keep_answered_questions: true,
+            ..Default::default()
+        };
+        let plan = StripPlan::for_review(&review, &CommentParser::new(), workdir.path(), &options)
+            .unwrap();
+        assert_eq!(plan.removed, ["tenant", "misses"]);
+        assert_eq!(plan.converted, ["ttl"]);
+        assert_eq!(plan.not_found, ["gone"]);
+
+        // Nothing is written until the plan is applied
+        assert_eq!(
+            std::fs::read_to_string(workdir.path().join("src/lib.rs")).unwrap(),
+            source
+        );
+        expect_test::expect![[r#"
+            diff --git a/src/lib.rs b/src/lib.rs
+            index 5bebb9f..1ea9add 100644
+            --- a/src/lib.rs
+            +++ b/src/lib.rs
+            @@ -1,8 +1,8 @@
+             pub fn load(key: &str) -> Value {
+            -    // 💡 Cached per tenant so evictions
+            -    // stay local to one customer.
+                 let cache = tenant_cache();
+            -    let ttl = 30; // ❓ Is 30 seconds enough?
+            -    /* ❓ Should misses be logged? */ cache.get(key)
+            +    // Is 30 seconds enough?
+            +    // user: Yes, entries are refreshed on write
+            +    let ttl = 30;
+            +    cache.get(key)
+                 // TODO: Warm the cache on startup
+             }
+        "#]]
+        .assert_eq(&plan.diff().unwrap());
+
+        plan.apply(workdir.path()).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(workdir.path().join("src/lib.rs")).unwrap(),
+            plan.edits[0].updated
+        );
+    }
+
+    #[test]
+    fn test_strip_comment_inside_block() {
+        let workdir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(workdir.path().join("src")).unwrap();
+        let source = "/**\n * Frees the buffer.\n *\n * ❓ Should this reset the length? */\nvoid release(void);\n";
+        std::fs::write(workdir.path().join("src/buffer.c"), source).unwrap();
+
+        let mut question = thread(
+            "q",
+            4,
+            CommentType::Question,
+            "Should this reset the length?",
+        );
+        question.file_path = "src/buffer.c".to_string();
+        let plan = StripPlan::for_review(
+            &review(vec![question]),
+            &CommentParser::new(),
+            workdir.path(),
+            &StripOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            plan.edits[0].updated,
+            "/**\n * Frees the buffer.\n *\n */\nvoid release(void);\n"
+        );
+    }
+}
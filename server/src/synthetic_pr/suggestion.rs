@@ -0,0 +1,368 @@
+use std::path::Path;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::synthetic_pr::{FileEdit, ReviewEventKind, ReviewState};
+
+/// Errors that can occur while applying a suggested change.
+#[derive(Error, Debug)]
+pub enum SuggestionError {
+    #[error("Suggestion not found: {suggestion_id}")]
+    NotFound { suggestion_id: String },
+
+    #[error("No suggestion left to apply in thread {thread_id}")]
+    NoneInThread { thread_id: String },
+
+    #[error("Suggestion {suggestion_id} was already applied")]
+    AlreadyApplied { suggestion_id: String },
+
+    #[error(
+        "Lines {start_line}-{end_line} of {file_path} changed since the suggestion was made \
+        and can't be found elsewhere in the file; apply it by hand or ask for a new one"
+    )]
+    LinesChanged {
+        file_path: String,
+        start_line: u32,
+        end_line: u32,
+    },
+
+    #[error("Failed to read or write {file_path}: {source}")]
+    Io {
+        file_path: String,
+        source: std::io::Error,
+    },
+}
+
+// ANCHOR: suggestion
+/// A change proposed in a review comment with a ```` ```suggestion ```` block, replacing
+/// the lines the comment's thread is on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Suggestion {
+    pub suggestion_id: String,
+    /// Thread whose comment holds the suggestion
+    pub thread_id: String,
+    /// Who suggested the change (e.g., [`AGENT_AUTHOR`](crate::synthetic_pr::AGENT_AUTHOR) or
+    /// [`USER_AUTHOR`](crate::synthetic_pr::USER_AUTHOR))
+    pub author: String,
+    /// File to edit, relative to the repository root
+    pub file_path: String,
+    /// First line (1-based) replaced, in the reviewed code (where it was applied, once applied)
+    pub start_line: u32,
+    /// Last line replaced
+    pub end_line: u32,
+    /// The replaced lines in the reviewed code; applying looks for them in the working tree
+    pub original_lines: Vec<String>,
+    /// Lines replacing them; empty deletes the lines
+    pub replacement: Vec<String>,
+    #[serde(default)]
+    pub applied: bool,
+}
+// ANCHOR_END: suggestion
+
+/// Contents of the ```` ```suggestion ```` blocks in a comment body, one list of lines per block.
+///
+/// As on GitHub, the fence may be longer than three backticks (so a suggestion can hold a
+/// code block) and a block must be closed by a fence at least as long. Unclosed blocks are ignored.
+pub fn parse_suggestion_blocks(body: &str) -> Vec<Vec<String>> {
+    let mut blocks = Vec::new();
+    let mut open: Option<(usize, Vec<String>)> = None;
+
+    for line in body.lines() {
+        let trimmed = line.trim();
+        let fence = trimmed.len() - trimmed.trim_start_matches('`').len();
+        match &mut open {
+            None => {
+                if fence >= 3 && trimmed[fence..].trim() == "suggestion" {
+                    open = Some((fence, Vec::new()));
+                }
+            }
+            Some((opening, lines)) => {
+                if fence >= *opening && trimmed[fence..].is_empty() {
+                    blocks.push(std::mem::take(lines));
+                    open = None;
+                } else {
+                    lines.push(line.to_string());
+                }
+            }
+        }
+    }
+    blocks
+}
+
+impl ReviewState {
+    /// Record the suggestions in a comment just added to a thread.
+    ///
+    /// Each suggestion replaces the lines the thread is on, as they read in the reviewed
+    /// code (the thread's line numbers refer to it, not to the working tree, which may have
+    /// changed since); suggestions whose lines can't be read (e.g., the file is gone) are
+    /// not recorded.
+    ///
+    /// # Arguments
+    /// * `thread_id` - Thread the comment was added to
+    /// * `body` - Comment body
+    /// * `author` - Who wrote the comment
+    /// * `read_file` - Reads a file of the reviewed code, e.g. from the latest round's snapshot
+    ///
+    /// # Returns
+    /// * `Vec<String>` - IDs of the recorded suggestions
+    pub fn record_suggestions(
+        &mut self,
+        thread_id: &str,
+        body: &str,
+        author: &str,
+        read_file: impl Fn(&str) -> Option<String>,
+    ) -> Vec<String> {
+        let Some(thread) = self
+            .comment_threads
+            .iter()
+            .find(|t| t.thread_id == thread_id)
+        else {
+            return vec![];
+        };
+        let (file_path, start_line, end_line) = (
+            thread.file_path.clone(),
+            thread.line_number,
+            thread.end_line_number.unwrap_or(thread.line_number),
+        );
+
+        let blocks = parse_suggestion_blocks(body);
+        if blocks.is_empty() {
+            return vec![];
+        }
+        let Some(original_lines) =
+            read_file(&file_path).and_then(|content| read_lines(&content, start_line, end_line))
+        else {
+            return vec![];
+        };
+
+        let mut recorded = Vec::new();
+        for replacement in blocks {
+            let suggestion_id = uuid::Uuid::new_v4().to_string();
+            self.suggestions.push(Suggestion {
+                suggestion_id: suggestion_id.clone(),
+                thread_id: thread_id.to_string(),
+                author: author.to_string(),
+                file_path: file_path.clone(),
+                start_line,
+                end_line,
+                original_lines: original_lines.clone(),
+                replacement,
+                applied: false,
+            });
+            recorded.push(suggestion_id);
+        }
+        recorded
+    }
+
+    /// Apply a suggestion to the working tree, replacing the lines it was made on.
+    ///
+    /// The lines are replaced where the suggestion was made if they still read the same
+    /// there; if the file changed since, they are looked for elsewhere, and replaced if
+    /// they appear exactly once.
+    ///
+    /// # Arguments
+    /// * `suggestion_id` - Suggestion to apply
+    /// * `actor` - Who applied it (e.g., [`AGENT_AUTHOR`](crate::synthetic_pr::AGENT_AUTHOR))
+    /// * `workdir` - Root of the repository's working tree
+    ///
+    /// # Returns
+    /// * `Ok(FileEdit)` - The edit written to the working tree
+    /// * `Err(SuggestionError)` - Unknown or already applied suggestion, lines that
+    ///   changed (or appear more than once) since, or file system error
+    pub fn apply_suggestion(
+        &mut self,
+        suggestion_id: &str,
+        actor: &str,
+        workdir: &Path,
+    ) -> Result<FileEdit, SuggestionError> {
+        let suggestion = self
+            .suggestions
+            .iter_mut()
+            .find(|s| s.suggestion_id == suggestion_id)
+            .ok_or_else(|| SuggestionError::NotFound {
+                suggestion_id: suggestion_id.to_string(),
+            })?;
+        if suggestion.applied {
+            return Err(SuggestionError::AlreadyApplied {
+                suggestion_id: suggestion_id.to_string(),
+            });
+        }
+
+        let path = workdir.join(&suggestion.file_path);
+        let io_error = |source| SuggestionError::Io {
+            file_path: suggestion.file_path.clone(),
+            source,
+        };
+        let original = std::fs::read_to_string(&path).map_err(io_error)?;
+        let (start_line, updated) =
+            replace_lines(&original, suggestion).ok_or_else(|| SuggestionError::LinesChanged {
+                file_path: suggestion.file_path.clone(),
+                start_line: suggestion.start_line,
+                end_line: suggestion.end_line,
+            })?;
+        std::fs::write(&path, &updated).map_err(io_error)?;
+
+        suggestion.end_line = start_line + (suggestion.end_line - suggestion.start_line);
+        suggestion.start_line = start_line;
+        suggestion.applied = true;
+        let edit = FileEdit {
+            path: suggestion.file_path.clone(),
+            original,
+            updated,
+        };
+        let thread_id = suggestion.thread_id.clone();
+        self.record_event(ReviewEventKind::SuggestionApplied, actor, Some(thread_id));
+        Ok(edit)
+    }
+
+    /// The most recent suggestion of a thread that hasn't been applied yet.
+    pub fn pending_suggestion_in(&self, thread_id: &str) -> Result<&Suggestion, SuggestionError> {
+        self.suggestions
+            .iter()
+            .rev()
+            .find(|s| s.thread_id == thread_id && !s.applied)
+            .ok_or_else(|| SuggestionError::NoneInThread {
+                thread_id: thread_id.to_string(),
+            })
+    }
+}
+
+/// Lines `start..=end` (1-based) of a file's content, without line endings.
+fn read_lines(content: &str, start: u32, end: u32) -> Option<Vec<String>> {
+    let lines: Vec<&str> = content.lines().collect();
+    let range = (start as usize).checked_sub(1)?..end as usize;
+    lines
+        .get(range)
+        .map(|lines| lines.iter().map(|line| line.to_string()).collect())
+}
+
+/// File contents with the suggestion's lines replaced, keeping the file's line endings,
+/// and the line (1-based) the replacement starts at.
+///
+/// The original lines are replaced at the suggestion's line if they are still there, or
+/// else where they now are if they appear exactly once; None if they can't be found.
+fn replace_lines(content: &str, suggestion: &Suggestion) -> Option<(u32, String)> {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let count = suggestion.original_lines.len();
+    if count == 0 {
+        return None;
+    }
+    let matches_at = |start: usize| {
+        lines.get(start..start + count).is_some_and(|current| {
+            current
+                .iter()
+                .map(|line| line.trim_end_matches(['\n', '\r']))
+                .eq(suggestion.original_lines.iter().map(String::as_str))
+        })
+    };
+
+    let recorded_start = (suggestion.start_line as usize).checked_sub(1)?;
+    let start = if matches_at(recorded_start) {
+        recorded_start
+    } else {
+        let mut found = (0..lines.len()).filter(|&start| matches_at(start));
+        match (found.next(), found.next()) {
+            (Some(start), None) => start,
+            _ => return None,
+        }
+    };
+    let range = start..start + count;
+    let current = &lines[range.clone()];
+
+    // The last replaced line may lack a line ending at the end of the file
+    let ending = if current[0].ends_with("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let last_ending = if current[current.len() - 1].ends_with('\n') {
+        ending
+    } else {
+        ""
+    };
+
+    let mut updated: String = lines[..range.start].concat();
+    for (i, line) in suggestion.replacement.iter().enumerate() {
+        updated.push_str(line);
+        updated.push_str(if i + 1 < suggestion.replacement.len() {
+            ending
+        } else {
+            last_ending
+        });
+    }
+    updated.push_str(&lines[range.end..].concat());
+    Some((start as u32 + 1, updated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_suggestion_blocks() {
+        let body = "Use the constant:\n\
+            ```suggestion\n    let timeout = DEFAULT_TIMEOUT;\n```\n\
+            Or keep the code block:\n\
+            ````suggestion\n```rust\nlet x = 1;\n```\n````\n\
+            ```rust\nnot a suggestion\n```\n\
+            ```suggestion\n```\n\
+            ```suggestion\nunclosed";
+
+        assert_eq!(
+            parse_suggestion_blocks(body),
+            vec![
+                vec!["    let timeout = DEFAULT_TIMEOUT;".to_string()],
+                vec![
+                    "```rust".to_string(),
+                    "let x = 1;".to_string(),
+                    "```".to_string()
+                ],
+                vec![],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_replace_lines_checks_original() {
+        let suggestion = Suggestion {
+            suggestion_id: "s1".to_string(),
+            thread_id: "t1".to_string(),
+            author: "user".to_string(),
+            file_path: "src/lib.rs".to_string(),
+            start_line: 2,
+            end_line: 3,
+            original_lines: vec!["b".to_string(), "c".to_string()],
+            replacement: vec!["B".to_string()],
+            applied: false,
+        };
+
+        assert_eq!(
+            replace_lines("a\r\nb\r\nc\r\nd\r\n", &suggestion),
+            Some((2, "a\r\nB\r\nd\r\n".to_string()))
+        );
+        assert_eq!(
+            replace_lines("a\nb\nc", &suggestion),
+            Some((2, "a\nB".to_string()))
+        );
+        assert_eq!(replace_lines("a\nb\nC\nd\n", &suggestion), None);
+        assert_eq!(replace_lines("a\nb\n", &suggestion), None);
+
+        // Lines that moved are found where they are now, unless that is ambiguous
+        assert_eq!(
+            replace_lines("x\ny\na\nb\nc\n", &suggestion),
+            Some((4, "x\ny\na\nB\n".to_string()))
+        );
+        assert_eq!(replace_lines("b\nc\nx\nb\nc\n", &suggestion), None);
+
+        let deletion = Suggestion {
+            replacement: vec![],
+            ..suggestion
+        };
+        assert_eq!(
+            replace_lines("a\nb\nc\nd\n", &deletion),
+            Some((2, "a\nd\n".to_string()))
+        );
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::synthetic_pr::ranking::definitions_in;
+use crate::synthetic_pr::{ChangeStatus, DiffLineType, FileChange, FileKind};
+
+// ANCHOR: test_link
+/// A changed test file paired with a changed source file it exercises.
+///
+/// Recorded on both files: on a source file `file` is the test, on a test file it is
+/// the source.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TestLink {
+    /// The other file of the pair, relative to the repository root
+    pub file: String,
+    /// Changed symbols of the source file the test refers to; empty when only the
+    /// IDE's findReferences linked the files
+    pub symbols: Vec<String>,
+}
+// ANCHOR_END: test_link
+
+/// Symbols a change to a source file touches: those defined on its added lines, and
+/// the functions or types its hunks change.
+pub fn changed_symbols(file: &FileChange) -> Vec<String> {
+    if FileKind::of(&file.path) != FileKind::Source {
+        return vec![];
+    }
+
+    let added = file
+        .hunks
+        .iter()
+        .flat_map(|hunk| &hunk.lines)
+        .filter(|line| matches!(line.line_type, DiffLineType::Added))
+        .map(|line| line.content.as_str());
+    let mut symbols = definitions_in(added);
+
+    // A hunk's changes are in the last definition above its first changed line, or
+    // the one git names in the hunk header ("@@ -3,4 +3,5 @@ pub fn load() {")
+    for hunk in &file.hunks {
+        let header = hunk.header.splitn(3, "@@").nth(2).unwrap_or_default();
+        let before_change = hunk
+            .lines
+            .iter()
+            .take_while(|line| matches!(line.line_type, DiffLineType::Context))
+            .map(|line| line.content.as_str());
+        if let Some(enclosing) = definitions_in(std::iter::once(header).chain(before_change)).pop()
+            && !symbols.contains(&enclosing)
+        {
+            symbols.push(enclosing);
+        }
+    }
+    symbols
+}
+
+/// Pair each changed test file with the changed source files it exercises, replacing
+/// the files' previous test links.
+///
+/// A test exercises a source file when it imports one of the source file's changed
+/// symbols from the source's module, or names it qualified (see [`SymbolUse`]), or when
+/// the IDE found references from the test to the source file.
+///
+/// # Arguments
+/// * `files` - Changed files of a review
+/// * `read_file` - Content of a file at the head of the reviewed range, if it exists
+/// * `referenced_by` - For each changed file, the files the IDE found references in
+pub fn link_tests(
+    files: &mut [FileChange],
+    read_file: impl Fn(&str) -> Option<String>,
+    referenced_by: &BTreeMap<String, Vec<String>>,
+) {
+    for file in files.iter_mut() {
+        file.test_links.clear();
+    }
+
+    let sources: Vec<(usize, Vec<SymbolUse>)> = files
+        .iter()
+        .enumerate()
+        .filter(|(_, file)| FileKind::of(&file.path) == FileKind::Source)
+        .map(|(i, file)| (i, SymbolUse::for_source(file)))
+        .collect();
+    let tests: Vec<(usize, String)> = files
+        .iter()
+        .enumerate()
+        .filter(|(_, file)| {
+            FileKind::of(&file.path) == FileKind::Test && file.status != ChangeStatus::Deleted
+        })
+        .filter_map(|(i, file)| Some((i, read_file(&file.path)?)))
+        .collect();
+
+    for (test, content) in &tests {
+        for (source, symbols) in &sources {
+            let used: Vec<String> = symbols
+                .iter()
+                .filter(|symbol| symbol.is_used_in(content))
+                .map(|symbol| symbol.symbol.clone())
+                .collect();
+            let referenced = referenced_by
+                .get(&files[*source].path)
+                .is_some_and(|paths| paths.contains(&files[*test].path));
+            if used.is_empty() && !referenced {
+                continue;
+            }
+
+            let (test_path, source_path) = (files[*test].path.clone(), files[*source].path.clone());
+            files[*source].test_links.push(TestLink {
+                file: test_path,
+                symbols: used.clone(),
+            });
+            files[*test].test_links.push(TestLink {
+                file: source_path,
+                symbols: used,
+            });
+        }
+    }
+}
+
+/// How a test can use a changed symbol of a source file.
+///
+/// A bare mention doesn't count, since names like `new` or `load` are in every test.
+/// The symbol has to be imported from the source's module (`use app::cache::load;`,
+/// `from app.cache import load`, `import { load } from './cache'`), used after a glob
+/// import of the module (`use app::cache::*;`), or qualified with the module or another
+/// changed symbol of the file (`cache::load`, `Cache::new`).
+struct SymbolUse {
+    symbol: String,
+    word: Regex,
+    imported: Regex,
+    qualified: Regex,
+    glob_imported: Regex,
+}
+
+impl SymbolUse {
+    /// Patterns for the changed symbols of a source file, compiled once per review.
+    fn for_source(file: &FileChange) -> Vec<SymbolUse> {
+        let symbols = changed_symbols(file);
+        let Some(module) = module_name(&file.path) else {
+            return vec![];
+        };
+        let module = regex::escape(module);
+        let glob_imported = format!(r"(?m)^\s*(?:pub\s+)?use\b[^;]*\b{module}::\*");
+
+        symbols
+            .iter()
+            .filter_map(|symbol| {
+                let name = regex::escape(symbol);
+                let qualifiers: Vec<String> = std::iter::once(module.clone())
+                    .chain(
+                        symbols
+                            .iter()
+                            .filter(|other| *other != symbol)
+                            .map(|other| regex::escape(other)),
+                    )
+                    .collect();
+                let imported = [
+                    format!(r"^\s*(?:pub\s+)?use\b[^;]*\b{module}\b[^;]*\b{name}\b"),
+                    format!(r"^\s*from\s+\S*\b{module}\b\S*\s+import\b.*\b{name}\b"),
+                    format!(r#"^\s*import\b[^;]*\b{name}\b[^;]*\bfrom\s+['"][^'"]*\b{module}\b"#),
+                ];
+                Some(SymbolUse {
+                    symbol: symbol.clone(),
+                    word: Regex::new(&format!(r"\b{name}\b")).ok()?,
+                    imported: Regex::new(&format!("(?m){}", imported.join("|"))).ok()?,
+                    qualified: Regex::new(&format!(
+                        r"\b(?:{})(?:::|\.){name}\b",
+                        qualifiers.join("|")
+                    ))
+                    .ok()?,
+                    glob_imported: Regex::new(&glob_imported).ok()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Whether a test's content uses the symbol.
+    fn is_used_in(&self, content: &str) -> bool {
+        self.imported.is_match(content)
+            || self.qualified.is_match(content)
+            || (self.glob_imported.is_match(content) && self.word.is_match(content))
+    }
+}
+
+/// Name a source file is imported by: its stem, or its directory's name for module
+/// files such as `mod.rs`, `index.ts` or `__init__.py`.
+fn module_name(path: &str) -> Option<&str> {
+    let path = Path::new(path);
+    match path.file_stem()?.to_str()? {
+        "mod" | "index" | "__init__" => path.parent()?.file_name()?.to_str(),
+        stem => Some(stem),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, header: &str, added_lines: &[&str]) -> FileChange {
+        let mut file = FileChange::for_test(path, added_lines.len() as u32, added_lines);
+        file.hunks[0].header = header.to_string();
+        file
+    }
+
+    #[test]
+    fn test_changed_symbols_include_enclosing_function() {
+        let cache = file(
+            "src/cache.rs",
+            "@@ -3,4 +3,5 @@ pub fn load(key: &str) -> Option<Entry> {",
+            &["    let ttl = Ttl::default();", "fn evict_expired() {"],
+        );
+        assert_eq!(changed_symbols(&cache), vec!["evict_expired", "load"]);
+    }
+
+    #[test]
+    fn test_link_tests_by_symbols_and_references() {
+        let mut files = vec![
+            file(
+                "src/cache.rs",
+                "@@ -1,1 +1,2 @@ pub fn load() {",
+                &["    fetch()"],
+            ),
+            file("src/db.rs", "@@ -1,0 +1,1 @@", &["pub fn connect() {}"]),
+            file("src/unused.rs", "@@ -1,0 +1,1 @@", &["pub fn helper() {}"]),
+            file("tests/cache_test.rs", "@@ -1,0 +1,1 @@", &["fn loads() {}"]),
+            file("tests/db_test.rs", "@@ -1,0 +1,1 @@", &["fn pool() {}"]),
+        ];
+        let contents = BTreeMap::from([
+            (
+                "tests/cache_test.rs",
+                "use app::cache::load;\nfn loads() { load(); reload(); }\n",
+            ),
+            ("tests/db_test.rs", "fn pool() { app::pool(); }\n"),
+        ]);
+        let referenced_by = BTreeMap::from([(
+            "src/db.rs".to_string(),
+            vec!["tests/db_test.rs".to_string()],
+        )]);
+
+        link_tests(
+            &mut files,
+            |path| contents.get(path).map(|content| content.to_string()),
+            &referenced_by,
+        );
+
+        let link = |file: &str, symbols: &[&str]| TestLink {
+            file: file.to_string(),
+            symbols: symbols.iter().map(|s| s.to_string()).collect(),
+        };
+        assert_eq!(
+            files[0].test_links,
+            vec![link("tests/cache_test.rs", &["load"])]
+        );
+        assert_eq!(files[1].test_links, vec![link("tests/db_test.rs", &[])]);
+        assert!(files[2].test_links.is_empty());
+        assert_eq!(files[3].test_links, vec![link("src/cache.rs", &["load"])]);
+        assert_eq!(files[4].test_links, vec![link("src/db.rs", &[])]);
+    }
+
+    #[test]
+    fn test_symbols_need_an_import_or_qualifier() {
+        let cache = file(
+            "src/cache/mod.rs",
+            "@@ -1,0 +1,2 @@",
+            &["pub struct Cache {}", "pub fn new() -> Cache {"],
+        );
+        let uses = SymbolUse::for_source(&cache);
+        let used = |content: &str| -> Vec<&str> {
+            uses.iter()
+                .filter(|symbol| symbol.is_used_in(content))
+                .map(|symbol| symbol.symbol.as_str())
+                .collect()
+        };
+
+        // Common names used on their own, or imported from elsewhere, aren't links
+        assert!(used("fn builds() { let db = Db::new(); load(); }").is_empty());
+        assert!(used("use app::db::new;\nfn builds() { new(); }").is_empty());
+
+        assert_eq!(used("use app::cache::{Cache, new};"), ["Cache", "new"]);
+        assert_eq!(used("fn builds() { let c = Cache::new(); }"), ["new"]);
+        assert_eq!(used("fn builds() { cache::new(); }"), ["new"]);
+        assert_eq!(used("use app::cache::*;\nfn builds() { new(); }"), ["new"]);
+        assert_eq!(used("from app.cache import Cache"), ["Cache"]);
+        assert_eq!(used("import { new as make } from '../src/cache';"), ["new"]);
+    }
+}
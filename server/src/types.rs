@@ -179,6 +179,8 @@ pub struct SyntheticPRPayload {
     pub description: serde_json::Value,
     pub commit_range: String,
     pub files_changed: Vec<crate::synthetic_pr::FileChange>,
+    pub commits: Vec<crate::synthetic_pr::ReviewCommit>,
+    pub rounds: Vec<crate::synthetic_pr::ReviewRound>,
     pub comment_threads: Vec<crate::synthetic_pr::CommentThread>,
     pub status: String,
 }
@@ -188,6 +190,8 @@ pub struct SyntheticPRPayload {
 pub struct UserFeedbackPayload {
     pub review_id: String,
     pub feedback_type: String, // "comment" or "complete_review"
+    #[serde(default)]
+    pub thread_id: Option<String>, // thread the user replied in, if any
     pub file_path: Option<String>,
     pub line_number: Option<u32>,
     pub comment_text: Option<String>,
@@ -0,0 +1,352 @@
+//! Resolved walkthrough documents.
+//!
+//! [`WalkthroughParser`](crate::walkthrough_parser::WalkthroughParser) resolves the markdown
+//! and XML elements of a walkthrough into a [`WalkthroughDocument`], which is sent to the
+//! extension over IPC. Rendering is up to the receiver; [`WalkthroughDocument::to_html`] is
+//! the HTML backend.
+//!
+//! The agent writes the walkthrough, so HTML in its markdown is sanitized as the document is
+//! built, and the HTML backend escapes every value it interpolates.
+
+use pulldown_cmark::{Event, HeadingLevel, Tag, TagEnd, html};
+use serde::{Deserialize, Serialize};
+
+use crate::html_sanitizer::{escape_html, render_markdown, sanitize_markdown};
+use crate::synthetic_pr::FileChange;
+
+// ANCHOR: walkthrough_document
+/// A walkthrough with its XML elements resolved, split into sections at its headings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WalkthroughDocument {
+    pub sections: Vec<WalkthroughSection>,
+}
+
+/// The content under one heading of a walkthrough.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WalkthroughSection {
+    /// Heading starting the section; none for content before the first heading
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heading: Option<Heading>,
+    pub nodes: Vec<WalkthroughNode>,
+}
+
+/// A markdown heading, as plain text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Heading {
+    /// 1 for `#`, up to 6 for `######`
+    pub level: u8,
+    pub text: String,
+}
+
+/// One block of a walkthrough section.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WalkthroughNode {
+    /// Markdown between XML elements, with its HTML sanitized
+    Markdown { markdown: String },
+    /// `<comment location="..." icon="...">`
+    Comment(CommentNode),
+    /// `<gitdiff range="..." />`
+    #[serde(rename = "gitdiff")]
+    GitDiff(GitDiffNode),
+    /// `<action button="...">`
+    Action(ActionNode),
+    /// `<mermaid>`
+    Mermaid { diagram: String },
+}
+
+/// A comment with its Dialect location resolved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommentNode {
+    /// Identifies the comment within the walkthrough
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// Markdown content of the comment, with its HTML sanitized
+    pub content: String,
+    /// The Dialect expression of the `location` attribute
+    pub dialect_expression: String,
+    /// Results of evaluating the expression
+    pub locations: Vec<serde_json::Value>,
+    /// Why the expression could not be evaluated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A git diff with its changed files.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitDiffNode {
+    pub range: String,
+    pub files: Vec<FileChange>,
+    /// Why the diff could not be generated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A button that sends `message` to the agent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionNode {
+    pub button: String,
+    pub message: String,
+}
+// ANCHOR_END: walkthrough_document
+
+impl WalkthroughDocument {
+    /// Start a new section under `heading`.
+    pub(crate) fn push_section(&mut self, heading: Heading) {
+        self.sections.push(WalkthroughSection {
+            heading: Some(heading),
+            nodes: vec![],
+        });
+    }
+
+    /// Append a node to the current section.
+    pub(crate) fn push_node(&mut self, node: WalkthroughNode) {
+        if self.sections.is_empty() {
+            self.sections.push(WalkthroughSection::default());
+        }
+        self.sections.last_mut().unwrap().nodes.push(node);
+    }
+
+    /// Append markdown to the current section, skipping blank text.
+    pub(crate) fn push_markdown(&mut self, markdown: &str) {
+        let markdown = markdown.trim();
+        if !markdown.is_empty() {
+            self.push_node(WalkthroughNode::Markdown {
+                markdown: sanitize_markdown(markdown),
+            });
+        }
+    }
+
+    /// Render the document as HTML for the walkthrough webview.
+    pub fn to_html(&self) -> String {
+        let mut output = String::new();
+        for section in &self.sections {
+            if let Some(heading) = &section.heading {
+                let level =
+                    HeadingLevel::try_from(heading.level as usize).unwrap_or(HeadingLevel::H6);
+                let events = [
+                    Event::Start(Tag::Heading {
+                        level,
+                        id: None,
+                        classes: vec![],
+                        attrs: vec![],
+                    }),
+                    Event::Text(heading.text.as_str().into()),
+                    Event::End(TagEnd::Heading(level)),
+                ];
+                html::push_html(&mut output, events.into_iter());
+            }
+            for node in &section.nodes {
+                output.push_str(&node.to_html());
+            }
+        }
+        output
+    }
+}
+
+impl WalkthroughNode {
+    /// Render the node as HTML, ending with a newline.
+    fn to_html(&self) -> String {
+        match self {
+            WalkthroughNode::Markdown { markdown } => render_markdown(markdown),
+            WalkthroughNode::Comment(comment) => comment_html(comment),
+            WalkthroughNode::GitDiff(diff) => gitdiff_html(diff),
+            WalkthroughNode::Action(action) => action_html(action),
+            // Kept as-is for client-side processing
+            WalkthroughNode::Mermaid { diagram } => {
+                format!("<mermaid>{}</mermaid>\n", escape_html(diagram))
+            }
+        }
+    }
+}
+
+fn comment_html(comment: &CommentNode) -> String {
+    // Comment data for the click handler
+    let comment_data = serde_json::json!({
+        "id": comment.id,
+        "locations": comment.locations,
+        "comment": [&comment.content]
+    });
+
+    let icon_emoji = match comment.icon.as_deref().unwrap_or("comment") {
+        "info" => "ℹ️",
+        "lightbulb" => "💡",
+        "gear" => "⚙️",
+        "warning" => "⚠️",
+        "question" => "❓",
+        _ => "💬",
+    };
+
+    let location_display = match comment.locations.as_slice() {
+        [] if comment.error.is_some() => "unresolved location".to_string(),
+        [] => "no location".to_string(),
+        // Single location - show file:line, of the definition for symbols
+        [location] => match location.get("definedAt").unwrap_or(location).as_object() {
+            Some(loc) => {
+                let path = loc
+                    .get("path")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("unknown");
+                let line = loc
+                    .get("start")
+                    .and_then(|v| v.get("line"))
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(1);
+                format!("{}:{}", path, line)
+            }
+            None => "unknown location".to_string(),
+        },
+        locations => format!("({} possible locations) 🔍", locations.len()),
+    };
+
+    format!(
+        r#"<div class="comment-item" data-comment="{}" style="cursor: pointer; border: 1px solid var(--vscode-panel-border); border-radius: 4px; padding: 8px; margin: 8px 0; background-color: var(--vscode-editor-background);">
+    <div style="display: flex; align-items: flex-start;">
+        <div class="comment-icon" style="margin-right: 8px; font-size: 16px;">{}</div>
+        <div class="comment-content" style="flex: 1;">
+            <div class="comment-locations" style="font-weight: 500; color: var(--vscode-textLink-foreground); margin-bottom: 4px; font-family: var(--vscode-editor-font-family); font-size: 0.9em;">{}</div>
+            <div class="comment-text" style="color: var(--vscode-foreground); font-size: 0.9em;">{}</div>
+        </div>
+    </div>
+</div>
+"#,
+        escape_html(&comment_data.to_string()),
+        icon_emoji,
+        escape_html(&location_display),
+        render_markdown(&comment.content)
+    )
+}
+
+fn gitdiff_html(diff: &GitDiffNode) -> String {
+    let body = match &diff.error {
+        Some(error) => format!(
+            r#"<div style="padding: 12px; color: var(--vscode-descriptionForeground);">{}: {}</div>"#,
+            escape_html(&diff.range),
+            escape_html(error)
+        ),
+        None => diff
+            .files
+            .iter()
+            .map(|file| {
+                format!(
+                    r#"<div class="file-diff"><div class="file-header"><span class="file-path clickable-file" data-file-path="{0}">{0}</span><span class="file-stats">+{1} -{2}</span></div></div>"#,
+                    escape_html(&file.path),
+                    file.additions,
+                    file.deletions
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+
+    format!(
+        r#"<div class="gitdiff-container" style="border: 1px solid var(--vscode-panel-border); border-radius: 4px; margin: 8px 0; background-color: var(--vscode-editor-background);">
+{}
+</div>
+"#,
+        body
+    )
+}
+
+fn action_html(action: &ActionNode) -> String {
+    format!(
+        r#"<button class="action-button" data-tell-agent="{}" style="background-color: var(--vscode-button-background); color: var(--vscode-button-foreground); border: none; padding: 8px 16px; border-radius: 4px; cursor: pointer; margin: 8px 0; font-size: 0.9em;">{}</button>
+"#,
+        escape_html(&action.message),
+        escape_html(&action.button)
+    )
+}
+
+/// Tags the HTML backend writes itself
+#[cfg(test)]
+pub(crate) const RENDERED_TAGS: &[&str] = &["button", "div", "mermaid", "span"];
+
+/// Attributes the HTML backend writes itself
+#[cfg(test)]
+pub(crate) const RENDERED_ATTRIBUTES: &[&str] = &[
+    "class",
+    "data-comment",
+    "data-file-path",
+    "data-tell-agent",
+    "style",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html_sanitizer::{arbitrary_text, assert_safe_html};
+    use expect_test::expect;
+
+    fn arbitrary_document(rng: &mut fastrand::Rng) -> WalkthroughDocument {
+        let mut document = WalkthroughDocument::default();
+        for _ in 0..rng.usize(1..4) {
+            document.push_section(Heading {
+                level: rng.u8(..8),
+                text: arbitrary_text(rng),
+            });
+            for _ in 0..rng.usize(1..6) {
+                let node = match rng.u8(..5) {
+                    0 => WalkthroughNode::Markdown {
+                        markdown: arbitrary_text(rng),
+                    },
+                    1 => WalkthroughNode::Comment(CommentNode {
+                        id: arbitrary_text(rng),
+                        icon: Some(arbitrary_text(rng)),
+                        content: arbitrary_text(rng),
+                        dialect_expression: arbitrary_text(rng),
+                        locations: (0..rng.usize(..3))
+                            .map(|_| {
+                                serde_json::json!({
+                                    "path": arbitrary_text(rng),
+                                    "start": {"line": 1}
+                                })
+                            })
+                            .collect(),
+                        error: rng.bool().then(|| arbitrary_text(rng)),
+                    }),
+                    2 => WalkthroughNode::GitDiff(GitDiffNode {
+                        range: arbitrary_text(rng),
+                        files: vec![FileChange::for_test(&arbitrary_text(rng), 1, &[])],
+                        error: rng.bool().then(|| arbitrary_text(rng)),
+                    }),
+                    3 => WalkthroughNode::Action(ActionNode {
+                        button: arbitrary_text(rng),
+                        message: arbitrary_text(rng),
+                    }),
+                    _ => WalkthroughNode::Mermaid {
+                        diagram: arbitrary_text(rng),
+                    },
+                };
+                document.push_node(node);
+            }
+        }
+        document
+    }
+
+    #[test]
+    fn test_html_backend_escapes_values() {
+        let mut document = WalkthroughDocument::default();
+        document.push_node(WalkthroughNode::Action(ActionNode {
+            button: r#"<img src=x onerror="alert('x')">"#.to_string(),
+            message: r#"it's "quoted""#.to_string(),
+        }));
+        document.push_node(WalkthroughNode::Mermaid {
+            diagram: "A['a'] --> B</mermaid><script>".to_string(),
+        });
+        expect![[r#"
+            <button class="action-button" data-tell-agent="it&#39;s &quot;quoted&quot;" style="background-color: var(--vscode-button-background); color: var(--vscode-button-foreground); border: none; padding: 8px 16px; border-radius: 4px; cursor: pointer; margin: 8px 0; font-size: 0.9em;">&lt;img src=x onerror=&quot;alert(&#39;x&#39;)&quot;&gt;</button>
+            <mermaid>A[&#39;a&#39;] --&gt; B&lt;/mermaid&gt;&lt;script&gt;</mermaid>
+        "#]]
+        .assert_eq(&document.to_html());
+    }
+
+    #[test]
+    fn test_arbitrary_documents_render_safely() {
+        let mut rng = fastrand::Rng::with_seed(0xd0c);
+        for _ in 0..300 {
+            let html = arbitrary_document(&mut rng).to_html();
+            assert_safe_html(&html, RENDERED_TAGS, RENDERED_ATTRIBUTES);
+        }
+    }
+}
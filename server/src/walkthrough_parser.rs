@@ -1,13 +1,16 @@
-use pulldown_cmark::{Event, Parser, html, Tag, TagEnd, CowStr};
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
 use quick_xml::events::Event as XmlEvent;
 use quick_xml::Reader;
-use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
 use anyhow::Result;
-use uuid::Uuid;
 
 use crate::dialect::{DialectInterpreter};
+use crate::html_sanitizer::sanitize_markdown;
 use crate::ide::IpcClient;
+use crate::synthetic_pr::DiffSettings;
+use crate::walkthrough_document::{
+    ActionNode, CommentNode, GitDiffNode, Heading, WalkthroughDocument, WalkthroughNode,
+};
 
 /// Parsed XML element from walkthrough markdown
 #[derive(Debug, Clone, PartialEq)]
@@ -21,6 +24,7 @@ pub enum XmlElement {
         range: String,
         exclude_unstaged: bool,
         exclude_staged: bool,
+        settings: DiffSettings,
     },
     Action {
         button: String,
@@ -31,57 +35,113 @@ pub enum XmlElement {
     },
 }
 
-/// Resolved XML element with dummy data for Phase 1
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ResolvedXmlElement {
-    pub element_type: String,
-    pub attributes: HashMap<String, String>,
-    pub resolved_data: serde_json::Value,
-    pub content: String,
+impl XmlElement {
+    /// Replace the element's content (the comment text, action message or diagram)
+    fn with_content(self, content: String) -> Self {
+        match self {
+            XmlElement::Comment { location, icon, .. } => XmlElement::Comment { location, icon, content },
+            XmlElement::Action { button, .. } => XmlElement::Action { button, message: content },
+            XmlElement::Mermaid { .. } => XmlElement::Mermaid { content },
+            gitdiff @ XmlElement::GitDiff { .. } => gitdiff,
+        }
+    }
 }
 
 /// Main walkthrough parser
 pub struct WalkthroughParser<T: IpcClient + Clone + 'static> {
     interpreter: DialectInterpreter<T>,
+    /// Comments resolved so far, used to number comment ids
+    comment_count: usize,
 }
 
 impl<T: IpcClient + Clone + 'static> WalkthroughParser<T> {
     pub fn new(interpreter: DialectInterpreter<T>) -> Self {
-        Self { interpreter }
-    }
-
-    /// Parse markdown with embedded XML elements and return normalized output
-    pub async fn parse_and_normalize(&mut self, content: &str) -> Result<String, anyhow::Error> {
-        let processed_events = self.process_events_sequentially(content).await?;
-        Self::render_events_to_markdown(processed_events)
+        Self { interpreter, comment_count: 0 }
     }
 
-    /// Process pulldown-cmark event stream sequentially
-    async fn process_events_sequentially<'a>(&mut self, content: &'a str) -> Result<Vec<Event<'a>>, anyhow::Error> {
-        let mut input_events: VecDeque<Event<'a>> = Parser::new(content).collect();
-        let mut output_events = Vec::new();
-        
-        while let Some(event) = input_events.pop_front() {
+    /// Parse markdown with embedded XML elements into a resolved walkthrough document.
+    ///
+    /// Headings start sections. XML elements become nodes with their data resolved, and the
+    /// markdown around them is kept as markdown nodes; an element inside a paragraph splits
+    /// the paragraph. Elements that fail to parse stay in the markdown as they were.
+    pub async fn parse(&mut self, content: &str) -> Result<WalkthroughDocument, anyhow::Error> {
+        self.comment_count = 0;
+
+        let mut events: VecDeque<_> = Parser::new(content).into_offset_iter().collect();
+        let mut document = WalkthroughDocument::default();
+        // Start of the markdown not yet added to the document
+        let mut cursor = 0;
+        let mut depth = 0;
+
+        while let Some((event, range)) = events.pop_front() {
             match event {
-                Event::InlineHtml(html) => {
-                    if self.is_xml_element(&html) {
-                        self.process_inline_xml(html, &mut input_events, &mut output_events).await?;
-                    } else {
-                        output_events.push(Event::InlineHtml(html));
+                Event::Start(Tag::Heading { level, .. }) if depth == 0 => {
+                    let mut text = String::new();
+                    while let Some((event, _)) = events.pop_front() {
+                        match event {
+                            Event::End(TagEnd::Heading(_)) => break,
+                            Event::Text(t) | Event::Code(t) => text.push_str(&t),
+                            _ => {}
+                        }
                     }
+                    document.push_markdown(&content[cursor..range.start]);
+                    document.push_section(Heading { level: level as u8, text });
+                    cursor = range.end;
                 }
-                Event::Start(Tag::HtmlBlock) => {
-                    if self.is_xml_block(&input_events) {
-                        self.process_xml_block(&mut input_events, &mut output_events).await?;
+                Event::Start(Tag::HtmlBlock) if self.is_xml_block(&events) => {
+                    let mut xml_content = String::new();
+                    while let Some((event, _)) = events.pop_front() {
+                        match event {
+                            Event::Html(html) => xml_content.push_str(&html),
+                            _ => break,
+                        }
+                    }
+
+                    // If parsing fails, the block stays in the markdown
+                    if let Ok(xml_element) = self.parse_xml_element(&xml_content) {
+                        document.push_markdown(&content[cursor..range.start]);
+                        document.push_node(self.resolve_single_element(xml_element).await?);
+                        cursor = range.end;
+                    }
+                }
+                Event::InlineHtml(html) if self.is_xml_element(&html) && !html.trim_start().starts_with("</") => {
+                    // Self-closing tags stand alone; otherwise the element's content is the
+                    // markdown up to its closing tag
+                    let (xml_text, element_end, element_content) = if html.contains("/>") {
+                        (html.to_string(), range.end, None)
                     } else {
-                        output_events.push(Event::Start(Tag::HtmlBlock));
+                        let closing_tag = format!("</{}", Self::tag_name(&html));
+                        let Some(close) = events.iter().position(|(event, _)| {
+                            matches!(event, Event::InlineHtml(closing) if closing.trim_start().starts_with(&closing_tag))
+                        }) else {
+                            continue;
+                        };
+                        events.drain(..close);
+                        let Some((Event::InlineHtml(closing_html), closing_range)) = events.pop_front() else {
+                            unreachable!("position found an inline closing tag");
+                        };
+                        let element_content = content[range.end..closing_range.start].trim().to_string();
+                        (format!("{}{}", html, closing_html), closing_range.end, Some(element_content))
+                    };
+
+                    if let Ok(xml_element) = self.parse_xml_element(&xml_text) {
+                        let xml_element = match element_content {
+                            Some(element_content) => xml_element.with_content(element_content),
+                            None => xml_element,
+                        };
+                        document.push_markdown(&content[cursor..range.start]);
+                        document.push_node(self.resolve_single_element(xml_element).await?);
+                        cursor = element_end;
                     }
                 }
-                _ => output_events.push(event),
+                Event::Start(_) => depth += 1,
+                Event::End(_) => depth -= 1,
+                _ => {}
             }
         }
-        
-        Ok(output_events)
+        document.push_markdown(&content[cursor..]);
+
+        Ok(document)
     }
 
     /// Check if HTML content is one of our XML elements
@@ -97,257 +157,85 @@ impl<T: IpcClient + Clone + 'static> WalkthroughParser<T> {
     }
 
     /// Check if upcoming events contain XML block content
-    fn is_xml_block(&self, upcoming_events: &VecDeque<Event>) -> bool {
-        if let Some(Event::Html(html)) = upcoming_events.front() {
+    fn is_xml_block<R>(&self, upcoming_events: &VecDeque<(Event, R)>) -> bool {
+        if let Some((Event::Html(html), _)) = upcoming_events.front() {
             self.is_xml_element(html)
         } else {
             false
         }
     }
 
-    /// Process inline XML elements (opening tag, content, closing tag)
-    async fn process_inline_xml<'a>(
-        &mut self,
-        html: CowStr<'a>,
-        input_events: &mut VecDeque<Event<'a>>,
-        output_events: &mut Vec<Event<'a>>,
-    ) -> Result<(), anyhow::Error> {
-        // If this is a self-closing tag, handle it directly
-        if html.contains("/>") {
-            let xml_content = html.to_string();
-            if let Ok(xml_element) = self.parse_xml_element(&xml_content) {
-                let resolved = self.resolve_single_element(xml_element).await?;
-                let normalized_xml = self.create_normalized_xml(&resolved);
-                output_events.push(Event::InlineHtml(normalized_xml.into()));
-            } else {
-                output_events.push(Event::InlineHtml(html));
-            }
-            return Ok(());
-        }
-
-        // If this is a closing tag, pass it through (shouldn't happen in our flow)
-        if html.starts_with("</") {
-            output_events.push(Event::InlineHtml(html));
-            return Ok(());
-        }
-
-        // This is an opening tag - collect all events until closing tag
-        let mut content_events = Vec::new();
-        
-        while let Some(event) = input_events.pop_front() {
-            match event {
-                Event::InlineHtml(closing_html) if closing_html.starts_with("</") => {
-                    // Found closing tag - render collected content and create complete XML
-                    let mut content_html = String::new();
-                    html::push_html(&mut content_html, content_events.iter().cloned());
-                    
-                    // Try to parse just the opening tag to get attributes
-                    if let Ok(xml_element) = self.parse_xml_element(&format!("{}</{}>", html, &closing_html[2..])) {
-                        let resolved = self.resolve_single_element(xml_element).await?;
-                        
-                        // Create the resolved XML with the rendered content
-                        let mut attrs = String::new();
-                        let resolved_json = serde_json::to_string(&resolved.resolved_data).unwrap_or_default();
-                        attrs.push_str(&format!(" data-resolved='{}'", resolved_json));
-                        
-                        for (key, value) in &resolved.attributes {
-                            attrs.push_str(&format!(" {}=\"{}\"", key, value));
-                        }
-                        
-                        let tag_name = resolved.element_type;
-                        let normalized_xml = format!("<{}{}>{}{}", tag_name, attrs, content_html, closing_html);
-                        output_events.push(Event::InlineHtml(normalized_xml.into()));
-                    } else {
-                        // If parsing fails, pass through original
-                        output_events.push(Event::InlineHtml(html));
-                        output_events.extend(content_events);
-                        output_events.push(Event::InlineHtml(closing_html));
-                    }
-                    return Ok(());
-                }
-                _ => {
-                    // Collect all events between opening and closing tags
-                    content_events.push(event);
-                }
-            }
-        }
-
-        // If we get here, no closing tag was found - pass through original
-        output_events.push(Event::InlineHtml(html));
-        output_events.extend(content_events);
-        Ok(())
-    }
-
-    /// Process block-level XML elements
-    async fn process_xml_block<'a>(
-        &mut self,
-        input_events: &mut VecDeque<Event<'a>>,
-        output_events: &mut Vec<Event<'a>>,
-    ) -> Result<(), anyhow::Error> {
-        let mut xml_content = String::new();
-        
-        // Collect all HTML events until End(HtmlBlock)
-        while let Some(event) = input_events.pop_front() {
-            match event {
-                Event::Html(html) => xml_content.push_str(&html),
-                Event::End(TagEnd::HtmlBlock) => break,
-                _ => {
-                    // Put back unexpected event and break
-                    input_events.push_front(event);
-                    break;
-                }
-            }
-        }
-
-        // Parse and resolve the complete XML block
-        if let Ok(xml_element) = self.parse_xml_element(&xml_content) {
-            let resolved = self.resolve_single_element(xml_element).await?;
-            let normalized_xml = self.create_normalized_xml(&resolved);
-            
-            // Emit as HTML block
-            output_events.push(Event::Start(Tag::HtmlBlock));
-            output_events.push(Event::Html(normalized_xml.into()));
-            output_events.push(Event::End(TagEnd::HtmlBlock));
-        } else {
-            // If parsing fails, pass through original
-            output_events.push(Event::Start(Tag::HtmlBlock));
-            output_events.push(Event::Html(xml_content.into()));
-            output_events.push(Event::End(TagEnd::HtmlBlock));
-        }
-
-        Ok(())
-    }
-
-    /// Render pulldown-cmark events back to markdown/HTML
-    fn render_events_to_markdown<'a>(events: Vec<Event<'a>>) -> Result<String, anyhow::Error> {
-        let mut output = String::new();
-        html::push_html(&mut output, events.into_iter());
-        Ok(output)
+    /// Name of the element an opening tag starts (`comment` for `<comment location="...">`)
+    fn tag_name(html: &str) -> &str {
+        let tag = html.trim_start().trim_start_matches('<');
+        let end = tag.find(|c: char| c.is_whitespace() || c == '>' || c == '/').unwrap_or(tag.len());
+        &tag[..end]
     }
 
     /// Resolve a single XML element with Dialect evaluation
-    async fn resolve_single_element(&mut self, element: XmlElement) -> Result<ResolvedXmlElement, anyhow::Error> {
-        let (element_type, attributes, resolved_data) = match &element {
-            XmlElement::Comment { location, icon, content: _ } => {
-                let mut attrs = HashMap::new();
-                if let Some(icon) = icon {
-                    attrs.insert("icon".to_string(), icon.clone());
-                }
-                
+    async fn resolve_single_element(&mut self, element: XmlElement) -> Result<WalkthroughNode, anyhow::Error> {
+        let node = match element {
+            XmlElement::Comment { location, icon, content } => {
+                self.comment_count += 1;
+                let mut comment = CommentNode {
+                    id: format!("comment-{}", self.comment_count),
+                    icon,
+                    content: sanitize_markdown(&content),
+                    dialect_expression: location.clone(),
+                    locations: vec![],
+                    error: None,
+                };
+
                 // Resolve Dialect expression for location
-                let resolved_data = if !location.is_empty() {
+                if !location.is_empty() {
                     // Clone interpreter for thread safety
                     let mut interpreter = self.interpreter.clone();
-                    let location_clone = location.clone();
-                    
+
                     let result = tokio::task::spawn_blocking(move || {
                         tokio::runtime::Handle::current().block_on(async move {
-                            interpreter.evaluate(&location_clone).await
+                            interpreter.evaluate(&location).await
                         })
                     }).await.map_err(|e| anyhow::anyhow!("Task execution failed: {}", e))?;
-                    
+
                     match result {
-                        Ok(result) => {
-                            serde_json::json!({
-                                "locations": result,
-                                "dialect_expression": location
-                            })
-                        }
-                        Err(e) => {
-                            serde_json::json!({
-                                "error": format!("Failed to resolve location: {}", e),
-                                "dialect_expression": location
-                            })
-                        }
+                        Ok(serde_json::Value::Array(locations)) => comment.locations = locations,
+                        Ok(serde_json::Value::Null) => {}
+                        Ok(location) => comment.locations = vec![location],
+                        Err(e) => comment.error = Some(format!("Failed to resolve location: {}", e)),
                     }
-                } else {
-                    serde_json::json!({
-                        "locations": []
-                    })
-                };
-                
-                ("comment".to_string(), attrs, resolved_data)
+                }
+
+                WalkthroughNode::Comment(comment)
             }
-            XmlElement::GitDiff { range, exclude_unstaged, exclude_staged } => {
+            XmlElement::GitDiff { range, settings, .. } => {
                 // Use GitService to generate actual file changes
                 use crate::synthetic_pr::git_service::GitService;
-                
-                let resolved_data = match GitService::new(".") {
-                    Ok(git_service) => {
-                        match git_service.parse_commit_range(range).and_then(|(base_oid, head_oid)| {
-                            git_service.generate_diff(base_oid, head_oid)
-                        }) {
-                            Ok(file_changes) => {
-                                serde_json::json!({
-                                    "type": "gitdiff",
-                                    "range": range,
-                                    "files": file_changes
-                                })
-                            }
-                            Err(e) => {
-                                // Fallback for git errors (tests, non-git directories, etc.)
-                                serde_json::json!({
-                                    "type": "gitdiff",
-                                    "range": range,
-                                    "error": format!("Git error: {}", e)
-                                })
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        // Fallback for non-git directories (like in tests)
-                        serde_json::json!({
-                            "type": "gitdiff",
-                            "range": range,
-                            "error": format!("Not a git repository: {}", e)
+
+                let files = match GitService::new(".") {
+                    Ok(git_service) => git_service
+                        .parse_commit_range(&range)
+                        .and_then(|(base_oid, head_oid)| {
+                            git_service.generate_diff_with_settings(base_oid, head_oid, &settings)
                         })
-                    }
+                        // Fallback for git errors (tests, bad ranges, etc.)
+                        .map_err(|e| format!("Git error: {}", e)),
+                    // Fallback for non-git directories
+                    Err(e) => Err(format!("Not a git repository: {}", e)),
                 };
-                
-                let mut attrs = HashMap::new();
-                if *exclude_unstaged {
-                    attrs.insert("exclude-unstaged".to_string(), "true".to_string());
-                }
-                if *exclude_staged {
-                    attrs.insert("exclude-staged".to_string(), "true".to_string());
-                }
-                
-                ("gitdiff".to_string(), attrs, resolved_data)
-            }
-            XmlElement::Action { button, message: _ } => {
-                let mut attrs = HashMap::new();
-                attrs.insert("button".to_string(), button.clone());
-                
-                let resolved_data = serde_json::json!({
-                    "button_text": button
-                });
-                
-                ("action".to_string(), attrs, resolved_data)
+
+                let (files, error) = match files {
+                    Ok(files) => (files, None),
+                    Err(error) => (vec![], Some(error)),
+                };
+                WalkthroughNode::GitDiff(GitDiffNode { range, files, error })
             }
-            XmlElement::Mermaid { content: _ } => {
-                let attrs = HashMap::new();
-                let resolved_data = serde_json::json!({
-                    "type": "mermaid",
-                    "rendered": true
-                });
-                
-                ("mermaid".to_string(), attrs, resolved_data)
+            XmlElement::Action { button, message } => {
+                WalkthroughNode::Action(ActionNode { button, message })
             }
+            XmlElement::Mermaid { content } => WalkthroughNode::Mermaid { diagram: content },
         };
 
-        let content = match &element {
-            XmlElement::Comment { content, .. } => content.clone(),
-            XmlElement::Action { message, .. } => message.clone(),
-            XmlElement::Mermaid { content } => content.clone(),
-            XmlElement::GitDiff { .. } => String::new(),
-        };
-
-        Ok(ResolvedXmlElement {
-            element_type,
-            attributes,
-            resolved_data,
-            content,
-        })
+        Ok(node)
     }
 
     fn parse_xml_element(&self, xml_text: &str) -> Result<XmlElement, anyhow::Error> {
@@ -405,6 +293,7 @@ impl<T: IpcClient + Clone + 'static> WalkthroughParser<T> {
                 range: attributes.get("range").unwrap_or(&String::new()).clone(),
                 exclude_unstaged: attributes.contains_key("exclude-unstaged"),
                 exclude_staged: attributes.contains_key("exclude-staged"),
+                settings: Self::parse_diff_settings(&attributes)?,
             }),
             "action" => Ok(XmlElement::Action {
                 button: attributes.get("button").unwrap_or(&String::new()).clone(),
@@ -415,123 +304,38 @@ impl<T: IpcClient + Clone + 'static> WalkthroughParser<T> {
         }
     }
 
-    /// Create final HTML element with resolved data
-    fn create_normalized_xml(&self, resolved: &ResolvedXmlElement) -> String {
-        match resolved.element_type.as_str() {
-            "comment" => self.create_comment_html(resolved),
-            "action" => self.create_action_html(resolved),
-            "gitdiff" => self.create_gitdiff_html(resolved),
-            "mermaid" => self.create_mermaid_html(resolved),
-            _ => {
-                // Fallback to original XML format for unknown types
-                let mut attrs = String::new();
-                let resolved_json = serde_json::to_string(&resolved.resolved_data).unwrap_or_default();
-                attrs.push_str(&format!(" data-resolved='{}'", resolved_json));
-                
-                for (key, value) in &resolved.attributes {
-                    attrs.push_str(&format!(" {}=\"{}\"", key, value));
-                }
-
-                if resolved.content.is_empty() {
-                    format!("<{}{} />", resolved.element_type, attrs)
-                } else {
-                    format!(
-                        "<{}{}>{}</{}>",
-                        resolved.element_type, attrs, resolved.content, resolved.element_type
-                    )
-                }
-            }
+    /// Build diff settings from `<gitdiff>` attributes.
+    ///
+    /// Supports `context-lines="5"`, `ignore-whitespace="none|eol|change|all"`,
+    /// `exclude-untracked="true"` and comma-separated `paths="server/src/,README.md"`.
+    fn parse_diff_settings(attributes: &HashMap<String, String>) -> Result<DiffSettings, anyhow::Error> {
+        let mut settings = DiffSettings::default();
+
+        if let Some(context_lines) = attributes.get("context-lines") {
+            settings.context_lines = context_lines
+                .trim()
+                .parse()
+                .map_err(|e| anyhow::anyhow!("Invalid context-lines '{}': {}", context_lines, e))?;
         }
-    }
-
-    /// Generate HTML for comment elements
-    fn create_comment_html(&self, resolved: &ResolvedXmlElement) -> String {
-        // Extract locations from resolved data
-        let empty_vec = vec![];
-        let locations = resolved.resolved_data.get("locations")
-            .and_then(|v| v.as_array())
-            .unwrap_or(&empty_vec);
-
-        // Generate comment data for click handler
-        let comment_data = serde_json::json!({
-            "id": format!("comment-{}", Uuid::new_v4()),
-            "locations": locations,
-            "comment": [&resolved.content]
-        });
-
-        // Get icon from attributes
-        let default_icon = "comment".to_string();
-        let icon = resolved.attributes.get("icon").unwrap_or(&default_icon);
-        let icon_emoji = match icon.as_str() {
-            "info" => "ℹ️",
-            "lightbulb" => "💡",
-            "gear" => "⚙️",
-            "warning" => "⚠️",
-            "question" => "❓",
-            _ => "💬",
-        };
-
-        // Generate location display
-        let location_display = if locations.len() == 1 {
-            // Single location - show file:line
-            if let Some(loc) = locations[0].as_object() {
-                let path = loc.get("path").and_then(|v| v.as_str()).unwrap_or("unknown");
-                let line = loc.get("start").and_then(|v| v.get("line")).and_then(|v| v.as_u64()).unwrap_or(1);
-                format!("{}:{}", path, line)
-            } else {
-                "unknown location".to_string()
-            }
-        } else if locations.len() > 1 {
-            // Multiple locations - show count
-            format!("({} possible locations) 🔍", locations.len())
-        } else {
-            "no location".to_string()
-        };
-
-        let comment_data_encoded = serde_json::to_string(&comment_data).unwrap_or_default();
-        let comment_data_escaped = comment_data_encoded.replace('"', "&quot;");
 
-        format!(
-            r#"<div class="comment-item" data-comment="{}" style="cursor: pointer; border: 1px solid var(--vscode-panel-border); border-radius: 4px; padding: 8px; margin: 8px 0; background-color: var(--vscode-editor-background);">
-                <div style="display: flex; align-items: flex-start;">
-                    <div class="comment-icon" style="margin-right: 8px; font-size: 16px;">{}</div>
-                    <div class="comment-content" style="flex: 1;">
-                        <div class="comment-locations" style="font-weight: 500; color: var(--vscode-textLink-foreground); margin-bottom: 4px; font-family: var(--vscode-editor-font-family); font-size: 0.9em;">{}</div>
-                        <div class="comment-text" style="color: var(--vscode-foreground); font-size: 0.9em;">{}</div>
-                    </div>
-                </div>
-            </div>"#,
-            comment_data_escaped, icon_emoji, location_display, resolved.content
-        )
-    }
-
-    /// Generate HTML for action elements
-    fn create_action_html(&self, resolved: &ResolvedXmlElement) -> String {
-        let default_button = "Action".to_string();
-        let button_text = resolved.attributes.get("button").unwrap_or(&default_button);
-        let tell_agent = resolved.content.replace('"', "&quot;");
+        if let Some(mode) = attributes.get("ignore-whitespace") {
+            settings.ignore_whitespace = mode.trim().parse().map_err(|e: String| anyhow::anyhow!(e))?;
+        }
 
-        format!(
-            r#"<button class="action-button" data-tell-agent="{}" style="background-color: var(--vscode-button-background); color: var(--vscode-button-foreground); border: none; padding: 8px 16px; border-radius: 4px; cursor: pointer; margin: 8px 0; font-size: 0.9em;">{}</button>"#,
-            tell_agent, button_text
-        )
-    }
+        if attributes.contains_key("exclude-untracked") {
+            settings.include_untracked = false;
+        }
 
-    /// Generate HTML for gitdiff elements
-    fn create_gitdiff_html(&self, resolved: &ResolvedXmlElement) -> String {
-        // For now, return a placeholder - we'll implement this properly later
-        format!(
-            r#"<div class="gitdiff-container" style="border: 1px solid var(--vscode-panel-border); border-radius: 4px; margin: 8px 0; background-color: var(--vscode-editor-background);">
-                <div style="padding: 12px; color: var(--vscode-descriptionForeground);">GitDiff rendering: {}</div>
-            </div>"#,
-            resolved.resolved_data.get("range").and_then(|v| v.as_str()).unwrap_or("unknown")
-        )
-    }
+        if let Some(paths) = attributes.get("paths") {
+            settings.paths = paths
+                .split(',')
+                .map(|p| p.trim())
+                .filter(|p| !p.is_empty())
+                .map(|p| p.to_string())
+                .collect();
+        }
 
-    /// Generate HTML for mermaid elements
-    fn create_mermaid_html(&self, resolved: &ResolvedXmlElement) -> String {
-        // Keep mermaid elements as-is for client-side processing
-        format!("<mermaid>{}</mermaid>", resolved.content)
+        Ok(settings)
     }
 }
 
@@ -549,11 +353,19 @@ mod tests {
         WalkthroughParser::new(interpreter)
     }
 
-    fn check(input: &str, expect: Expect) {
+    fn parse(input: &str) -> WalkthroughDocument {
         let rt = tokio::runtime::Runtime::new().unwrap();
         let mut parser = create_test_parser();
-        let result = rt.block_on(parser.parse_and_normalize(input)).unwrap();
-        expect.assert_eq(&result);
+        rt.block_on(parser.parse(input)).unwrap()
+    }
+
+    fn check(input: &str, expect: Expect) {
+        let document = parse(input);
+        expect.assert_eq(&serde_json::to_string_pretty(&document).unwrap());
+    }
+
+    fn check_html(input: &str, expect: Expect) {
+        expect.assert_eq(&parse(input).to_html());
     }
 
     #[test]
@@ -561,8 +373,38 @@ mod tests {
         check(
             r#"<comment location="findDefinitions(`User`)">User struct</comment>"#,
             expect![[r#"
-                <p><comment data-resolved='{"dialect_expression":"findDefinitions(`User`)","locations":[{"definedAt":{"content":"struct User {","end":{"column":4,"line":10},"path":"src/models.rs","start":{"column":0,"line":10}},"kind":"struct","name":"User"}]}'>User struct</comment></p>
-            "#]],
+                {
+                  "sections": [
+                    {
+                      "nodes": [
+                        {
+                          "type": "comment",
+                          "id": "comment-1",
+                          "content": "User struct",
+                          "dialect_expression": "findDefinitions(`User`)",
+                          "locations": [
+                            {
+                              "definedAt": {
+                                "content": "struct User {",
+                                "end": {
+                                  "column": 4,
+                                  "line": 10
+                                },
+                                "path": "src/models.rs",
+                                "start": {
+                                  "column": 0,
+                                  "line": 10
+                                }
+                              },
+                              "kind": "struct",
+                              "name": "User"
+                            }
+                          ]
+                        }
+                      ]
+                    }
+                  ]
+                }"#]],
         );
     }
 
@@ -570,7 +412,21 @@ mod tests {
     fn test_self_closing_gitdiff() {
         check(
             r#"<gitdiff range="HEAD~1..HEAD" />"#,
-            expect![[r#"<gitdiff data-resolved='{"error":"Not a git repository: could not find repository at '.'; class=Repository (6); code=NotFound (-3)","range":"HEAD~1..HEAD","type":"gitdiff"}' />"#]],
+            expect![[r#"
+                {
+                  "sections": [
+                    {
+                      "nodes": [
+                        {
+                          "type": "gitdiff",
+                          "range": "HEAD~1..HEAD",
+                          "files": [],
+                          "error": "Not a git repository: could not find repository at '.'; class=Repository (6); code=NotFound (-3)"
+                        }
+                      ]
+                    }
+                  ]
+                }"#]],
         );
     }
 
@@ -579,8 +435,19 @@ mod tests {
         check(
             r#"<action button="Next Step">What should we do next?</action>"#,
             expect![[r#"
-                <p><action data-resolved='{"button_text":"Next Step"}' button="Next Step">What should we do next?</action></p>
-            "#]],
+                {
+                  "sections": [
+                    {
+                      "nodes": [
+                        {
+                          "type": "action",
+                          "button": "Next Step",
+                          "message": "What should we do next?"
+                        }
+                      ]
+                    }
+                  ]
+                }"#]],
         );
     }
 
@@ -597,17 +464,76 @@ This explains the User struct
 
 More markdown here.
 
+## Changes
+
 <gitdiff range="HEAD~1..HEAD" />
 
 <action button="Next Step">What should we do next?</action>"#,
             expect![[r#"
-                <h1>My Walkthrough</h1>
-                <p>This is some markdown content.</p>
-                <comment data-resolved='{"dialect_expression":"findDefinitions(`User`)","locations":[{"definedAt":{"content":"struct User {","end":{"column":4,"line":10},"path":"src/models.rs","start":{"column":0,"line":10}},"kind":"struct","name":"User"}]}' icon="lightbulb">This explains the User struct</comment>
-                <p>More markdown here.</p>
-                <gitdiff data-resolved='{"error":"Not a git repository: could not find repository at '.'; class=Repository (6); code=NotFound (-3)","range":"HEAD~1..HEAD","type":"gitdiff"}' />
-                <p><action data-resolved='{"button_text":"Next Step"}' button="Next Step">What should we do next?</action></p>
-            "#]],
+                {
+                  "sections": [
+                    {
+                      "heading": {
+                        "level": 1,
+                        "text": "My Walkthrough"
+                      },
+                      "nodes": [
+                        {
+                          "type": "markdown",
+                          "markdown": "This is some markdown content."
+                        },
+                        {
+                          "type": "comment",
+                          "id": "comment-1",
+                          "icon": "lightbulb",
+                          "content": "This explains the User struct",
+                          "dialect_expression": "findDefinitions(`User`)",
+                          "locations": [
+                            {
+                              "definedAt": {
+                                "content": "struct User {",
+                                "end": {
+                                  "column": 4,
+                                  "line": 10
+                                },
+                                "path": "src/models.rs",
+                                "start": {
+                                  "column": 0,
+                                  "line": 10
+                                }
+                              },
+                              "kind": "struct",
+                              "name": "User"
+                            }
+                          ]
+                        },
+                        {
+                          "type": "markdown",
+                          "markdown": "More markdown here."
+                        }
+                      ]
+                    },
+                    {
+                      "heading": {
+                        "level": 2,
+                        "text": "Changes"
+                      },
+                      "nodes": [
+                        {
+                          "type": "gitdiff",
+                          "range": "HEAD~1..HEAD",
+                          "files": [],
+                          "error": "Not a git repository: could not find repository at '.'; class=Repository (6); code=NotFound (-3)"
+                        },
+                        {
+                          "type": "action",
+                          "button": "Next Step",
+                          "message": "What should we do next?"
+                        }
+                      ]
+                    }
+                  ]
+                }"#]],
         );
     }
 
@@ -621,13 +547,60 @@ Some text after
 <gitdiff range="HEAD" />
 More text"#,
             expect![[r#"
-                <h1>Title</h1>
-                <p>Some text before
-                <comment data-resolved='{"dialect_expression":"findDefinitions(`User`)","locations":[{"definedAt":{"content":"struct User {","end":{"column":4,"line":10},"path":"src/models.rs","start":{"column":0,"line":10}},"kind":"struct","name":"User"}]}'>User comment</comment>
-                Some text after
-                <gitdiff data-resolved='{"error":"Not a git repository: could not find repository at '.'; class=Repository (6); code=NotFound (-3)","range":"HEAD","type":"gitdiff"}' />
-                More text</p>
-            "#]],
+                {
+                  "sections": [
+                    {
+                      "heading": {
+                        "level": 1,
+                        "text": "Title"
+                      },
+                      "nodes": [
+                        {
+                          "type": "markdown",
+                          "markdown": "Some text before"
+                        },
+                        {
+                          "type": "comment",
+                          "id": "comment-1",
+                          "content": "User comment",
+                          "dialect_expression": "findDefinitions(`User`)",
+                          "locations": [
+                            {
+                              "definedAt": {
+                                "content": "struct User {",
+                                "end": {
+                                  "column": 4,
+                                  "line": 10
+                                },
+                                "path": "src/models.rs",
+                                "start": {
+                                  "column": 0,
+                                  "line": 10
+                                }
+                              },
+                              "kind": "struct",
+                              "name": "User"
+                            }
+                          ]
+                        },
+                        {
+                          "type": "markdown",
+                          "markdown": "Some text after"
+                        },
+                        {
+                          "type": "gitdiff",
+                          "range": "HEAD",
+                          "files": [],
+                          "error": "Not a git repository: could not find repository at '.'; class=Repository (6); code=NotFound (-3)"
+                        },
+                        {
+                          "type": "markdown",
+                          "markdown": "More text"
+                        }
+                      ]
+                    }
+                  ]
+                }"#]],
         );
     }
 
@@ -636,10 +609,134 @@ More text"#,
         check(
             r#"<comment location="findDefinitions(`User`)">This has *emphasis* and **bold** text</comment>"#,
             expect![[r#"
-                <p><comment data-resolved='{"dialect_expression":"findDefinitions(`User`)","locations":[{"definedAt":{"content":"struct User {","end":{"column":4,"line":10},"path":"src/models.rs","start":{"column":0,"line":10}},"kind":"struct","name":"User"}]}'>This has <em>emphasis</em> and <strong>bold</strong> text</comment></p>
+                {
+                  "sections": [
+                    {
+                      "nodes": [
+                        {
+                          "type": "comment",
+                          "id": "comment-1",
+                          "content": "This has *emphasis* and **bold** text",
+                          "dialect_expression": "findDefinitions(`User`)",
+                          "locations": [
+                            {
+                              "definedAt": {
+                                "content": "struct User {",
+                                "end": {
+                                  "column": 4,
+                                  "line": 10
+                                },
+                                "path": "src/models.rs",
+                                "start": {
+                                  "column": 0,
+                                  "line": 10
+                                }
+                              },
+                              "kind": "struct",
+                              "name": "User"
+                            }
+                          ]
+                        }
+                      ]
+                    }
+                  ]
+                }"#]],
+        );
+    }
+
+    #[test]
+    fn test_quote_in_resolved_data() {
+        // Resolved data used to be spliced into a single-quoted attribute
+        check(
+            r#"<comment location="findDefinitions(`User`)">The user's struct</comment>
+
+<mermaid>
+flowchart TD
+    A['start'] --> B
+</mermaid>"#,
+            expect![[r#"
+                {
+                  "sections": [
+                    {
+                      "nodes": [
+                        {
+                          "type": "comment",
+                          "id": "comment-1",
+                          "content": "The user's struct",
+                          "dialect_expression": "findDefinitions(`User`)",
+                          "locations": [
+                            {
+                              "definedAt": {
+                                "content": "struct User {",
+                                "end": {
+                                  "column": 4,
+                                  "line": 10
+                                },
+                                "path": "src/models.rs",
+                                "start": {
+                                  "column": 0,
+                                  "line": 10
+                                }
+                              },
+                              "kind": "struct",
+                              "name": "User"
+                            }
+                          ]
+                        },
+                        {
+                          "type": "mermaid",
+                          "diagram": "flowchart TD\n    A['start'] --> B"
+                        }
+                      ]
+                    }
+                  ]
+                }"#]],
+        );
+    }
+
+    #[test]
+    fn test_html_backend() {
+        check_html(
+            r#"# My Walkthrough
+
+Some *markdown*.
+
+<comment location="findDefinitions(`User`)" icon="lightbulb">The **User** struct</comment>
+
+<action button="Next Step">What should we do next?</action>"#,
+            expect![[r#"
+                <h1>My Walkthrough</h1>
+                <p>Some <em>markdown</em>.</p>
+                <div class="comment-item" data-comment="{&quot;comment&quot;:[&quot;The **User** struct&quot;],&quot;id&quot;:&quot;comment-1&quot;,&quot;locations&quot;:[{&quot;definedAt&quot;:{&quot;content&quot;:&quot;struct User {&quot;,&quot;end&quot;:{&quot;column&quot;:4,&quot;line&quot;:10},&quot;path&quot;:&quot;src/models.rs&quot;,&quot;start&quot;:{&quot;column&quot;:0,&quot;line&quot;:10}},&quot;kind&quot;:&quot;struct&quot;,&quot;name&quot;:&quot;User&quot;}]}" style="cursor: pointer; border: 1px solid var(--vscode-panel-border); border-radius: 4px; padding: 8px; margin: 8px 0; background-color: var(--vscode-editor-background);">
+                    <div style="display: flex; align-items: flex-start;">
+                        <div class="comment-icon" style="margin-right: 8px; font-size: 16px;">💡</div>
+                        <div class="comment-content" style="flex: 1;">
+                            <div class="comment-locations" style="font-weight: 500; color: var(--vscode-textLink-foreground); margin-bottom: 4px; font-family: var(--vscode-editor-font-family); font-size: 0.9em;">src/models.rs:10</div>
+                            <div class="comment-text" style="color: var(--vscode-foreground); font-size: 0.9em;"><p>The <strong>User</strong> struct</p>
+                </div>
+                        </div>
+                    </div>
+                </div>
+                <button class="action-button" data-tell-agent="What should we do next?" style="background-color: var(--vscode-button-background); color: var(--vscode-button-foreground); border: none; padding: 8px 16px; border-radius: 4px; cursor: pointer; margin: 8px 0; font-size: 0.9em;">Next Step</button>
             "#]],
         );
     }
+
+    #[test]
+    fn test_arbitrary_walkthroughs_render_safely() {
+        use crate::html_sanitizer::{arbitrary_text, assert_safe_html};
+        use crate::walkthrough_document::{RENDERED_ATTRIBUTES, RENDERED_TAGS};
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let mut parser = create_test_parser();
+        let mut rng = fastrand::Rng::with_seed(0xface);
+        for _ in 0..300 {
+            let input = arbitrary_text(&mut rng);
+            let html = rt.block_on(parser.parse(&input)).unwrap().to_html();
+            assert_safe_html(&html, RENDERED_TAGS, RENDERED_ATTRIBUTES);
+        }
+    }
+
     #[test]
     fn test_parse_comment_element() {
         let parser = create_test_parser();
@@ -665,15 +762,39 @@ More text"#,
         let element = parser.parse_xml_element(xml).unwrap();
         
         match element {
-            XmlElement::GitDiff { range, exclude_unstaged, exclude_staged } => {
+            XmlElement::GitDiff { range, exclude_unstaged, exclude_staged, settings } => {
                 assert_eq!(range, "HEAD~2..HEAD");
                 assert!(exclude_unstaged);
                 assert!(!exclude_staged);
+                assert_eq!(settings, DiffSettings::default());
             }
             _ => panic!("Expected GitDiff element"),
         }
     }
 
+    #[test]
+    fn test_parse_gitdiff_settings() {
+        use crate::synthetic_pr::IgnoreWhitespace;
+
+        let parser = create_test_parser();
+        let xml = r#"<gitdiff range="HEAD" context-lines="8" ignore-whitespace="change" exclude-untracked="true" paths="server/src/, README.md" />"#;
+
+        let element = parser.parse_xml_element(xml).unwrap();
+
+        match element {
+            XmlElement::GitDiff { settings, .. } => {
+                assert_eq!(settings.context_lines, 8);
+                assert_eq!(settings.ignore_whitespace, IgnoreWhitespace::Change);
+                assert!(!settings.include_untracked);
+                assert_eq!(settings.paths, vec!["server/src/", "README.md"]);
+            }
+            _ => panic!("Expected GitDiff element"),
+        }
+
+        let invalid = r#"<gitdiff range="HEAD" ignore-whitespace="sometimes" />"#;
+        assert!(parser.parse_xml_element(invalid).is_err());
+    }
+
     #[test]
     fn test_parse_action_element() {
         let parser = create_test_parser();
@@ -1,6 +1,10 @@
 use std::fs;
 use tempfile::TempDir;
 
+mod mock_http;
+
+pub use mock_http::{MockHttpServer, RecordedRequest, RunningMockHttpServer};
+
 /// Action-based test repository builder
 pub struct TestRepo {
     actions: Vec<RepoAction>,
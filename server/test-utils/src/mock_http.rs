@@ -0,0 +1,163 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+
+/// A request received by a [`MockHttpServer`]
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// Path including any query string
+    pub path: String,
+    /// Headers with lowercased names
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl RecordedRequest {
+    /// Value of a header (name is matched case-insensitively)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_ascii_lowercase();
+        self.headers
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MockRoute {
+    method: String,
+    path: String,
+    status: u16,
+    body: String,
+}
+
+/// Minimal local HTTP server returning canned responses, for testing HTTP clients offline.
+///
+/// Routes are matched on method and exact path; anything else gets a 404. Every
+/// request is recorded so tests can check what the client sent.
+#[derive(Default)]
+pub struct MockHttpServer {
+    routes: Vec<MockRoute>,
+}
+
+/// Handle to a running [`MockHttpServer`]
+pub struct RunningMockHttpServer {
+    address: SocketAddr,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl MockHttpServer {
+    /// Create a server builder with no routes
+    pub fn new() -> Self {
+        Self { routes: Vec::new() }
+    }
+
+    /// Respond to `method path` with the given status and JSON body
+    pub fn route(mut self, method: &str, path: &str, status: u16, body: &str) -> Self {
+        self.routes.push(MockRoute {
+            method: method.to_string(),
+            path: path.to_string(),
+            status,
+            body: body.to_string(),
+        });
+        self
+    }
+
+    /// Start serving on an ephemeral localhost port in a background thread
+    pub fn start(self) -> RunningMockHttpServer {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind mock HTTP server");
+        let address = listener
+            .local_addr()
+            .expect("Failed to get mock server address");
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                handle_connection(stream, &self.routes, &recorded);
+            }
+        });
+
+        RunningMockHttpServer { address, requests }
+    }
+}
+
+impl RunningMockHttpServer {
+    /// Base URL of the server, e.g. `http://127.0.0.1:12345`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// Requests received so far, oldest first
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    routes: &[MockRoute],
+    requests: &Mutex<Vec<RecordedRequest>>,
+) {
+    let mut reader = BufReader::new(stream.try_clone().expect("Failed to clone stream"));
+
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).is_err() {
+            return;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+
+    let content_length = headers
+        .iter()
+        .find(|(name, _)| name == "content-length")
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; content_length];
+    if reader.read_exact(&mut body).is_err() {
+        return;
+    }
+
+    let route = routes
+        .iter()
+        .find(|route| route.method == method && route.path == path);
+    let (status, response_body) = match route {
+        Some(route) => (route.status, route.body.as_str()),
+        None => (404, r#"{"message":"Not Found"}"#),
+    };
+
+    requests.lock().unwrap().push(RecordedRequest {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).to_string(),
+    });
+
+    let response = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        response_body.len(),
+        response_body
+    );
+    let mut stream = stream;
+    let _ = stream.write_all(response.as_bytes());
+    let _ = stream.flush();
+}
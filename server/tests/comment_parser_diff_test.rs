@@ -1,4 +1,4 @@
-use dialectic_mcp_server::synthetic_pr::{GitService, CommentParser};
+use dialectic_mcp_server::synthetic_pr::{GitService, CommentParser, MarkerStatus};
 use test_utils::TestRepo;
 
 #[test]
@@ -99,7 +99,7 @@ pub fn validate_session(session_id: &str) -> bool {
 }
 
 #[test]
-fn test_removed_lines_ignored() {
+fn test_removed_lines_reported_as_resolved() {
     // Create repo where we remove lines with comments
     let temp_dir = TestRepo::new()
         .overwrite_and_add("test.rs", r#"
@@ -129,16 +129,57 @@ fn new_function() {}
     let file_changes = git_service.generate_diff(base_oid, head_oid).unwrap();
     let comment_threads = comment_parser.parse_file_changes(&file_changes).unwrap();
 
-    // Should only find comments from kept context lines and new added lines
-    // Should NOT find the "will be removed" comment
-    let comment_contents: Vec<&str> = comment_threads.iter()
-        .map(|t| t.content.as_str())
+    // Comments on context lines are pre-existing, comments on added lines introduced,
+    // and the removed comment is reported as resolved (at its line in the old file)
+    let mut statuses: Vec<(&str, u32, Option<MarkerStatus>)> = comment_threads.iter()
+        .map(|t| (t.content.as_str(), t.line_number, t.marker_status))
         .collect();
-    
-    assert!(!comment_contents.iter().any(|c| c.contains("will be removed")), 
-            "Should not find comments from removed lines");
-    assert!(comment_contents.iter().any(|c| c.contains("will stay")), 
-            "Should find comments from context lines");
-    assert!(comment_contents.iter().any(|c| c.contains("added line")), 
-            "Should find comments from added lines");
+    statuses.sort_by_key(|(content, _, _)| *content);
+
+    assert_eq!(statuses, vec![
+        ("New comment on added line", 5, Some(MarkerStatus::Introduced)),
+        ("This comment will be removed", 2, Some(MarkerStatus::Resolved)),
+        ("This comment will stay", 2, Some(MarkerStatus::PreExisting)),
+    ]);
+}
+
+#[test]
+fn test_moved_markers_are_pre_existing() {
+    let temp_dir = TestRepo::new()
+        .overwrite_and_add("jobs.py", r#"
+# TODO: Retry failed jobs
+def run(job):
+    job.start()
+
+def stop(job):
+    # FIXME: Leaks the worker thread
+    job.cancel()
+"#)
+        .commit("Initial jobs")
+        .overwrite_and_add("jobs.py", r#"
+def run(job):
+    job.start()
+
+def stop(job):
+    job.cancel()
+    job.join()
+
+# TODO: Retry failed jobs
+"#)
+        .commit("Join the worker thread")
+        .create();
+
+    let repo_path = temp_dir.path().to_str().unwrap();
+    let git_service = GitService::new(repo_path).unwrap();
+    let (base_oid, head_oid) = git_service.parse_commit_range("HEAD~1..HEAD").unwrap();
+    let file_changes = git_service.generate_diff(base_oid, head_oid).unwrap();
+    let comment_threads = CommentParser::new().parse_file_changes(&file_changes).unwrap();
+
+    // The TODO only moved; the FIXME was fixed
+    let statuses: Vec<(&str, Option<MarkerStatus>)> = comment_threads.iter()
+        .map(|t| (t.content.as_str(), t.marker_status))
+        .collect();
+    assert_eq!(statuses.len(), 2, "{:?}", statuses);
+    assert!(statuses.contains(&("Retry failed jobs", Some(MarkerStatus::PreExisting))));
+    assert!(statuses.contains(&("Leaks the worker thread", Some(MarkerStatus::Resolved))));
 }
@@ -3,7 +3,7 @@ use dialectic_mcp_server::synthetic_pr::*;
 use rmcp::ServerHandler;
 use serde_json::json;
 use tempfile::TempDir;
-use test_utils::TestRepo;
+use test_utils::{MockHttpServer, TestRepo};
 
 /// Create the standard test repository with AI insight comments
 fn setup_test_git_repo() -> TempDir {
@@ -96,6 +96,7 @@ async fn test_synthetic_pr_full_workflow_with_real_git() {
             "automated": true
         }),
         repo_path: Some(repo_path.to_string()),
+        ..Default::default()
     };
 
     // This should now work without changing directories
@@ -184,7 +185,7 @@ async fn test_synthetic_pr_full_workflow_with_real_git() {
     }
 
     // Test that review state was persisted
-    let status_result = get_review_status(Some(repo_path)).await;
+    let status_result = get_review_status(Some(repo_path), None).await;
 
     assert!(
         status_result.is_ok(),
@@ -203,6 +204,23 @@ async fn test_synthetic_pr_full_workflow_with_real_git() {
         "Should have active review"
     );
 
+    // A fresh review: nothing answered or reworked yet
+    let metrics = status.metrics.unwrap();
+    assert_eq!(Some(metrics.files.len()), status.files_changed);
+    assert_eq!(
+        metrics.total_additions,
+        metrics.files.iter().map(|f| f.additions).sum::<u32>()
+    );
+    assert!(metrics.threads_by_type["Question"] >= 1);
+    assert_eq!(
+        metrics.threads_by_type.values().sum::<usize>(),
+        status.comment_threads.unwrap()
+    );
+    assert_eq!(metrics.answered_threads, 0);
+    assert_eq!(metrics.rounds, 1);
+    assert_eq!(metrics.churn, 0);
+    assert!(metrics.seconds_in_status.contains_key("Pending"));
+
     // Keep temp_dir alive until the end
     drop(temp_dir);
 }
@@ -377,6 +395,7 @@ export function sanitizeInput(input: string): string {
         title: "Multi-language AI comments test".to_string(),
         description: json!({"test": "custom_spec"}),
         repo_path: Some(repo_path.to_string()),
+        ..Default::default()
     };
 
     let result = harvest_review_data(review_params).await;
@@ -487,6 +506,7 @@ pub fn experimental_feature() -> bool {
         title: "Work in progress with unstaged changes".to_string(),
         description: json!({"test": "unstaged_changes"}),
         repo_path: Some(repo_path.to_string()),
+        ..Default::default()
     };
 
     let result = harvest_review_data(review_params).await;
@@ -546,6 +566,978 @@ pub fn experimental_feature() -> bool {
     drop(temp_dir);
 }
 
+#[tokio::test]
+async fn test_diff_settings_focus_review() {
+    let temp_dir = TestRepo::new()
+        .overwrite_and_add(
+            "server/src/lib.rs",
+            "pub fn one() -> u32 {\n    1\n}\n\npub fn two() -> u32 {\n    2\n}\n",
+        )
+        .overwrite_and_add("docs/notes.md", "# Notes\n")
+        .commit("Initial commit")
+        .overwrite(
+            "server/src/lib.rs",
+            "pub fn one() -> u32 {\n    // 💡 One is the loneliest number\n    1\n}\n\npub fn two() -> u32 {\n      2\n}\n",
+        )
+        .overwrite("docs/notes.md", "# Notes\n\nMore notes.\n")
+        .overwrite("scratch.txt", "// TODO: scratch file that should not be reviewed\n")
+        .create();
+
+    let repo_path = temp_dir.path().to_str().unwrap();
+
+    let review_params = RequestReviewParams {
+        commit_range: "HEAD".to_string(),
+        title: "Focused review".to_string(),
+        description: json!({}),
+        repo_path: Some(repo_path.to_string()),
+        diff_settings: DiffSettings {
+            context_lines: 0,
+            ignore_whitespace: IgnoreWhitespace::Change,
+            include_untracked: false,
+            paths: vec!["server/src/".to_string()],
+        },
+        feedback_mode: FeedbackMode::Block,
+        group_files: false,
+    };
+
+    let response = harvest_review_data(review_params).await.unwrap();
+
+    let paths: Vec<&str> = response
+        .files_changed
+        .iter()
+        .map(|f| f.path.as_str())
+        .collect();
+    assert_eq!(paths, vec!["server/src/lib.rs"]);
+
+    // The indentation-only change to `two` is ignored, and no context lines are emitted
+    let lib = &response.files_changed[0];
+    assert_eq!(lib.additions, 1);
+    assert_eq!(lib.deletions, 0);
+    assert!(
+        lib.hunks
+            .iter()
+            .flat_map(|h| &h.lines)
+            .all(|l| !matches!(l.line_type, DiffLineType::Context))
+    );
+
+    assert_eq!(response.comment_threads.len(), 1);
+    assert!(
+        response
+            .comment_threads
+            .iter()
+            .all(|t| t.file_path == "server/src/lib.rs")
+    );
+
+    drop(temp_dir);
+}
+
+#[tokio::test]
+async fn test_multi_commit_review_navigation() {
+    let temp_dir = TestRepo::new()
+        .overwrite_and_add("src/lib.rs", "pub mod auth;\n")
+        .commit("Initial commit")
+        .overwrite_and_add(
+            "src/auth.rs",
+            "// 💡 Tokens are compared in constant time\npub fn check() {}\n",
+        )
+        .commit("Add auth module")
+        .overwrite_and_add(
+            "src/payment.rs",
+            "// ❓ Should refunds be supported?\npub fn pay() {}\n",
+        )
+        .commit("Add payment module")
+        .append("src/lib.rs", "// TODO: wire up payment module\n")
+        .create();
+
+    let repo_path = temp_dir.path().to_str().unwrap();
+
+    let review_params = RequestReviewParams {
+        commit_range: "HEAD~2".to_string(),
+        title: "Auth and payments".to_string(),
+        description: json!({}),
+        repo_path: Some(repo_path.to_string()),
+        ..Default::default()
+    };
+
+    let response = harvest_review_data(review_params).await.unwrap();
+
+    // Two real commits, oldest first, followed by the uncommitted changes
+    let messages: Vec<&str> = response
+        .commits
+        .iter()
+        .map(|c| c.message.as_str())
+        .collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Add auth module",
+            "Add payment module",
+            "Uncommitted changes"
+        ]
+    );
+    assert!(response.commits[0].author.contains("Test User"));
+    assert!(response.commits[2].sha.is_none());
+
+    let commit_files = |i: usize| -> Vec<&str> {
+        response.commits[i]
+            .files_changed
+            .iter()
+            .map(|f| f.path.as_str())
+            .collect()
+    };
+    assert_eq!(commit_files(0), vec!["src/auth.rs"]);
+    assert_eq!(commit_files(1), vec!["src/payment.rs"]);
+    assert_eq!(commit_files(2), vec!["src/lib.rs"]);
+
+    // The cumulative diff still covers everything
+    assert_eq!(response.files_changed.len(), 3);
+
+    // Each thread is attributed to the commit that introduced it
+    let sha_of = |path: &str| {
+        response
+            .comment_threads
+            .iter()
+            .find(|t| t.file_path == path)
+            .unwrap()
+            .commit_sha
+            .clone()
+    };
+    assert_eq!(sha_of("src/auth.rs"), response.commits[0].sha);
+    assert_eq!(sha_of("src/payment.rs"), response.commits[1].sha);
+    assert_eq!(sha_of("src/lib.rs"), None);
+
+    drop(temp_dir);
+}
+
+#[tokio::test]
+async fn test_concurrent_reviews_keyed_by_id() {
+    let temp_dir = setup_test_git_repo();
+    let repo_path = temp_dir.path().to_str().unwrap();
+
+    let request = |commit_range: &str, title: &str| RequestReviewParams {
+        commit_range: commit_range.to_string(),
+        title: title.to_string(),
+        description: json!({}),
+        repo_path: Some(repo_path.to_string()),
+        ..Default::default()
+    };
+
+    let first = harvest_review_data(request("HEAD~1..HEAD", "Insights"))
+        .await
+        .unwrap();
+    let second = harvest_review_data(request("HEAD~1", "Everything since initial"))
+        .await
+        .unwrap();
+    assert_ne!(first.review_id, second.review_id);
+
+    // Updating the first review must not touch the second
+    let thread_id = first.comment_threads[0].thread_id.clone();
+    let updated = update_review(UpdateReviewParams {
+        review_id: first.review_id.clone(),
+        action: UpdateReviewAction::AddComment {
+            comment: ReviewComment {
+                thread_id: Some(thread_id.clone()),
+                file_path: None,
+                line_number: None,
+                body: "Agreed, JWT validation is next".to_string(),
+            },
+        },
+        repo_path: Some(repo_path.to_string()),
+        feedback_mode: FeedbackMode::Block,
+    })
+    .await
+    .unwrap();
+    assert_eq!(updated.review.review_id, first.review_id);
+
+    // User feedback is recorded in the review it names
+    let feedback = UserFeedback {
+        review_id: first.review_id.clone(),
+        feedback: FeedbackData::Comment {
+            thread_id: Some(thread_id.clone()),
+            file_path: None,
+            line_number: None,
+            comment_text: "Thanks!".to_string(),
+            context_lines: None,
+        },
+    };
+    let recorded = record_user_feedback(Some(repo_path), &feedback).unwrap();
+    assert_eq!(recorded, Some(thread_id.clone()));
+
+    update_review(UpdateReviewParams {
+        review_id: second.review_id.clone(),
+        action: UpdateReviewAction::Approve,
+        repo_path: Some(repo_path.to_string()),
+        feedback_mode: FeedbackMode::Block,
+    })
+    .await
+    .unwrap();
+
+    let store = ReviewStore::for_repo(Some(repo_path)).unwrap();
+    let first_state = store.load(&first.review_id).unwrap();
+    let thread = first_state
+        .comment_threads
+        .iter()
+        .find(|t| t.thread_id == thread_id)
+        .unwrap();
+    let authors: Vec<_> = thread.responses.iter().map(|r| r.author.as_str()).collect();
+    assert_eq!(authors, vec![AGENT_AUTHOR, USER_AUTHOR]);
+    assert!(matches!(first_state.status, ReviewStatus::Pending));
+
+    let second_state = store.load(&second.review_id).unwrap();
+    assert!(matches!(second_state.status, ReviewStatus::Approved));
+    assert!(
+        second_state
+            .comment_threads
+            .iter()
+            .all(|t| t.responses.is_empty())
+    );
+
+    // Status defaults to the most recently updated review, but can be asked for by id
+    let latest = get_review_status(Some(repo_path), None).await.unwrap();
+    assert_eq!(latest.review_id, Some(second.review_id.clone()));
+    let first_status = get_review_status(Some(repo_path), Some(&first.review_id))
+        .await
+        .unwrap();
+    assert_eq!(first_status.title, Some("Insights".to_string()));
+    assert_eq!(first_status.status, "Pending");
+
+    let reviews = list_reviews(Some(repo_path)).await.unwrap();
+    let ids: Vec<_> = reviews.iter().map(|r| r.review_id.clone()).collect();
+    assert_eq!(ids, vec![second.review_id, first.review_id]);
+}
+
+#[tokio::test]
+async fn test_review_state_stays_out_of_working_tree() {
+    let temp_dir = TestRepo::new()
+        .overwrite_and_add("src/lib.rs", "pub fn one() {}\n")
+        .commit("Initial commit")
+        .overwrite("src/lib.rs", "pub fn one() {}\npub fn two() {}\n")
+        .create();
+    let repo_path = temp_dir.path().to_str().unwrap();
+
+    // A review left behind in the working tree by an older version
+    let legacy = json!({
+        "review_id": "legacy-review",
+        "title": "Legacy review",
+        "description": {},
+        "commit_range": "HEAD",
+        "status": "Pending",
+        "files_changed": [],
+        "comment_threads": [],
+        "created_at": "2025-01-01T00:00:00Z",
+        "updated_at": "2025-01-01T00:00:00Z"
+    });
+    let legacy_path = temp_dir.path().join(LEGACY_REVIEW_FILE);
+    std::fs::write(&legacy_path, legacy.to_string()).unwrap();
+
+    let review_params = || RequestReviewParams {
+        commit_range: "HEAD".to_string(),
+        title: "Add two".to_string(),
+        description: json!({}),
+        repo_path: Some(repo_path.to_string()),
+        ..Default::default()
+    };
+
+    // Untracked files are included by default, yet neither the legacy file nor the
+    // store show up in this review or the next one
+    for _ in 0..2 {
+        let response = harvest_review_data(review_params()).await.unwrap();
+        let paths: Vec<&str> = response
+            .files_changed
+            .iter()
+            .map(|f| f.path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["src/lib.rs"]);
+    }
+
+    assert!(
+        !legacy_path.exists(),
+        "legacy review file should be migrated"
+    );
+    let reviews = list_reviews(Some(repo_path)).await.unwrap();
+    assert_eq!(reviews.len(), 3);
+    assert!(reviews.iter().any(|r| r.review_id == "legacy-review"));
+
+    let repo = git2::Repository::open(repo_path).unwrap();
+    let untracked = repo
+        .statuses(None)
+        .unwrap()
+        .iter()
+        .filter(|entry| entry.status().is_wt_new())
+        .count();
+    assert_eq!(
+        untracked, 0,
+        "reviews must not add files to the working tree"
+    );
+}
+
+#[tokio::test]
+async fn test_comment_threads_follow_code_between_rounds() {
+    let temp_dir = TestRepo::new()
+        .overwrite_and_add("src/cache.rs", "pub fn load() {}\n")
+        .commit("Initial commit")
+        .overwrite(
+            "src/cache.rs",
+            "pub fn load() {\n    // ❓ Should this be cached?\n    fetch()\n}\n",
+        )
+        .create();
+    let repo_path = temp_dir.path().to_str().unwrap();
+    let file = temp_dir.path().join("src/cache.rs");
+
+    let review = harvest_review_data(RequestReviewParams {
+        commit_range: "HEAD".to_string(),
+        title: "Caching".to_string(),
+        description: json!({}),
+        repo_path: Some(repo_path.to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    assert_eq!(review.comment_threads.len(), 1);
+    let question = review.comment_threads[0].clone();
+    assert_eq!(question.line_number, 2);
+
+    let update = |action| UpdateReviewParams {
+        review_id: review.review_id.clone(),
+        action,
+        repo_path: Some(repo_path.to_string()),
+        feedback_mode: FeedbackMode::Block,
+    };
+
+    update_review(update(UpdateReviewAction::AddComment {
+        comment: ReviewComment {
+            thread_id: Some(question.thread_id.clone()),
+            file_path: None,
+            line_number: None,
+            body: "Yes, behind an LRU".to_string(),
+        },
+    }))
+    .await
+    .unwrap();
+
+    // The agent adds code above the question and a new insight below it
+    std::fs::write(
+        &file,
+        "use lru::LruCache;\n\npub fn load() {\n    // ❓ Should this be cached?\n    fetch()\n    // 💡 Cached in an LRU\n}\n",
+    )
+    .unwrap();
+
+    let updated = update_review(update(UpdateReviewAction::WaitForFeedback))
+        .await
+        .unwrap();
+    let threads = &updated.review.comment_threads;
+    assert_eq!(threads.len(), 2);
+
+    let moved = threads
+        .iter()
+        .find(|t| t.thread_id == question.thread_id)
+        .expect("thread keeps its id");
+    assert_eq!(moved.line_number, 4);
+    assert!(!moved.outdated);
+    assert_eq!(moved.responses.len(), 1);
+
+    let insight = threads
+        .iter()
+        .find(|t| t.thread_id != question.thread_id)
+        .unwrap();
+    assert!(matches!(insight.comment_type, CommentType::Explanation));
+    assert_eq!(insight.line_number, 6);
+
+    // Removing the question's line leaves its thread outdated, with the discussion intact
+    std::fs::write(
+        &file,
+        "use lru::LruCache;\n\npub fn load() {\n    fetch()\n    // 💡 Cached in an LRU\n}\n",
+    )
+    .unwrap();
+
+    let updated = update_review(update(UpdateReviewAction::WaitForFeedback))
+        .await
+        .unwrap();
+    let threads = &updated.review.comment_threads;
+    let lost = threads
+        .iter()
+        .find(|t| t.thread_id == question.thread_id)
+        .unwrap();
+    assert!(lost.outdated);
+    assert_eq!(lost.line_number, 4);
+    assert_eq!(lost.responses[0].content, "Yes, behind an LRU");
+
+    let insight = threads
+        .iter()
+        .find(|t| t.thread_id != question.thread_id)
+        .unwrap();
+    assert!(!insight.outdated);
+    assert_eq!(insight.line_number, 5);
+}
+
+#[tokio::test]
+async fn test_review_rounds_show_changes_since_last_round() {
+    let temp_dir = TestRepo::new()
+        .overwrite_and_add("src/lib.rs", "pub fn one() {}\n")
+        .commit("Initial commit")
+        .overwrite("src/lib.rs", "pub fn one() {}\npub fn two() {}\n")
+        .create();
+    let repo_path = temp_dir.path().to_str().unwrap();
+
+    let review = harvest_review_data(RequestReviewParams {
+        commit_range: "HEAD".to_string(),
+        title: "Add two".to_string(),
+        description: json!({}),
+        repo_path: Some(repo_path.to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    assert_eq!(review.rounds.len(), 1);
+    assert!(review.rounds[0].interdiff.is_empty());
+
+    let update = |action| UpdateReviewParams {
+        review_id: review.review_id.clone(),
+        action,
+        repo_path: Some(repo_path.to_string()),
+        feedback_mode: FeedbackMode::Block,
+    };
+
+    // Nothing changed: still the first round
+    let updated = update_review(update(UpdateReviewAction::RequestChanges))
+        .await
+        .unwrap();
+    assert_eq!(updated.review.rounds.len(), 1);
+
+    // The agent addresses the feedback, touching an existing file and adding a new one
+    std::fs::write(
+        temp_dir.path().join("src/lib.rs"),
+        "pub fn one() {}\npub fn two() -> u32 {\n    2\n}\n",
+    )
+    .unwrap();
+    std::fs::write(temp_dir.path().join("src/three.rs"), "pub fn three() {}\n").unwrap();
+
+    let updated = update_review(update(UpdateReviewAction::Approve))
+        .await
+        .unwrap();
+    let rounds = &updated.review.rounds;
+    assert_eq!(rounds.len(), 2);
+    assert_eq!(rounds[1].number, 2);
+    assert_ne!(rounds[0].tree_oid, rounds[1].tree_oid);
+
+    // The interdiff only contains the response to the review, not the original change
+    let interdiff = &rounds[1].interdiff;
+    let paths: Vec<&str> = interdiff.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, vec!["src/lib.rs", "src/three.rs"]);
+    let lib_lines: Vec<(DiffLineType, &str)> = interdiff[0]
+        .hunks
+        .iter()
+        .flat_map(|hunk| &hunk.lines)
+        .filter(|line| !matches!(line.line_type, DiffLineType::Context))
+        .map(|line| (line.line_type.clone(), line.content.trim_end()))
+        .collect();
+    assert_eq!(
+        lib_lines,
+        vec![
+            (DiffLineType::Removed, "pub fn two() {}"),
+            (DiffLineType::Added, "pub fn two() -> u32 {"),
+            (DiffLineType::Added, "    2"),
+            (DiffLineType::Added, "}"),
+        ]
+    );
+    assert!(matches!(interdiff[1].status, ChangeStatus::Added));
+
+    // The cumulative diff still covers everything since the base
+    assert_eq!(updated.review.files_changed.len(), 2);
+
+    // Round snapshots are pinned so garbage collection keeps them
+    let repo = git2::Repository::open(repo_path).unwrap();
+    for round in rounds {
+        let pinned = repo
+            .find_reference(&format!(
+                "refs/socratic-shell/reviews/{}/{}",
+                review.review_id, round.number
+            ))
+            .unwrap();
+        assert_eq!(pinned.target().unwrap().to_string(), round.tree_oid);
+    }
+
+    // A snapshot that is gone only costs the interdiff, not the update
+    let store = ReviewStore::for_repo(Some(repo_path)).unwrap();
+    let mut stored = store.load(&review.review_id).unwrap();
+    stored.rounds[1].tree_oid = "0123456789012345678901234567890123456789".to_string();
+    store.save(&stored).unwrap();
+    std::fs::write(
+        temp_dir.path().join("src/three.rs"),
+        "pub fn three() -> u32 {\n    3\n}\n",
+    )
+    .unwrap();
+    let updated = update_review(update(UpdateReviewAction::RequestChanges))
+        .await
+        .unwrap();
+    assert_eq!(updated.review.rounds.len(), 3);
+    assert!(updated.review.rounds[2].interdiff.is_empty());
+}
+
+#[tokio::test]
+async fn test_review_status_transitions_and_history() {
+    let temp_dir = setup_test_git_repo();
+    let repo_path = temp_dir.path().to_str().unwrap();
+
+    let review = harvest_review_data(RequestReviewParams {
+        commit_range: "HEAD~1..HEAD".to_string(),
+        title: "Status history".to_string(),
+        description: json!({}),
+        repo_path: Some(repo_path.to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let update = |action: UpdateReviewAction| {
+        update_review(UpdateReviewParams {
+            review_id: review.review_id.clone(),
+            action,
+            repo_path: Some(repo_path.to_string()),
+            feedback_mode: FeedbackMode::Block,
+        })
+    };
+
+    // The user asks for changes, the agent addresses them, then the user checkpoints
+    let complete = |completion_action: CompletionAction| UserFeedback {
+        review_id: review.review_id.clone(),
+        feedback: FeedbackData::CompleteReview {
+            completion_action,
+            additional_notes: None,
+        },
+    };
+    record_user_feedback(Some(repo_path), &complete(CompletionAction::RequestChanges)).unwrap();
+    update(UpdateReviewAction::Approve).await.unwrap();
+    update(UpdateReviewAction::RequestChanges).await.unwrap();
+    record_user_feedback(Some(repo_path), &complete(CompletionAction::Checkpoint)).unwrap();
+    update(UpdateReviewAction::Merge).await.unwrap();
+
+    // A merged review is final
+    let error = update(UpdateReviewAction::Approve).await.unwrap_err();
+    assert!(error.to_string().contains("Merged"), "{}", error);
+
+    let status = get_review_status(Some(repo_path), Some(&review.review_id))
+        .await
+        .unwrap();
+    assert_eq!(status.status, "Merged");
+    let history: Vec<_> = status
+        .history
+        .iter()
+        .map(|e| (e.kind.clone(), e.actor.as_str()))
+        .collect();
+    assert_eq!(
+        history,
+        vec![
+            (ReviewEventKind::Created, AGENT_AUTHOR),
+            (ReviewEventKind::ChangesRequested, USER_AUTHOR),
+            (ReviewEventKind::Approved, AGENT_AUTHOR),
+            (ReviewEventKind::ChangesRequested, AGENT_AUTHOR),
+            (ReviewEventKind::Approved, USER_AUTHOR),
+            (ReviewEventKind::Merged, AGENT_AUTHOR),
+        ]
+    );
+    assert!(
+        status
+            .history
+            .windows(2)
+            .all(|pair| pair[0].timestamp <= pair[1].timestamp)
+    );
+}
+
+#[tokio::test]
+async fn test_export_review_formats() {
+    let temp_dir = setup_test_git_repo();
+    let repo_path = temp_dir.path().to_str().unwrap();
+
+    let review = harvest_review_data(RequestReviewParams {
+        commit_range: "HEAD~1..HEAD".to_string(),
+        title: "Export me".to_string(),
+        description: json!("Adds insight comments"),
+        repo_path: Some(repo_path.to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let export = |format: ExportFormat| {
+        export_review(ExportReviewParams {
+            review_id: Some(review.review_id.clone()),
+            format,
+            repo_path: Some(repo_path.to_string()),
+        })
+    };
+
+    let mbox = export(ExportFormat::Mbox).await.unwrap();
+    assert!(mbox.starts_with("From "), "{}", mbox);
+    assert!(mbox.contains("diff --git a/src/auth.rs b/src/auth.rs"));
+
+    // Every thread shows up in the report
+    let markdown = export(ExportFormat::Markdown).await.unwrap();
+    assert!(markdown.starts_with("# Export me\n"));
+    for thread in &review.comment_threads {
+        assert!(markdown.contains(&thread.content), "{}", markdown);
+    }
+
+    let sarif: serde_json::Value =
+        serde_json::from_str(&export(ExportFormat::Sarif).await.unwrap()).unwrap();
+    assert_eq!(sarif["version"], "2.1.0");
+
+    // The CLI subcommand exports the most recent review
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_dialectic-mcp-server"))
+        .args(["export", "--format", "markdown", "--repo-path", repo_path])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), markdown);
+}
+
+#[tokio::test]
+async fn test_exported_mbox_applies_with_git_am() {
+    let temp_dir = TestRepo::new()
+        .overwrite_and_add("src/auth.rs", "fn check() -> bool {\n    true\n}")
+        .overwrite_and_add("src/legacy.rs", "// Superseded by auth.rs\n")
+        .overwrite_and_add("run.sh", "cargo test\n")
+        .commit("Initial commit")
+        .create();
+    let repo_path = temp_dir.path().to_str().unwrap();
+    let git = |args: &[&str]| {
+        let output = std::process::Command::new("git")
+            .args(args)
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?}: {:?}", args, output);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    // A file losing its missing final newline, trailing whitespace, a new file,
+    // a deleted file, a mode change and a commit message line starting with "From "
+    std::fs::write(
+        temp_dir.path().join("src/auth.rs"),
+        "fn check() -> bool {   \n    false\n}\n",
+    )
+    .unwrap();
+    std::fs::write(temp_dir.path().join("src/token.rs"), "pub struct Token;").unwrap();
+    git(&["rm", "-q", "src/legacy.rs"]);
+    git(&["add", "src/auth.rs", "src/token.rs"]);
+    git(&["update-index", "--chmod=+x", "run.sh"]);
+    git(&["checkout", "run.sh"]);
+    git(&[
+        "commit",
+        "-q",
+        "-m",
+        "Reject tokens by default",
+        "-m",
+        "From now on, callers must validate tokens.",
+    ]);
+    std::fs::write(temp_dir.path().join("src/token.rs"), "pub struct Token(String);\n").unwrap();
+    git(&["commit", "-q", "-am", "Keep the token value"]);
+
+    let review = harvest_review_data(RequestReviewParams {
+        commit_range: "HEAD~2..HEAD".to_string(),
+        title: "Harden auth".to_string(),
+        description: json!(""),
+        repo_path: Some(repo_path.to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let mbox = export_review(ExportReviewParams {
+        review_id: Some(review.review_id.clone()),
+        format: ExportFormat::Mbox,
+        repo_path: Some(repo_path.to_string()),
+    })
+    .await
+    .unwrap();
+    assert!(mbox.contains("\n>From now on"), "{}", mbox);
+
+    // Replaying the series on the base commit reproduces the reviewed tree
+    let reviewed_tree = git(&["rev-parse", "HEAD^{tree}"]);
+    git(&["checkout", "-q", "--detach", "HEAD~2"]);
+    let mbox_path = temp_dir.path().join(".git/review.mbox");
+    std::fs::write(&mbox_path, &mbox).unwrap();
+    git(&["am", "-q", "--patch-format=mboxrd", mbox_path.to_str().unwrap()]);
+    assert_eq!(git(&["rev-parse", "HEAD^{tree}"]), reviewed_tree);
+    assert_eq!(
+        git(&["log", "-1", "--skip=1", "--format=%B"]).trim(),
+        "Reject tokens by default\n\nFrom now on, callers must validate tokens."
+    );
+}
+
+/// Query for open pull requests from octo's `feature` branch into `main`
+const OPEN_FEATURE_PULLS: &str = "/repos/octo/app/pulls?head=octo%3Afeature&base=main&state=open";
+const OPEN_MISSING_PULLS: &str = "/repos/octo/app/pulls?head=octo%3Amissing&base=main&state=open";
+
+#[tokio::test]
+async fn test_publish_review_to_github() {
+    let temp_dir = setup_test_git_repo();
+    let repo_path = temp_dir.path().to_str().unwrap();
+
+    let review = harvest_review_data(RequestReviewParams {
+        commit_range: "HEAD~1..HEAD".to_string(),
+        title: "Publish me".to_string(),
+        description: json!("Adds insight comments"),
+        repo_path: Some(repo_path.to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let server = MockHttpServer::new()
+        .route("GET", OPEN_FEATURE_PULLS, 200, "[]")
+        .route(
+            "POST",
+            "/repos/octo/app/pulls",
+            201,
+            r#"{"number": 7, "html_url": "https://github.com/octo/app/pull/7", "head": {"sha": "abc123"}}"#,
+        )
+        .route("POST", "/repos/octo/app/pulls/7/reviews", 200, r#"{"id": 1}"#)
+        .start();
+    let publisher = GitHubPublisher::new("octo", "app", "secret").with_api_url(&server.url());
+
+    let published = publish_review(
+        &publisher,
+        Some(repo_path),
+        Some(&review.review_id),
+        PullRequestBranches {
+            base: "main".to_string(),
+            head: "feature".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(published.number, 7);
+    assert_eq!(published.url, "https://github.com/octo/app/pull/7");
+    assert_eq!(published.comments_published, review.comment_threads.len());
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[0].header("authorization"), Some("Bearer secret"));
+    let created: serde_json::Value = serde_json::from_str(&requests[1].body).unwrap();
+    assert_eq!(created["title"], "Publish me");
+    assert_eq!(created["base"], "main");
+    assert_eq!(created["head"], "feature");
+
+    // Every thread becomes a line comment on the pull request's head commit
+    let comments: serde_json::Value = serde_json::from_str(&requests[2].body).unwrap();
+    assert_eq!(comments["commit_id"], "abc123");
+    assert_eq!(comments["event"], "COMMENT");
+    for (comment, thread) in comments["comments"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .zip(&review.comment_threads)
+    {
+        assert_eq!(comment["path"], thread.file_path.as_str());
+        assert_eq!(comment["line"], thread.line_number);
+        assert!(comment["body"].as_str().unwrap().contains(&thread.content));
+    }
+}
+
+#[tokio::test]
+async fn test_publish_review_reports_forge_errors() {
+    let server = MockHttpServer::new()
+        .route("GET", OPEN_MISSING_PULLS, 200, "[]")
+        .route(
+            "POST",
+            "/repos/octo/app/pulls",
+            422,
+            r#"{"message": "Validation Failed"}"#,
+        )
+        .start();
+    let publisher = GitHubPublisher::new("octo", "app", "secret").with_api_url(&server.url());
+
+    let pull_request = ForgePullRequest {
+        title: "Broken".to_string(),
+        body: String::new(),
+        branches: PullRequestBranches {
+            base: "main".to_string(),
+            head: "missing".to_string(),
+        },
+        comments: vec![],
+    };
+    let error = publisher.publish(&pull_request).await.unwrap_err();
+    assert!(
+        matches!(&error, ForgeError::Api { status: 422, message } if message == "Validation Failed"),
+        "{:?}",
+        error
+    );
+}
+
+#[tokio::test]
+async fn test_publish_review_reuses_pull_request_after_comment_failure() {
+    let pull_request = ForgePullRequest {
+        title: "Retry me".to_string(),
+        body: String::new(),
+        branches: PullRequestBranches {
+            base: "main".to_string(),
+            head: "feature".to_string(),
+        },
+        comments: vec![ForgeComment {
+            path: "src/lib.rs".to_string(),
+            line: 1,
+            start_line: None,
+            body: "Why?".to_string(),
+        }],
+    };
+    let created = r#"{"number": 7, "html_url": "https://github.com/octo/app/pull/7", "head": {"sha": "abc123"}}"#;
+
+    // The pull request is opened but the review is rejected: the error still names it
+    let server = MockHttpServer::new()
+        .route("GET", OPEN_FEATURE_PULLS, 200, "[]")
+        .route("POST", "/repos/octo/app/pulls", 201, created)
+        .route(
+            "POST",
+            "/repos/octo/app/pulls/7/reviews",
+            422,
+            r#"{"message": "Line could not be resolved"}"#,
+        )
+        .start();
+    let publisher = GitHubPublisher::new("octo", "app", "secret").with_api_url(&server.url());
+    let error = publisher.publish(&pull_request).await.unwrap_err();
+    match &error {
+        ForgeError::Comments {
+            pull_request,
+            source,
+        } => {
+            assert_eq!(pull_request.number, 7);
+            assert_eq!(pull_request.url, "https://github.com/octo/app/pull/7");
+            assert!(matches!(**source, ForgeError::Api { status: 422, .. }));
+        }
+        error => panic!("unexpected error: {:?}", error),
+    }
+    assert!(error.to_string().contains("https://github.com/octo/app/pull/7"));
+
+    // Publishing again attaches the comments to the open pull request instead of creating one
+    let server = MockHttpServer::new()
+        .route("GET", OPEN_FEATURE_PULLS, 200, &format!("[{}]", created))
+        .route("POST", "/repos/octo/app/pulls/7/reviews", 200, r#"{"id": 1}"#)
+        .start();
+    let publisher = GitHubPublisher::new("octo", "app", "secret").with_api_url(&server.url());
+    let published = publisher.publish(&pull_request).await.unwrap();
+    assert_eq!(published.number, 7);
+    assert_eq!(published.comments_published, 1);
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1].path, "/repos/octo/app/pulls/7/reviews");
+}
+
+#[tokio::test]
+async fn test_import_github_pull_request() {
+    let temp_dir = setup_test_git_repo();
+    let repo_path = temp_dir.path().to_str().unwrap();
+    let repo = git2::Repository::open(repo_path).unwrap();
+    let sha = |spec: &str| repo.revparse_single(spec).unwrap().id().to_string();
+
+    // Point the recorded pull request at the commits of the test repository; the
+    // rewritten files live under .git so they stay out of the reviewed diff
+    let scratch = temp_dir.path().join(".git");
+    let mut pull_request: serde_json::Value =
+        serde_json::from_str(include_str!("fixtures/github_pull_request.json")).unwrap();
+    let unfetched_path = scratch.join("unfetched_pull.json");
+    std::fs::write(&unfetched_path, pull_request.to_string()).unwrap();
+    pull_request["base"]["sha"] = json!(sha("HEAD~1"));
+    pull_request["head"]["sha"] = json!(sha("HEAD"));
+    let pull_request_path = scratch.join("pull.json");
+    std::fs::write(&pull_request_path, pull_request.to_string()).unwrap();
+    let comments_path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/github_review_comments.json"
+    );
+
+    let review = import_review(ImportReviewParams {
+        pull_request_path: pull_request_path.to_str().unwrap().to_string(),
+        comments_path: comments_path.to_string(),
+        repo_path: Some(repo_path.to_string()),
+        feedback_mode: FeedbackMode::Block,
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(review.title, "Add payment module (#42)");
+    assert_eq!(
+        review.commit_range,
+        format!("{}..{}", sha("HEAD~1"), sha("HEAD"))
+    );
+    assert_eq!(review.files_changed.len(), 2);
+
+    // Insight comments from the diff are kept next to the reviewers' threads
+    let imported: Vec<_> = review
+        .comment_threads
+        .iter()
+        .filter(|t| t.thread_id.starts_with("github-"))
+        .collect();
+    assert_eq!(imported.len(), 3);
+    assert!(review.comment_threads.len() > imported.len());
+    let thread = imported
+        .iter()
+        .find(|t| t.thread_id == "github-101")
+        .unwrap();
+    assert_eq!(thread.line_number, 10);
+    assert!(thread.anchor.is_some());
+
+    // The agent answers the human reviewer like any other thread
+    let updated = update_review(UpdateReviewParams {
+        review_id: review.review_id.clone(),
+        action: UpdateReviewAction::AddComment {
+            comment: ReviewComment {
+                thread_id: Some("github-101".to_string()),
+                file_path: None,
+                line_number: None,
+                body: "Moved the token to configuration".to_string(),
+            },
+        },
+        repo_path: Some(repo_path.to_string()),
+        feedback_mode: FeedbackMode::Block,
+    })
+    .await
+    .unwrap();
+    let thread = updated
+        .review
+        .comment_threads
+        .iter()
+        .find(|t| t.thread_id == "github-101")
+        .unwrap();
+    let authors: Vec<_> = thread.responses.iter().map(|r| r.author.as_str()).collect();
+    assert_eq!(authors, vec!["hubot", "octocat", AGENT_AUTHOR]);
+
+    // Commits that were never fetched are reported as such
+    let error = import_review(ImportReviewParams {
+        pull_request_path: unfetched_path.to_str().unwrap().to_string(),
+        comments_path: comments_path.to_string(),
+        repo_path: Some(repo_path.to_string()),
+        feedback_mode: FeedbackMode::Block,
+    })
+    .await
+    .unwrap_err();
+    assert!(error.to_string().contains("fetch them first"), "{}", error);
+
+    // When the base branch moved on, only the pull request's own changes are reviewed
+    let fork_point = repo.find_commit(repo.revparse_single("HEAD~1").unwrap().id()).unwrap();
+    let mut tree = repo.treebuilder(Some(&fork_point.tree().unwrap())).unwrap();
+    let readme = repo.blob(b"# Payments\n").unwrap();
+    tree.insert("README.md", readme, 0o100644).unwrap();
+    let tree = repo.find_tree(tree.write().unwrap()).unwrap();
+    let signature = git2::Signature::now("Base", "base@example.com").unwrap();
+    let moved_base = repo
+        .commit(None, &signature, &signature, "Add README", &tree, &[&fork_point])
+        .unwrap();
+    pull_request["base"]["sha"] = json!(moved_base.to_string());
+    std::fs::write(&pull_request_path, pull_request.to_string()).unwrap();
+
+    let review = import_review(ImportReviewParams {
+        pull_request_path: pull_request_path.to_str().unwrap().to_string(),
+        comments_path: comments_path.to_string(),
+        repo_path: Some(repo_path.to_string()),
+        feedback_mode: FeedbackMode::Block,
+    })
+    .await
+    .unwrap();
+    assert_eq!(
+        review.commit_range,
+        format!("{}..{}", sha("HEAD~1"), sha("HEAD"))
+    );
+    assert!(review.files_changed.iter().all(|f| f.path != "README.md"));
+}
+
 #[tokio::test]
 async fn test_server_info_includes_synthetic_pr_tools() {
     let server = DialecticServer::new_test();
@@ -569,6 +1561,22 @@ async fn test_server_info_includes_synthetic_pr_tools() {
         instructions.contains("get_review_status"),
         "Should mention get_review_status tool"
     );
+    assert!(
+        instructions.contains("list_reviews"),
+        "Should mention list_reviews tool"
+    );
+    assert!(
+        instructions.contains("poll_review_feedback"),
+        "Should mention poll_review_feedback tool"
+    );
+    assert!(
+        instructions.contains("strip_insight_comments"),
+        "Should mention strip_insight_comments tool"
+    );
+    assert!(
+        instructions.contains("apply_suggestion"),
+        "Should mention apply_suggestion tool"
+    );
     assert!(
         instructions.contains("synthetic pull requests"),
         "Should mention synthetic PR functionality"
@@ -579,3 +1587,767 @@ async fn test_server_info_includes_synthetic_pr_tools() {
         "Server should support tools"
     );
 }
+
+#[tokio::test]
+async fn test_comment_marker_config() {
+    // The config is read from the base commit of the review
+    let temp_dir = TestRepo::new()
+        .overwrite_and_add("README.md", "# Reports\n")
+        .overwrite_and_add(
+            COMMENT_CONFIG_FILE,
+            r#"{
+  "markers": [
+    { "marker": "NOTE:", "type": "explanation" },
+    { "marker": "SAFETY:", "type": "safety" }
+  ],
+  "languages": { "pgsql": { "line": ["--"], "block": [{ "start": "/*", "end": "*/" }] } }
+}
+"#,
+        )
+        .commit("Initial commit")
+        .overwrite_and_add(
+            "sql/report.pgsql",
+            r#"
+-- NOTE: Totals are computed in the database to keep the payload small
+SELECT customer_id, sum(amount) FROM payments GROUP BY customer_id;
+/* ❓ Should refunds be excluded here? */
+"#,
+        )
+        .overwrite_and_add(
+            "src/ffi.c",
+            r#"
+/* SAFETY: callers pass a buffer of at least len bytes */
+void fill(char *buf, int len);
+"#,
+        )
+        .commit("Add report query")
+        .create();
+    let repo_path = temp_dir.path().to_str().unwrap();
+
+    let review = harvest_review_data(RequestReviewParams {
+        commit_range: "HEAD~1..HEAD".to_string(),
+        title: "Configured markers".to_string(),
+        description: json!({}),
+        repo_path: Some(repo_path.to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let mut found: Vec<(String, u32, serde_json::Value, String)> = review
+        .comment_threads
+        .iter()
+        .map(|thread| {
+            (
+                thread.file_path.clone(),
+                thread.line_number,
+                serde_json::to_value(&thread.comment_type).unwrap(),
+                thread.content.clone(),
+            )
+        })
+        .collect();
+    found.sort_by(|a, b| (&a.0, a.1).cmp(&(&b.0, b.1)));
+    assert_eq!(
+        found,
+        [
+            (
+                "sql/report.pgsql".to_string(),
+                2,
+                json!("Explanation"),
+                "Totals are computed in the database to keep the payload small".to_string()
+            ),
+            (
+                "sql/report.pgsql".to_string(),
+                4,
+                json!("Question"),
+                "Should refunds be excluded here?".to_string()
+            ),
+            (
+                "src/ffi.c".to_string(),
+                2,
+                json!("safety"),
+                "callers pass a buffer of at least len bytes".to_string()
+            ),
+        ]
+    );
+
+    // Changing the config in the working tree doesn't change how the review is parsed,
+    // so the changes under review can't hide a new FIXME
+    std::fs::write(
+        temp_dir.path().join(COMMENT_CONFIG_FILE),
+        r#"{ "replace_default_markers": true, "markers": [{ "marker": "FIXME:", "type": "explanation" }] }"#,
+    )
+    .unwrap();
+    std::fs::write(
+        temp_dir.path().join("src/ffi.c"),
+        "/* FIXME: len is never checked */\nvoid fill(char *buf, int len);\n",
+    )
+    .unwrap();
+    let review = harvest_review_data(RequestReviewParams {
+        commit_range: "HEAD~1".to_string(),
+        title: "Hidden FIXME".to_string(),
+        description: json!({}),
+        repo_path: Some(repo_path.to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let fixme = review
+        .comment_threads
+        .iter()
+        .find(|thread| thread.file_path == "src/ffi.c")
+        .unwrap();
+    assert!(matches!(fixme.comment_type, CommentType::Fixme));
+    assert_eq!(fixme.marker_status, Some(MarkerStatus::Introduced));
+    assert!(
+        review
+            .comment_threads
+            .iter()
+            .any(|thread| matches!(thread.comment_type, CommentType::Question))
+    );
+
+    // An invalid config file is reported instead of silently ignored
+    std::fs::write(temp_dir.path().join(COMMENT_CONFIG_FILE), "{ not json").unwrap();
+    let output = std::process::Command::new("git")
+        .args(["commit", "-qam", "Break the marker config"])
+        .current_dir(repo_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let error = harvest_review_data(RequestReviewParams {
+        commit_range: "HEAD".to_string(),
+        title: "Broken config".to_string(),
+        description: json!({}),
+        repo_path: Some(repo_path.to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap_err();
+    assert!(error.to_string().contains("Invalid comment marker config"));
+}
+
+#[tokio::test]
+async fn test_multi_line_insight_comments() {
+    let temp_dir = TestRepo::new()
+        .overwrite_and_add("src/retry.rs", "pub fn retry() {}\n")
+        .commit("Initial commit")
+        .overwrite(
+            "src/retry.rs",
+            r#"pub fn retry() {
+    // 💡 Exponential backoff, so a flapping dependency
+    // isn't hammered while it recovers.
+    backoff();
+}
+
+/* ❓ Should the delay be capped?
+ * Retries could otherwise wait for minutes. */
+fn backoff() {}
+"#,
+        )
+        .create();
+    let repo_path = temp_dir.path().to_str().unwrap();
+
+    let review = harvest_review_data(RequestReviewParams {
+        commit_range: "HEAD".to_string(),
+        title: "Retries".to_string(),
+        description: json!({}),
+        repo_path: Some(repo_path.to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let ranges = |threads: &[CommentThread]| {
+        let mut ranges: Vec<_> = threads
+            .iter()
+            .map(|t| (t.line_range(), t.content.clone()))
+            .collect();
+        ranges.sort_by_key(|(range, _)| *range.start());
+        ranges
+    };
+    assert_eq!(
+        ranges(&review.comment_threads),
+        [
+            (
+                2..=3,
+                "Exponential backoff, so a flapping dependency\nisn't hammered while it recovers."
+                    .to_string()
+            ),
+            (
+                7..=8,
+                "Should the delay be capped?\nRetries could otherwise wait for minutes.".to_string()
+            ),
+        ]
+    );
+
+    // Code added above moves each comment as a whole
+    let file = temp_dir.path().join("src/retry.rs");
+    let content = std::fs::read_to_string(&file).unwrap();
+    std::fs::write(&file, format!("use std::time::Duration;\n\n{}", content)).unwrap();
+
+    let updated = update_review(UpdateReviewParams {
+        review_id: review.review_id.clone(),
+        action: UpdateReviewAction::WaitForFeedback,
+        repo_path: Some(repo_path.to_string()),
+        feedback_mode: FeedbackMode::Block,
+    })
+    .await
+    .unwrap();
+    let moved = ranges(&updated.review.comment_threads);
+    assert_eq!(moved.len(), 2);
+    assert_eq!(moved[0].0, 4..=5);
+    assert_eq!(moved[1].0, 9..=10);
+}
+
+#[tokio::test]
+async fn test_resolved_and_pre_existing_markers() {
+    let temp_dir = TestRepo::new()
+        .overwrite_and_add(
+            "src/queue.rs",
+            "pub fn pop() {\n    // TODO: Bound the queue\n    // FIXME: Returns stale items\n    take();\n}\n",
+        )
+        .commit("Initial commit")
+        .overwrite(
+            "src/queue.rs",
+            "pub fn pop() {\n    // TODO: Bound the queue\n    // 💡 Items are checked for staleness on the way out\n    take_fresh();\n}\n",
+        )
+        .create();
+    let repo_path = temp_dir.path().to_str().unwrap();
+
+    let review = harvest_review_data(RequestReviewParams {
+        commit_range: "HEAD".to_string(),
+        title: "Fix stale items".to_string(),
+        description: json!({}),
+        repo_path: Some(repo_path.to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let status_of = |threads: &[CommentThread], content: &str| {
+        let thread = threads.iter().find(|t| t.content == content).unwrap();
+        (thread.marker_status, thread.outdated)
+    };
+    let threads = &review.comment_threads;
+    assert_eq!(threads.len(), 3);
+    assert_eq!(
+        status_of(threads, "Bound the queue"),
+        (Some(MarkerStatus::PreExisting), false)
+    );
+    assert_eq!(
+        status_of(threads, "Returns stale items"),
+        (Some(MarkerStatus::Resolved), false)
+    );
+    assert_eq!(
+        status_of(threads, "Items are checked for staleness on the way out"),
+        (Some(MarkerStatus::Introduced), false)
+    );
+
+    let markdown = export_review(ExportReviewParams {
+        review_id: Some(review.review_id.clone()),
+        format: ExportFormat::Markdown,
+        repo_path: Some(repo_path.to_string()),
+    })
+    .await
+    .unwrap();
+    assert!(markdown.contains("**FIXME** on `src/queue.rs:3` (resolved)"));
+    assert!(markdown.contains("**TODO** on `src/queue.rs:2` (pre-existing)"));
+
+    // Restoring the FIXME makes its resolved thread outdated, and refreshing again
+    // doesn't duplicate the remaining threads
+    std::fs::write(
+        temp_dir.path().join("src/queue.rs"),
+        "pub fn pop() {\n    // TODO: Bound the queue\n    // FIXME: Returns stale items\n    take_fresh();\n}\n",
+    )
+    .unwrap();
+    let update = || {
+        update_review(UpdateReviewParams {
+            review_id: review.review_id.clone(),
+            action: UpdateReviewAction::WaitForFeedback,
+            repo_path: Some(repo_path.to_string()),
+            feedback_mode: FeedbackMode::Block,
+        })
+    };
+    update().await.unwrap();
+    let updated = update().await.unwrap();
+    let threads = &updated.review.comment_threads;
+    assert_eq!(
+        status_of(threads, "Returns stale items"),
+        (Some(MarkerStatus::Resolved), true)
+    );
+    assert_eq!(
+        threads
+            .iter()
+            .filter(|t| t.content == "Bound the queue")
+            .count(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_strip_insight_comments_before_commit() {
+    let temp_dir = TestRepo::new()
+        .overwrite_and_add("src/cache.rs", "pub fn load() {}\n")
+        .commit("Initial commit")
+        .overwrite(
+            "src/cache.rs",
+            r#"pub fn load() -> Value {
+    // 💡 Entries expire after a minute
+    // so stale prices are never served.
+    let ttl = 60; // ❓ Is a minute enough?
+    // ❓ Should misses be logged?
+    // TODO: Warm the cache on startup
+    fetch(ttl)
+}
+"#,
+        )
+        .create();
+    let repo_path = temp_dir.path().to_str().unwrap();
+
+    let review = harvest_review_data(RequestReviewParams {
+        commit_range: "HEAD".to_string(),
+        title: "Price cache".to_string(),
+        description: json!({}),
+        repo_path: Some(repo_path.to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    assert_eq!(review.comment_threads.len(), 4);
+
+    let ttl_question = review
+        .comment_threads
+        .iter()
+        .find(|t| t.content == "Is a minute enough?")
+        .unwrap();
+    let answer = UserFeedback {
+        review_id: review.review_id.clone(),
+        feedback: FeedbackData::Comment {
+            thread_id: Some(ttl_question.thread_id.clone()),
+            file_path: None,
+            line_number: None,
+            comment_text: "Yes, prices change hourly".to_string(),
+            context_lines: None,
+        },
+    };
+    record_user_feedback(Some(repo_path), &answer).unwrap();
+
+    let strip = |apply: bool| StripInsightCommentsParams {
+        review_id: Some(review.review_id.clone()),
+        options: StripOptions {
+            keep_answered_questions: true,
+            ..Default::default()
+        },
+        apply,
+        repo_path: Some(repo_path.to_string()),
+    };
+
+    // A dry run only reports the edits
+    let file = temp_dir.path().join("src/cache.rs");
+    let before = std::fs::read_to_string(&file).unwrap();
+    let preview = strip_insight_comments(strip(false)).await.unwrap();
+    assert!(!preview.applied);
+    assert_eq!(preview.files_changed, ["src/cache.rs"]);
+    assert_eq!(preview.removed.len(), 2);
+    assert_eq!(preview.converted, std::slice::from_ref(&ttl_question.thread_id));
+    assert!(preview.diff.contains("-    // 💡 Entries expire after a minute"));
+    assert!(preview.diff.contains("+    // user: Yes, prices change hourly"));
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), before);
+
+    let applied = strip_insight_comments(strip(true)).await.unwrap();
+    assert!(applied.applied);
+    assert_eq!(applied.diff, preview.diff);
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        r#"pub fn load() -> Value {
+    // Is a minute enough?
+    // user: Yes, prices change hourly
+    let ttl = 60;
+    // TODO: Warm the cache on startup
+    fetch(ttl)
+}
+"#
+    );
+
+    // Once stripped, there is nothing left to remove
+    let again = strip_insight_comments(strip(false)).await.unwrap();
+    assert!(again.diff.is_empty());
+    assert_eq!(again.not_found.len(), 3);
+}
+
+#[tokio::test]
+async fn test_apply_suggested_change() {
+    let temp_dir = TestRepo::new()
+        .overwrite_and_add("src/retry.rs", "pub fn retry() {}\n")
+        .commit("Initial commit")
+        .overwrite_and_add(
+            "src/retry.rs",
+            "pub fn retry() {\n    let attempts = 3;\n    let delay = 100;\n    run(attempts, delay)\n}\n",
+        )
+        .commit("Retry with a delay")
+        .create();
+    let repo_path = temp_dir.path().to_str().unwrap();
+
+    let review = harvest_review_data(RequestReviewParams {
+        commit_range: "HEAD".to_string(),
+        title: "Retries".to_string(),
+        description: json!({}),
+        repo_path: Some(repo_path.to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let suggest = |line_number: u32, body: &str| UserFeedback {
+        review_id: review.review_id.clone(),
+        feedback: FeedbackData::Comment {
+            thread_id: None,
+            file_path: Some("src/retry.rs".to_string()),
+            line_number: Some(line_number),
+            comment_text: body.to_string(),
+            context_lines: None,
+        },
+    };
+    let thread_id = record_user_feedback(
+        Some(repo_path),
+        &suggest(
+            2,
+            "Make it configurable:\n```suggestion\n    let attempts = config.attempts;\n```",
+        ),
+    )
+    .unwrap()
+    .unwrap();
+    let stale_thread_id = record_user_feedback(
+        Some(repo_path),
+        &suggest(3, "```suggestion\n    let delay = config.delay;\n```"),
+    )
+    .unwrap()
+    .unwrap();
+
+    let stored = load_review_data(Some(repo_path), &review.review_id).unwrap();
+    assert_eq!(stored.suggestions.len(), 2);
+    assert_eq!(stored.suggestions[0].start_line, 2);
+    assert_eq!(stored.suggestions[0].original_lines, ["    let attempts = 3;"]);
+
+    let apply = |thread_id: &str| ApplySuggestionParams {
+        review_id: review.review_id.clone(),
+        suggestion_id: None,
+        thread_id: Some(thread_id.to_string()),
+        repo_path: Some(repo_path.to_string()),
+    };
+    let applied = apply_suggestion(apply(&thread_id)).await.unwrap();
+    assert_eq!((applied.start_line, applied.end_line), (2, 2));
+    assert!(applied.diff.contains("+    let attempts = config.attempts;"));
+
+    // The delay line was edited after it was commented on: nothing is written
+    let file = temp_dir.path().join("src/retry.rs");
+    let edited = std::fs::read_to_string(&file)
+        .unwrap()
+        .replace("delay = 100", "delay = 250");
+    std::fs::write(&file, &edited).unwrap();
+    let error = apply_suggestion(apply(&stale_thread_id)).await.unwrap_err();
+    assert!(error.to_string().contains("changed since the suggestion was made"));
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), edited);
+    assert_eq!(
+        edited,
+        "pub fn retry() {\n    let attempts = config.attempts;\n    let delay = 250;\n    run(attempts, delay)\n}\n"
+    );
+
+    // Applying is recorded, and a suggestion is applied only once
+    assert!(apply_suggestion(apply(&thread_id)).await.is_err());
+    let stored = load_review_data(Some(repo_path), &review.review_id).unwrap();
+    assert!(stored.suggestions[0].applied);
+    assert!(!stored.suggestions[1].applied);
+}
+
+#[tokio::test]
+async fn test_suggestion_follows_code_changed_after_review() {
+    let temp_dir = TestRepo::new()
+        .overwrite_and_add("src/retry.rs", "pub fn retry() {}\n")
+        .commit("Initial commit")
+        .overwrite(
+            "src/retry.rs",
+            "pub fn retry() {\n    let attempts = 3;\n    run(attempts)\n}\n",
+        )
+        .create();
+    let repo_path = temp_dir.path().to_str().unwrap();
+
+    let review = harvest_review_data(RequestReviewParams {
+        commit_range: "HEAD".to_string(),
+        title: "Retries".to_string(),
+        description: json!({}),
+        repo_path: Some(repo_path.to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    // The agent keeps working after the review was shown, moving the reviewed line down
+    let file = temp_dir.path().join("src/retry.rs");
+    std::fs::write(
+        &file,
+        "pub fn retry() {\n    log(\"retrying\");\n    let attempts = 3;\n    run(attempts)\n}\n",
+    )
+    .unwrap();
+
+    // The user comments on line 2 of the code they reviewed
+    let thread_id = record_user_feedback(
+        Some(repo_path),
+        &UserFeedback {
+            review_id: review.review_id.clone(),
+            feedback: FeedbackData::Comment {
+                thread_id: None,
+                file_path: Some("src/retry.rs".to_string()),
+                line_number: Some(2),
+                comment_text: "```suggestion\n    let attempts = config.attempts;\n```".to_string(),
+                context_lines: None,
+            },
+        },
+    )
+    .unwrap()
+    .unwrap();
+    let stored = load_review_data(Some(repo_path), &review.review_id).unwrap();
+    assert_eq!(stored.suggestions[0].original_lines, ["    let attempts = 3;"]);
+
+    // Applying finds the line where it is now
+    let applied = apply_suggestion(ApplySuggestionParams {
+        review_id: review.review_id.clone(),
+        suggestion_id: None,
+        thread_id: Some(thread_id),
+        repo_path: Some(repo_path.to_string()),
+    })
+    .await
+    .unwrap();
+    assert_eq!((applied.start_line, applied.end_line), (3, 3));
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        "pub fn retry() {\n    log(\"retrying\");\n    let attempts = config.attempts;\n    run(attempts)\n}\n"
+    );
+}
+
+#[tokio::test]
+async fn test_review_policy_blocks_approval() {
+    let temp_dir = TestRepo::new()
+        .overwrite_and_add("src/cache.rs", "pub fn load() {}\n")
+        .overwrite_and_add(
+            REVIEW_POLICY_FILE,
+            r#"{ "questions_answered": true, "forbid_introduced": ["fixme"] }"#,
+        )
+        .commit("Initial commit")
+        .overwrite(
+            "src/cache.rs",
+            "pub fn load() {\n    let ttl = 60; // ❓ Is a minute enough?\n    fetch(ttl)\n}\n",
+        )
+        .create();
+    let repo_path = temp_dir.path().to_str().unwrap();
+
+    let review = harvest_review_data(RequestReviewParams {
+        commit_range: "HEAD".to_string(),
+        title: "Price cache".to_string(),
+        description: json!({}),
+        repo_path: Some(repo_path.to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let approve = || UpdateReviewParams {
+        review_id: review.review_id.clone(),
+        action: UpdateReviewAction::Approve,
+        repo_path: Some(repo_path.to_string()),
+        feedback_mode: FeedbackMode::Block,
+    };
+
+    let error = update_review(approve()).await.unwrap_err().to_string();
+    assert!(error.starts_with("Cannot approve: the review policy is not met."));
+    assert!(error.contains("unanswered question at `src/cache.rs:2` \"Is a minute enough?\""));
+
+    // The policy is read from the base commit: relaxing it in the working tree changes nothing
+    std::fs::write(temp_dir.path().join(REVIEW_POLICY_FILE), "{}").unwrap();
+    assert!(update_review(approve()).await.is_err());
+
+    let status = get_review_status(Some(repo_path), Some(&review.review_id))
+        .await
+        .unwrap();
+    assert_eq!(status.status, "Pending");
+    let policy = status.policy.unwrap();
+    assert!(!policy.passed);
+    let failed: Vec<_> = policy
+        .checks
+        .iter()
+        .filter(|check| !check.passed)
+        .map(|check| check.rule.as_str())
+        .collect();
+    assert_eq!(failed, ["questions_answered"]);
+
+    // Once the user answers, the agent may approve
+    let question = &review.comment_threads[0];
+    record_user_feedback(
+        Some(repo_path),
+        &UserFeedback {
+            review_id: review.review_id.clone(),
+            feedback: FeedbackData::Comment {
+                thread_id: Some(question.thread_id.clone()),
+                file_path: None,
+                line_number: None,
+                comment_text: "Yes".to_string(),
+                context_lines: None,
+            },
+        },
+    )
+    .unwrap();
+    let approved = update_review(approve()).await.unwrap();
+    assert_eq!(approved.status, "approved");
+    let status = get_review_status(Some(repo_path), Some(&review.review_id))
+        .await
+        .unwrap();
+    assert!(status.policy.unwrap().passed);
+}
+
+#[tokio::test]
+async fn test_files_ranked_for_review() {
+    let temp_dir = TestRepo::new()
+        .overwrite_and_add("Cargo.lock", "# lock\n")
+        .overwrite_and_add("README.md", "# Cache\n")
+        .overwrite_and_add("src/cache.rs", "pub fn load() {}\n")
+        .overwrite_and_add("src/util.rs", "\n")
+        .commit("Initial commit")
+        .overwrite("Cargo.lock", &"[[package]]\n".repeat(40))
+        .overwrite("README.md", "# Cache\n\nLoads things, once.\n")
+        .overwrite(
+            "src/cache.rs",
+            "pub fn load() {\n    // ❓ Should this be cached?\n    fetch()\n}\n",
+        )
+        .overwrite("src/util.rs", "pub fn fetch() {}\n")
+        .create();
+    let repo_path = temp_dir.path().to_str().unwrap();
+
+    let review = harvest_review_data(RequestReviewParams {
+        commit_range: "HEAD".to_string(),
+        title: "Caching".to_string(),
+        description: json!({}),
+        repo_path: Some(repo_path.to_string()),
+        group_files: true,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    // Source with an open question first, the lock file last despite its size
+    let order = |review: &ReviewData| -> Vec<String> {
+        review
+            .file_ranking
+            .files
+            .iter()
+            .map(|file| file.path.clone())
+            .collect()
+    };
+    assert_eq!(
+        order(&review),
+        vec!["src/cache.rs", "src/util.rs", "README.md", "Cargo.lock"]
+    );
+    assert!(
+        review.file_ranking.files[0]
+            .reasons
+            .contains(&"1 open ❓ question (+10)".to_string())
+    );
+    let titles: Vec<&str> = review
+        .file_ranking
+        .sections
+        .iter()
+        .map(|section| section.title.as_str())
+        .collect();
+    assert_eq!(titles, vec!["Source", "Documentation", "Generated files"]);
+
+    // References found by the IDE (absolute paths included) move the used file up
+    let referenced_by = std::collections::BTreeMap::from([(
+        "src/util.rs".to_string(),
+        vec![
+            temp_dir.path().join("src/cache.rs").display().to_string(),
+            "src/main.rs".to_string(),
+            "src/lib.rs".to_string(),
+            "src/config.rs".to_string(),
+            "src/db.rs".to_string(),
+            "src/util.rs".to_string(),
+            "src/main.rs".to_string(),
+        ],
+    )]);
+    let ranked = record_file_references(Some(repo_path), &review.review_id, referenced_by).unwrap();
+    let util = &ranked.file_ranking.files[0];
+    assert_eq!(util.path, "src/util.rs");
+    assert_eq!(
+        util.reasons.last().unwrap(),
+        "referenced from 5 other files (+15)"
+    );
+
+    // The references are kept with the review
+    let reloaded = load_review_data(Some(repo_path), &review.review_id).unwrap();
+    assert_eq!(order(&reloaded), order(&ranked));
+}
+
+#[tokio::test]
+async fn test_tests_paired_with_sources() {
+    let temp_dir = TestRepo::new()
+        .overwrite_and_add(
+            "src/cache.rs",
+            "pub fn load(key: &str) -> Option<String> {\n    None\n}\n",
+        )
+        .overwrite_and_add("src/db.rs", "pub fn connect() {}\n")
+        .overwrite_and_add("tests/cache_test.rs", "#[test]\nfn misses() {}\n")
+        .commit("Initial commit")
+        .overwrite(
+            "src/cache.rs",
+            "pub fn load(key: &str) -> Option<String> {\n    lookup(key)\n}\n\nfn lookup(key: &str) -> Option<String> {\n    None\n}\n",
+        )
+        .overwrite("src/db.rs", "pub fn connect() {\n    // pooled\n}\n")
+        .overwrite(
+            "tests/cache_test.rs",
+            "use app::cache::load;\n\n#[test]\nfn misses() {\n    assert!(load(\"key\").is_none());\n}\n",
+        )
+        .create();
+    let repo_path = temp_dir.path().to_str().unwrap();
+
+    let review = harvest_review_data(RequestReviewParams {
+        commit_range: "HEAD".to_string(),
+        title: "Cache lookups".to_string(),
+        description: json!({}),
+        repo_path: Some(repo_path.to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let file = |path: &str| {
+        review
+            .files_changed
+            .iter()
+            .find(|file| file.path == path)
+            .unwrap()
+    };
+
+    let link = |file: &str, symbols: &[&str]| TestLink {
+        file: file.to_string(),
+        symbols: symbols.iter().map(|s| s.to_string()).collect(),
+    };
+    // The test calls `load`, whose body changed; nothing refers to the db change
+    assert_eq!(
+        file("src/cache.rs").test_links,
+        vec![link("tests/cache_test.rs", &["load"])]
+    );
+    assert_eq!(
+        file("tests/cache_test.rs").test_links,
+        vec![link("src/cache.rs", &["load"])]
+    );
+    assert!(file("src/db.rs").test_links.is_empty());
+
+    // References the IDE found link tests that don't name a changed symbol
+    let references = std::collections::BTreeMap::from([(
+        "src/db.rs".to_string(),
+        vec!["tests/cache_test.rs".to_string()],
+    )]);
+    let linked = record_file_references(Some(repo_path), &review.review_id, references).unwrap();
+    let db = linked
+        .files_changed
+        .iter()
+        .find(|file| file.path == "src/db.rs")
+        .unwrap();
+    assert_eq!(db.test_links, vec![link("tests/cache_test.rs", &[])]);
+}